@@ -0,0 +1,36 @@
+use anyhow::Error;
+use pars_core::autotype::{
+    autotype, detect_backend, fields_sequence, parse_sequence, DEFAULT_SEQUENCE,
+};
+use pars_core::config::cli::ParsConfig;
+
+use crate::constants::ParsExitCode;
+use crate::fuzzy::fuzzy_resolve;
+use crate::util::{decrypt_entry, unwrap_pass_path};
+
+/// Type fields of `pass_name` into the focused window, `--field a,b` types them separated
+/// by tab, `--sequence` gives the keystrokes in full
+pub fn cmd_type(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    pass_name: &str,
+    fields: &[String],
+    sequence: Option<&str>,
+) -> Result<(), (i32, Error)> {
+    let sequence = match sequence {
+        Some(sequence) => sequence.to_string(),
+        None if !fields.is_empty() => fields_sequence(fields),
+        None => DEFAULT_SEQUENCE.to_string(),
+    };
+    // Checked before decrypting, a typo should not need the passphrase
+    let sequence = parse_sequence(&sequence).map_err(|e| (ParsExitCode::InvalidArgs.into(), e))?;
+    let mut backend = detect_backend().map_err(|e| (ParsExitCode::CommandNotFound.into(), e))?;
+
+    let (root, pass_name) = unwrap_pass_path(base_dir, config, pass_name);
+    let pass_name =
+        &*fuzzy_resolve(config, &root, pass_name).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let content =
+        decrypt_entry(config, &root, pass_name).map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+
+    autotype(backend.as_mut(), &sequence, &content).map_err(|e| (ParsExitCode::Error.into(), e))
+}
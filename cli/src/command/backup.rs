@@ -0,0 +1,86 @@
+use std::fs;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use anyhow::{anyhow, Error};
+use pars_core::config::cli::ParsConfig;
+use pars_core::operation::backup::{
+    create_backup, create_encrypted_backup, read_backup, restore_backup,
+};
+use pars_core::pgp::PGPClient;
+use pars_core::util::fs_util::create_private_file;
+
+use crate::constants::ParsExitCode;
+use crate::util::{offer_key_import, unwrap_root_path};
+
+pub fn cmd_backup_create(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    output: &str,
+    recipient: Option<&str>,
+    include_git: bool,
+    force: bool,
+) -> Result<(), (i32, Error)> {
+    if output != "-" && Path::new(output).exists() && !force {
+        return Err((
+            ParsExitCode::Error.into(),
+            anyhow!("'{output}' already exists, --force replaces it"),
+        ));
+    }
+
+    let root = unwrap_root_path(base_dir, config);
+    if !root.is_dir() {
+        return Err((ParsExitCode::Error.into(), anyhow!("No password store at {root:?}")));
+    }
+    let summary = if let Some(recipient) = recipient {
+        let client = PGPClient::new(&config.executable_config.pgp_executable, &[recipient])
+            .map_err(|e| (ParsExitCode::PGPError.into(), e))?
+            .with_options(&config.executable_config.pgp_options);
+        if output != "-" && force {
+            let _ = fs::remove_file(output);
+        }
+        create_encrypted_backup(&client, &root, include_git, output)
+            .map_err(|e| (ParsExitCode::PGPError.into(), e))?
+    } else if output == "-" {
+        create_backup(&root, include_git, io::stdout().lock())
+            .map_err(|e| (ParsExitCode::Error.into(), e))?
+    } else {
+        let file = create_private_file(Path::new(output), force)
+            .map_err(|e| (ParsExitCode::Error.into(), e))?;
+        create_backup(&root, include_git, BufWriter::new(file)).map_err(|e| {
+            // A partial archive is no backup
+            let _ = fs::remove_file(output);
+            (ParsExitCode::Error.into(), e)
+        })?
+    };
+
+    let encrypted = recipient.map(|r| format!(", encrypted for {r}")).unwrap_or_default();
+    eprintln!(
+        "Backed up {} entries ({} files) of {root:?}{encrypted}",
+        summary.entries, summary.files
+    );
+    Ok(())
+}
+
+pub fn cmd_backup_restore(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    file: &str,
+) -> Result<(), (i32, Error)> {
+    let executable_config = &config.executable_config;
+    let backup = read_backup(
+        &executable_config.pgp_executable,
+        &executable_config.pgp_options,
+        Path::new(file),
+    )
+    .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    let root = unwrap_root_path(base_dir, config);
+    let summary =
+        restore_backup(backup.path(), &root).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    println!(
+        "Verified and restored {} entries ({} files) into {root:?}",
+        summary.entries, summary.files
+    );
+    offer_key_import(config, &root);
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use std::io::{self, Read};
+
+use anyhow::{Error, Result};
+use pars_core::clipboard::copy_to_clipboard;
+use pars_core::config::cli::ParsConfig;
+use secrecy::SecretString;
+
+use crate::constants::ParsExitCode;
+
+/// Copy stdin to the clipboard, cleared after `clip_time` like `show -c`. This is what the
+/// `clip` helper of pass extensions calls.
+pub fn cmd_clip(config: &ParsConfig, name: Option<&str>) -> Result<(), (i32, Error)> {
+    let mut content = String::new();
+    io::stdin().read_to_string(&mut content).map_err(|e| (ParsExitCode::Error.into(), e.into()))?;
+    copy_to_clipboard(
+        SecretString::from(content),
+        &config.feature_config.clip_time,
+        &config.feature_config.x_selection,
+    )
+    .map_err(|e| (ParsExitCode::Error.into(), e))?;
+
+    let name = name.unwrap_or("the content");
+    match config.feature_config.clip_time {
+        Some(secs) => println!("Copied {name} to clipboard. Will clear in {secs} seconds."),
+        None => println!("Copied {name} to clipboard."),
+    }
+    Ok(())
+}
@@ -0,0 +1,226 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::{env, fs};
+
+use anyhow::{anyhow, Error, Result};
+use pars_core::config::cli::{config_to_string, save_config, ParsConfig};
+use pars_core::config::keys::{
+    check_config, get_key, set_key_in_toml, unset_key_in_toml, validate_file, Value,
+};
+use pars_core::config::layered::{Layer, LayeredConfig};
+use pars_core::util::defer::Defer;
+use pars_core::util::fs_util::system_config_path;
+
+use crate::constants::ParsExitCode;
+use crate::util::confirm;
+
+const MAX_ALIGN_WIDTH: usize = 60;
+
+pub fn cmd_config_show(layered: &LayeredConfig, origin: bool) -> Result<(), (i32, Error)> {
+    if !origin {
+        let content = config_to_string(&layered.config)
+            .map_err(|e| (ParsExitCode::Error.into(), anyhow!("{e}")))?;
+        print!("{content}");
+        return Ok(());
+    }
+
+    let entries = layered.entries().map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let lines: Vec<_> =
+        entries.iter().map(|(key, value, origin)| (format!("{key} = {value}"), origin)).collect();
+    // Long lists would push every origin far to the right
+    let width = lines
+        .iter()
+        .map(|(line, _)| line.chars().count())
+        .filter(|len| *len <= MAX_ALIGN_WIDTH)
+        .max()
+        .unwrap_or_default();
+    for (line, origin) in lines {
+        println!("{line:<width$}  # {origin}");
+    }
+    Ok(())
+}
+
+pub fn cmd_config_get(layered: &LayeredConfig, key: &str) -> Result<(), (i32, Error)> {
+    match get_key(&layered.config, key).map_err(|e| (ParsExitCode::Error.into(), e))? {
+        Some(Value::String(value)) => println!("{value}"),
+        Some(Value::Table(_)) => {
+            let prefix = format!("{key}.");
+            let entries = layered.entries().map_err(|e| (ParsExitCode::Error.into(), e))?;
+            for (entry_key, value, _) in entries {
+                if entry_key.starts_with(&prefix) {
+                    println!("{entry_key} = {value}");
+                }
+            }
+        }
+        Some(value) => println!("{value}"),
+        None => return Err((ParsExitCode::Error.into(), anyhow!("'{key}' is not set"))),
+    }
+    Ok(())
+}
+
+/// Set `key` in the user's config file, the value is checked before anything is written. The
+/// other keys of the file are left as they are.
+pub fn cmd_config_set(user_path: &Path, key: &str, value: &str) -> Result<(), (i32, Error)> {
+    let content = read_user_config(user_path)?;
+    let (content, config) =
+        set_key_in_toml(&content, key, value).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    if let Some(problem) = check_config(&config).into_iter().find(|problem| problem.key == key) {
+        return Err((ParsExitCode::Error.into(), anyhow!("{key}: {}", problem.message)));
+    }
+    write_user_config(&content, user_path)
+}
+
+pub fn cmd_config_unset(user_path: &Path, key: &str) -> Result<(), (i32, Error)> {
+    let content = read_user_config(user_path)?;
+    let content = unset_key_in_toml(&content, key).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    write_user_config(&content, user_path)
+}
+
+/// Edit a copy of the user's config file, which replaces it once it can be loaded
+pub fn cmd_config_edit(config: &ParsConfig, user_path: &Path) -> Result<(), (i32, Error)> {
+    if !user_path.exists() {
+        write_config(&ParsConfig::default(), user_path)?;
+    }
+    let editor =
+        env::var("PARS_EDITOR").unwrap_or(config.executable_config.editor_executable.clone());
+
+    let draft = draft_path(user_path);
+    fs::copy(user_path, &draft).map_err(|e| (ParsExitCode::Error.into(), e.into()))?;
+    let _cleaner = Defer::new(|| {
+        let _ = fs::remove_file(&draft);
+    });
+
+    loop {
+        let status = Command::new(&editor)
+            .arg(&draft)
+            .status()
+            .map_err(|e| (ParsExitCode::Error.into(), anyhow!("Failed to run '{editor}': {e}")))?;
+        if !status.success() {
+            return Err((ParsExitCode::Error.into(), anyhow!("Failed to edit the config")));
+        }
+
+        match validate_file(&draft) {
+            Ok(problems) => {
+                for problem in problems {
+                    eprintln!("Warning: {}: {}", problem.key, problem.message);
+                }
+                fs::rename(&draft, user_path)
+                    .map_err(|e| (ParsExitCode::Error.into(), e.into()))?;
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{e}");
+                if !confirm("Edit again?") {
+                    return Err((ParsExitCode::Error.into(), anyhow!("Changes discarded")));
+                }
+            }
+        }
+    }
+}
+
+/// Check `file`, or every config file in use, failing when one has a problem
+pub fn cmd_config_validate(
+    layered: &LayeredConfig,
+    file: Option<&str>,
+) -> Result<(), (i32, Error)> {
+    let paths: Vec<PathBuf> = match file {
+        Some(file) => vec![file.into()],
+        None => {
+            let repo_files = layered
+                .files
+                .iter()
+                .filter(|(layer, _)| *layer == Layer::Repo)
+                .map(|(_, path)| path.clone());
+            [system_config_path(), layered.user_path.clone()]
+                .into_iter()
+                .filter(|path| path.is_file())
+                .chain(repo_files)
+                .collect()
+        }
+    };
+    if paths.is_empty() {
+        println!("No config file, the defaults are used");
+        return Ok(());
+    }
+
+    let mut failed = false;
+    for path in paths {
+        match validate_file(&path) {
+            Ok(problems) if problems.is_empty() => println!("{}: ok", path.display()),
+            Ok(problems) => {
+                failed = true;
+                for problem in problems {
+                    println!("{}: {}: {}", path.display(), problem.key, problem.message);
+                }
+            }
+            Err(e) => {
+                failed = true;
+                println!("{}: {e}", path.display());
+            }
+        }
+    }
+    if failed {
+        Err((ParsExitCode::Error.into(), anyhow!("The config has problems")))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn cmd_config_init(user_path: &Path, force: bool) -> Result<(), (i32, Error)> {
+    if user_path.exists() && !force {
+        return Err((
+            ParsExitCode::Error.into(),
+            anyhow!("'{}' exists, use --force to overwrite it", user_path.display()),
+        ));
+    }
+    write_config(&ParsConfig::default(), user_path)?;
+    println!("Config written to '{}'", user_path.display());
+    Ok(())
+}
+
+/// Text of the user's config file, empty when there is none yet
+fn read_user_config(user_path: &Path) -> Result<String, (i32, Error)> {
+    if !user_path.exists() {
+        return Ok(String::new());
+    }
+    fs::read_to_string(user_path).map_err(|e| {
+        (ParsExitCode::Error.into(), anyhow!("Failed to load '{}': {e}", user_path.display()))
+    })
+}
+
+/// Write `content` next to the user's config file and rename it over, so an interrupted write
+/// never leaves a truncated config behind
+fn write_user_config(content: &str, user_path: &Path) -> Result<(), (i32, Error)> {
+    if let Some(parent) = user_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| (ParsExitCode::Error.into(), e.into()))?;
+    }
+    let temp = temp_path(user_path);
+    let _cleaner = Defer::new(|| {
+        let _ = fs::remove_file(&temp);
+    });
+    fs::write(&temp, content).map_err(|e| (ParsExitCode::Error.into(), e.into()))?;
+    if let Ok(metadata) = fs::metadata(user_path) {
+        fs::set_permissions(&temp, metadata.permissions())
+            .map_err(|e| (ParsExitCode::Error.into(), e.into()))?;
+    }
+    fs::rename(&temp, user_path).map_err(|e| (ParsExitCode::Error.into(), e.into()))
+}
+
+fn write_config(config: &ParsConfig, user_path: &Path) -> Result<(), (i32, Error)> {
+    if let Some(parent) = user_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| (ParsExitCode::Error.into(), e.into()))?;
+    }
+    save_config(config, user_path).map_err(|e| (ParsExitCode::Error.into(), anyhow!("{e}")))
+}
+
+/// `config.toml` is edited as `config.edit.toml`, next to it so the rename stays on one disk
+fn draft_path(user_path: &Path) -> PathBuf {
+    let stem = user_path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    user_path.with_file_name(format!("{stem}.edit.toml"))
+}
+
+/// `config.toml` is written as `config.tmp.toml` first, apart from a draft being edited
+fn temp_path(user_path: &Path) -> PathBuf {
+    let stem = user_path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    user_path.with_file_name(format!("{stem}.tmp.toml"))
+}
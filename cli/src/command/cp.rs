@@ -1,15 +1,18 @@
 use std::io::BufReader;
+use std::path::Path;
 
 use anyhow::{Error, Result};
 use log::debug;
 use pars_core::config::cli::ParsConfig;
 use pars_core::git::add_and_commit;
 use pars_core::git::commit::{CommitType, GitCommit};
-use pars_core::operation::copy_or_rename::{copy_rename_io, CopyRenameConfig};
+use pars_core::operation::copy_or_rename::{
+    copy_rename_io, cross_copy_rename_io, CopyRenameConfig,
+};
 use pars_core::operation::generate::IOStreams;
 
 use crate::constants::{ParsExitCode, SECRET_EXTENSION};
-use crate::util::unwrap_root_path;
+use crate::util::unwrap_pass_path;
 
 pub fn cmd_cp(
     config: &ParsConfig,
@@ -18,10 +21,29 @@ pub fn cmd_cp(
     old_path: &str,
     new_path: &str,
 ) -> Result<(), (i32, Error)> {
-    let root = unwrap_root_path(base_dir, config);
+    let (root, old_name) = unwrap_pass_path(base_dir, config, old_path);
+    let (new_root, new_name) = unwrap_pass_path(base_dir, config, new_path);
+    if root != new_root {
+        return cmd_cross_store(
+            config,
+            CopyRenameConfig {
+                copy: true,
+                force,
+                file_extension: SECRET_EXTENSION.to_string(),
+                groups: config.groups.clone(),
+            },
+            (&root, old_name, old_path),
+            (&new_root, new_name, new_path),
+        );
+    }
+    let (old_path, new_path) = (old_name, new_name);
 
-    let copy_config =
-        CopyRenameConfig { copy: true, force, file_extension: SECRET_EXTENSION.to_string() };
+    let copy_config = CopyRenameConfig {
+        copy: true,
+        force,
+        file_extension: SECRET_EXTENSION.to_string(),
+        groups: config.groups.clone(),
+    };
 
     let mut stdin = BufReader::new(std::io::stdin());
     let mut stdout = std::io::stdout();
@@ -31,15 +53,66 @@ pub fn cmd_cp(
     copy_rename_io(copy_config, &root, old_path, new_path, io_streams)
         .map_err(|e| (ParsExitCode::Error.into(), e))?;
 
-    let commit =
-        GitCommit::new(&root, CommitType::Copy((old_path.to_string(), new_path.to_string())));
-    debug!("cmd_cp: commit {commit}");
-    add_and_commit(
-        &config.executable_config.git_executable,
+    let commit = GitCommit::new(
         &root,
-        commit.get_commit_msg().as_str(),
+        CommitType::Copy((old_path.to_string(), new_path.to_string())),
+        &config.git_config,
+    );
+    debug!("cmd_cp: commit {commit}");
+    add_and_commit(config, &root, commit.get_commit_msg().as_str())
+        .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+
+    Ok(())
+}
+
+/// Copy or move between two stores, each side is given as `(root, name in store, name as typed)`.
+/// The destination gets a commit and, when moving, the source as well.
+pub(crate) fn cmd_cross_store(
+    config: &ParsConfig,
+    copy_config: CopyRenameConfig,
+    (root, old_name, old_path): (&Path, &str, &str),
+    (new_root, new_name, new_path): (&Path, &str, &str),
+) -> Result<(), (i32, Error)> {
+    let copy = copy_config.copy;
+    let mut stdin = BufReader::new(std::io::stdin());
+    let mut stdout = std::io::stdout();
+    let mut stderr = std::io::stderr();
+    let io_streams = IOStreams { in_s: &mut stdin, out_s: &mut stdout, err_s: &mut stderr };
+
+    let written = cross_copy_rename_io(
+        copy_config,
+        &config.executable_config.pgp_executable,
+        &config.executable_config.pgp_options,
+        &config.groups,
+        (root, old_name),
+        (new_root, new_name),
+        io_streams,
     )
-    .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+    .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    if written == 0 {
+        return Ok(());
+    }
+
+    // Moving into a directory keeps the source name. Both commits name the two sides as typed,
+    // with the repo prefix, so they read the same in either store.
+    let new_path = if new_name.is_empty() || new_name.ends_with(['/', '\\']) {
+        let base_name = old_name.trim_end_matches(['/', '\\']).rsplit(['/', '\\']).next();
+        format!("{new_path}{}", base_name.unwrap_or_default())
+    } else {
+        new_path.to_string()
+    };
+    let names = (old_path.to_string(), new_path);
+    let commit_type = if copy { CommitType::Copy(names) } else { CommitType::Rename(names) };
+    let mut commits = vec![(new_root, commit_type.clone())];
+    if !copy {
+        commits.push((root, commit_type));
+    }
+    for (repo, commit_type) in commits {
+        let commit = GitCommit::new(repo, commit_type, &config.git_config);
+        debug!("cmd_cross_store: commit {commit}");
+        add_and_commit(config, repo, commit.get_commit_msg().as_str())
+            .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+    }
 
     Ok(())
 }
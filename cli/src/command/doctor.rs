@@ -0,0 +1,52 @@
+use std::env;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Error, Result};
+use pars_core::config::layered::LayeredConfig;
+use pars_core::operation::doctor::{diagnose, DoctorConfig, Severity};
+use pars_core::util::fs_util::system_config_path;
+
+use crate::constants::ParsExitCode;
+use crate::util::{offer_key_import, unwrap_root_path};
+
+pub fn cmd_doctor(layered: &LayeredConfig, base_dir: Option<&str>) -> Result<(), (i32, Error)> {
+    let config = &layered.config;
+    let root = unwrap_root_path(base_dir, config);
+    let editor =
+        env::var("PARS_EDITOR").unwrap_or(config.executable_config.editor_executable.clone());
+    // Without a loaded config, look at the files that would have been read
+    let config_files: Vec<PathBuf> = if layered.files.is_empty() {
+        [system_config_path(), layered.user_path.clone()]
+            .into_iter()
+            .filter(|path| path.is_file())
+            .collect()
+    } else {
+        layered.files.iter().map(|(_, path)| path.clone()).collect()
+    };
+
+    let checks = diagnose(&DoctorConfig {
+        config,
+        root: &root,
+        editor: &editor,
+        config_files: &config_files,
+        config_error: layered.load_error.as_deref(),
+    });
+
+    for check in &checks {
+        println!("[{}] {}: {}", check.severity, check.name, check.message);
+    }
+    let fixes: Vec<&str> = checks.iter().filter_map(|check| check.fix.as_deref()).collect();
+    if !fixes.is_empty() {
+        println!("\nSuggested fixes:");
+        for fix in fixes {
+            println!("  - {fix}");
+        }
+    }
+    offer_key_import(config, &root);
+
+    if checks.iter().any(|check| check.severity == Severity::Error) {
+        Err((ParsExitCode::Error.into(), anyhow!("Some checks failed")))
+    } else {
+        Ok(())
+    }
+}
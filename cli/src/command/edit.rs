@@ -6,14 +6,17 @@ use pars_core::git::{add_and_commit, commit};
 use pars_core::operation::edit::edit;
 
 use crate::constants::{ParsExitCode, SECRET_EXTENSION};
-use crate::util::unwrap_root_path;
+use crate::fuzzy::fuzzy_resolve;
+use crate::util::unwrap_pass_path;
 
 pub fn cmd_edit(
     config: &ParsConfig,
     base_dir: Option<&str>,
     target_pass: &str,
 ) -> Result<(), (i32, Error)> {
-    let root = unwrap_root_path(base_dir, config);
+    let (root, target_pass) = unwrap_pass_path(base_dir, config, target_pass);
+    let target_pass =
+        &*fuzzy_resolve(config, &root, target_pass).map_err(|e| (ParsExitCode::Error.into(), e))?;
     let editor =
         env::var("PARS_EDITOR").unwrap_or(config.executable_config.editor_executable.clone());
 
@@ -23,18 +26,19 @@ pub fn cmd_edit(
         SECRET_EXTENSION,
         &editor,
         &config.executable_config.pgp_executable,
+        &config.executable_config.pgp_options,
+        &config.groups,
     )
     .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
 
     if need_commit {
-        let commit =
-            commit::GitCommit::new(&root, commit::CommitType::Update(target_pass.to_string()));
-        add_and_commit(
-            &config.executable_config.git_executable,
+        let commit = commit::GitCommit::new(
             &root,
-            commit.get_commit_msg().as_str(),
-        )
-        .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+            commit::CommitType::Update(target_pass.to_string()),
+            &config.git_config,
+        );
+        add_and_commit(config, &root, commit.get_commit_msg().as_str())
+            .map_err(|e| (ParsExitCode::GitError.into(), e))?;
     }
 
     Ok(())
@@ -0,0 +1,122 @@
+use std::fs;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Error, Result};
+use pars_core::config::cli::ParsConfig;
+use pars_core::operation::export::{
+    collect_entries, to_csv, to_json, to_keepass_xml, write_bundle, ExportFormat,
+};
+use pars_core::util::fs_util::write_private_file;
+
+use crate::constants::ParsExitCode;
+use crate::util::{confirm, unwrap_root_path};
+
+pub struct ExportCommandConfig<'a> {
+    pub base_dir: Option<&'a str>,
+    pub path: Option<&'a str>,
+    pub output: Option<&'a str>,
+    pub recipient: Option<&'a str>,
+    pub force: bool,
+    pub format: &'a str,
+}
+
+pub fn cmd_export(
+    config: &ParsConfig,
+    cmd_config: ExportCommandConfig,
+) -> Result<(), (i32, Error)> {
+    let format: ExportFormat =
+        cmd_config.format.parse().map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let recipient = match (format, cmd_config.recipient) {
+        (ExportFormat::Bundle, None) => {
+            return Err((ParsExitCode::Error.into(), anyhow!("A bundle needs --recipient")));
+        }
+        (ExportFormat::Bundle, Some(recipient)) => recipient,
+        (_, Some(_)) => {
+            return Err((
+                ParsExitCode::Error.into(),
+                anyhow!("--recipient only applies to bundles, the other formats are plain text"),
+            ));
+        }
+        (_, None) => "",
+    };
+    if let Some(output) = cmd_config.output {
+        if Path::new(output).exists() && !cmd_config.force {
+            return Err((
+                ParsExitCode::Error.into(),
+                anyhow!("'{output}' already exists, --force replaces it"),
+            ));
+        }
+    }
+    if format.is_plaintext() {
+        warn_plaintext(cmd_config.force)?;
+    }
+
+    let root = unwrap_root_path(cmd_config.base_dir, config);
+    let (entries, binary) = collect_entries(config, &root, cmd_config.path)
+        .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    for name in binary {
+        eprintln!("Skipped '{name}', binary entries cannot be exported");
+    }
+
+    let content = match format {
+        ExportFormat::Json => to_json(&entries),
+        ExportFormat::Csv => to_csv(&entries),
+        ExportFormat::KeepassXml => {
+            let root_name =
+                cmd_config.path.and_then(|path| path.rsplit('/').find(|part| !part.is_empty()));
+            Ok(to_keepass_xml(&entries, root_name.unwrap_or("pars")))
+        }
+        ExportFormat::Bundle => {
+            let output = cmd_config.output.unwrap_or("-");
+            if output != "-" && cmd_config.force {
+                let _ = fs::remove_file(output);
+            }
+            write_bundle(config, recipient, &entries, output)
+                .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+            eprintln!("Exported {} entries encrypted for {recipient}", entries.len());
+            return Ok(());
+        }
+    }
+    .map_err(|e| (ParsExitCode::Error.into(), e))?;
+
+    match cmd_config.output {
+        Some(output) => {
+            write_private_file(Path::new(output), content.as_bytes(), cmd_config.force)
+                .map_err(|e| (ParsExitCode::Error.into(), e))?;
+            eprintln!(
+                "Exported {} entries to '{output}', delete it once it is imported",
+                entries.len()
+            );
+        }
+        None => {
+            io::stdout()
+                .write_all(content.as_bytes())
+                .map_err(|e| (ParsExitCode::Error.into(), e.into()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Every password ends up readable by anyone who gets hold of the output, make sure it is meant
+fn warn_plaintext(force: bool) -> Result<(), (i32, Error)> {
+    eprintln!("WARNING: this export holds every password in plain text, unencrypted.");
+    eprintln!(
+        "Anyone reading the output, a backup of it or your terminal scrollback can use them."
+    );
+    eprintln!("Prefer a bundle (`pars export bundle -r <gpg-id>`) to hand passwords over.");
+    if force {
+        return Ok(());
+    }
+    if !io::stdin().is_terminal() {
+        return Err((
+            ParsExitCode::Error.into(),
+            anyhow!("Refusing a plain text export without a terminal to confirm, pass --force"),
+        ));
+    }
+    if confirm("Export in plain text anyway?") {
+        Ok(())
+    } else {
+        Err((ParsExitCode::Error.into(), anyhow!("Export cancelled")))
+    }
+}
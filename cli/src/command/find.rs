@@ -1,18 +1,25 @@
+use std::path::PathBuf;
+
 use anyhow::{Error, Result};
 use pars_core::config::cli::ParsConfig;
-use pars_core::operation::find::find_term;
-use pars_core::util::tree::{FilterType, TreeConfig};
+use pars_core::operation::find::{find_term, find_tree};
+use pars_core::util::tree::{attach_trees, join_trees, FilterType, TreeConfig, TreePrintConfig};
 
 use crate::constants::ParsExitCode;
-use crate::util::unwrap_root_path;
+use crate::util::{existing_repos, mounted_stores, unwrap_root_path};
 
 pub fn cmd_find(
     config: &ParsConfig,
     base_dir: Option<&str>,
     names: &[String],
+    all: bool,
 ) -> Result<(), (i32, Error)> {
+    let terms: Vec<&str> = names.iter().map(|s| s.as_str()).collect();
+    if all {
+        return find_all(config, &terms);
+    }
+
     let root = unwrap_root_path(base_dir, config);
-    let terms = names.iter().map(|s| s.as_str()).collect();
     let tree_cfg = TreeConfig {
         root: &root,
         target: "",
@@ -20,8 +27,44 @@ pub fn cmd_find(
         filters: Vec::new(),
     };
 
-    let res = find_term(&terms, &tree_cfg, &config.print_config.clone().into())
-        .map_err(|e| (ParsExitCode::Error.into(), e))?;
-    println!("{res}");
+    let print_cfg = Into::<TreePrintConfig>::into(&config.print_config);
+    let mounts = mounted_stores(base_dir, config);
+    if mounts.is_empty() {
+        let res = find_term(&terms, &tree_cfg, &print_cfg)
+            .map_err(|e| (ParsExitCode::Error.into(), e))?;
+        println!("{res}");
+        return Ok(());
+    }
+
+    let tree =
+        find_tree(&terms, &tree_cfg, &print_cfg).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let mounted = find_trees(&mounts, &terms, &print_cfg)?;
+    println!("Search Terms: {}\n{}", terms.join(", "), attach_trees(&tree, &mounted, &print_cfg));
+    Ok(())
+}
+
+/// Search each store, stores without any match are left out
+fn find_trees<'a>(
+    stores: &[(&'a str, PathBuf)],
+    terms: &[&str],
+    print_cfg: &TreePrintConfig,
+) -> Result<Vec<(&'a str, String)>, (i32, Error)> {
+    let mut trees = Vec::new();
+    for (name, root) in stores {
+        let tree_cfg =
+            TreeConfig { root, target: "", filter_type: FilterType::Include, filters: Vec::new() };
+        let tree =
+            find_tree(terms, &tree_cfg, print_cfg).map_err(|e| (ParsExitCode::Error.into(), e))?;
+        if !tree.is_empty() {
+            trees.push((*name, tree));
+        }
+    }
+    Ok(trees)
+}
+
+fn find_all(config: &ParsConfig, terms: &[&str]) -> Result<(), (i32, Error)> {
+    let print_cfg = Into::<TreePrintConfig>::into(&config.print_config);
+    let trees = find_trees(&existing_repos(config), terms, &print_cfg)?;
+    println!("Search Terms: {}\n{}", terms.join(", "), join_trees(&trees, &print_cfg));
     Ok(())
 }
@@ -0,0 +1,42 @@
+use anyhow::{anyhow, Error, Result};
+use pars_core::config::cli::ParsConfig;
+use pars_core::operation::fsck::{fsck, FsckConfig};
+
+use crate::constants::ParsExitCode;
+use crate::util::unwrap_root_path;
+
+pub fn cmd_fsck(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    decrypt: bool,
+    fix: bool,
+) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+    let report = fsck(config, &root, &FsckConfig { decrypt, fix })
+        .map_err(|e| (ParsExitCode::Error.into(), e))?;
+
+    for problem in &report.problems {
+        println!("{problem}");
+    }
+    for fixed in &report.fixed {
+        println!("{fixed}");
+    }
+    for unfixed in &report.unfixed {
+        println!("{unfixed}");
+    }
+    println!("Checked {} entries, {} problem(s) found", report.checked, report.problems.len());
+
+    let fixable = report.problems.iter().any(|problem| problem.is_fixable());
+    if !report.unfixed.is_empty() {
+        Err((
+            ParsExitCode::Error.into(),
+            anyhow!("{} problem(s) could not be fixed", report.unfixed.len()),
+        ))
+    } else if report.problems.iter().all(|problem| fix && problem.is_fixable()) {
+        Ok(())
+    } else if fixable && !fix {
+        Err((ParsExitCode::Error.into(), anyhow!("The store has problems, --fix repairs some")))
+    } else {
+        Err((ParsExitCode::Error.into(), anyhow!("The store has problems")))
+    }
+}
@@ -10,8 +10,8 @@ use pars_core::operation::generate::{generate_io, IOStreams, PasswdGenerateConfi
 use secrecy::zeroize::Zeroize;
 use secrecy::ExposeSecret;
 
-use crate::constants::{ParsExitCode, DEFAULT_PASS_LENGTH, SECRET_EXTENSION};
-use crate::util::unwrap_root_path;
+use crate::constants::{ParsExitCode, SECRET_EXTENSION};
+use crate::util::unwrap_pass_path;
 
 pub struct GenerateCommandConfig<'a> {
     pub base_dir: Option<&'a str>,
@@ -27,15 +27,18 @@ pub fn cmd_generate(
     config: &ParsConfig,
     cmd_config: GenerateCommandConfig,
 ) -> Result<(), (i32, Error)> {
-    let root = unwrap_root_path(cmd_config.base_dir, config);
+    let (root, pass_name) = unwrap_pass_path(cmd_config.base_dir, config, cmd_config.pass_name);
 
     let gen_cfg = PasswdGenerateConfig {
         no_symbols: cmd_config.no_symbols,
         in_place: cmd_config.in_place,
         force: cmd_config.force,
-        pass_length: cmd_config.pass_length.unwrap_or(DEFAULT_PASS_LENGTH),
+        pass_length: cmd_config.pass_length.unwrap_or(config.feature_config.generated_length),
+        character_set: config.feature_config.character_set.clone(),
         extension: SECRET_EXTENSION.to_string(),
         pgp_executable: config.executable_config.pgp_executable.clone(),
+        pgp_options: config.executable_config.pgp_options.clone(),
+        groups: config.groups.clone(),
     };
 
     let mut stdin = BufReader::new(std::io::stdin());
@@ -43,7 +46,7 @@ pub fn cmd_generate(
     let mut stderr = std::io::stderr();
     let mut io_streams = IOStreams { in_s: &mut stdin, out_s: &mut stdout, err_s: &mut stderr };
 
-    let mut res = generate_io(&root, cmd_config.pass_name, &gen_cfg, &mut io_streams)
+    let mut res = generate_io(&root, pass_name, &gen_cfg, &mut io_streams)
         .map_err(|e| (ParsExitCode::Error.into(), e))?;
 
     if !cmd_config.clip {
@@ -53,18 +56,17 @@ pub fn cmd_generate(
             res.expose_secret()
         );
         res.zeroize();
-    } else if let Err(e) = copy_to_clipboard(res, &config.feature_config.clip_time) {
+    } else if let Err(e) =
+        copy_to_clipboard(res, &config.feature_config.clip_time, &config.feature_config.x_selection)
+    {
         eprintln!("Failed to copy to clipboard: {e}");
     }
 
-    let commit = GitCommit::new(&root, CommitType::Generate(cmd_config.pass_name.to_string()));
+    let commit =
+        GitCommit::new(&root, CommitType::Generate(pass_name.to_string()), &config.git_config);
     debug!("cmd_generate: commit {commit}");
-    add_and_commit(
-        &config.executable_config.git_executable,
-        &root,
-        commit.get_commit_msg().as_str(),
-    )
-    .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+    add_and_commit(config, &root, commit.get_commit_msg().as_str())
+        .map_err(|e| (ParsExitCode::GitError.into(), e))?;
 
     Ok(())
 }
@@ -1,27 +1,48 @@
+use std::path::PathBuf;
+
 use anyhow::{Error, Result};
 use pars_core::config::cli::ParsConfig;
 use pars_core::operation::grep::{grep_stream, GrepPrintConfig};
 
 use crate::constants::ParsExitCode;
-use crate::util::unwrap_root_path;
+use crate::util::{existing_repos, mounted_stores, unwrap_root_path};
 
 pub fn cmd_grep(
     config: &ParsConfig,
     base_dir: Option<&str>,
     search_string: &str,
+    all: bool,
 ) -> Result<(), (i32, Error)> {
-    let root = unwrap_root_path(base_dir, config);
+    // Each store with the prefix its pass names are printed with
+    let stores: Vec<(String, PathBuf)> = if all {
+        existing_repos(config).into_iter().map(|(name, root)| (format!("{name}:"), root)).collect()
+    } else {
+        let mut stores = vec![(String::new(), unwrap_root_path(base_dir, config))];
+        stores.extend(
+            mounted_stores(base_dir, config)
+                .into_iter()
+                .map(|(prefix, root)| (format!("{prefix}/"), root)),
+        );
+        stores
+    };
+
+    for (prefix, root) in stores {
+        let mut print_cfg = Into::<GrepPrintConfig>::into(&config.print_config);
+        print_cfg.pass_prefix = prefix;
 
-    // Pass the pgp_executable directly to the grep function
-    // instead of creating a single PGPClient instance
-    grep_stream(
-        &config.executable_config.pgp_executable,
-        &root,
-        search_string,
-        &Into::<GrepPrintConfig>::into(&config.print_config),
-        &mut std::io::stdout(),
-    )
-    .map_err(|e| (ParsExitCode::Error.into(), e))?;
+        // Pass the pgp_executable directly to the grep function
+        // instead of creating a single PGPClient instance
+        grep_stream(
+            &config.executable_config.pgp_executable,
+            &config.executable_config.pgp_options,
+            &config.groups,
+            &root,
+            search_string,
+            &print_cfg,
+            &mut std::io::stdout(),
+        )
+        .map_err(|e| (ParsExitCode::Error.into(), e))?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,84 @@
+use std::fs;
+use std::io::{self, Read};
+
+use anyhow::{anyhow, Error, Result};
+use pars_core::config::cli::ParsConfig;
+use pars_core::operation::import::{
+    import, parse, plan, ConflictPolicy, ImportAction, ImportFormat, PlannedEntry,
+};
+
+use crate::constants::ParsExitCode;
+use crate::util::unwrap_root_path;
+
+pub struct ImportCommandConfig<'a> {
+    pub base_dir: Option<&'a str>,
+    pub path: Option<&'a str>,
+    pub dry_run: bool,
+    pub on_conflict: &'a str,
+    pub format: &'a str,
+    pub file: &'a str,
+}
+
+pub fn cmd_import(
+    config: &ParsConfig,
+    cmd_config: ImportCommandConfig,
+) -> Result<(), (i32, Error)> {
+    let format: ImportFormat =
+        cmd_config.format.parse().map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let policy: ConflictPolicy =
+        cmd_config.on_conflict.parse().map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let content = read_export(cmd_config.file).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let entries = parse(format, &content).map_err(|e| (ParsExitCode::Error.into(), e))?;
+
+    let root = unwrap_root_path(cmd_config.base_dir, config);
+    let planned = plan(&root, cmd_config.path, entries, policy);
+    let conflicts = planned.iter().filter(|entry| entry.action == ImportAction::Conflict).count();
+
+    if cmd_config.dry_run {
+        for entry in &planned {
+            println!("{}", describe(entry));
+        }
+        println!(
+            "{} to import, {} skipped, {} conflict(s)",
+            planned.iter().filter(|entry| entry.target().is_some()).count(),
+            planned.iter().filter(|entry| entry.action == ImportAction::Skip).count(),
+            conflicts
+        );
+        return Ok(());
+    }
+
+    if conflicts > 0 {
+        for entry in planned.iter().filter(|entry| entry.action == ImportAction::Conflict) {
+            eprintln!("{}", describe(entry));
+        }
+        return Err((
+            ParsExitCode::Error.into(),
+            anyhow!(
+                "{conflicts} name(s) already taken, nothing imported. Pick --on-conflict skip, \
+                 overwrite or rename"
+            ),
+        ));
+    }
+    let count = import(config, &root, &planned).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    println!("Imported {count} entries from {format}");
+    Ok(())
+}
+
+fn read_export(file: &str) -> Result<String> {
+    if file == "-" {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        return Ok(content);
+    }
+    fs::read_to_string(file).map_err(|e| anyhow!("Failed to read '{file}': {e}"))
+}
+
+fn describe(entry: &PlannedEntry) -> String {
+    match &entry.action {
+        ImportAction::Create => format!("create     {}", entry.name),
+        ImportAction::Overwrite => format!("overwrite  {}", entry.name),
+        ImportAction::Skip => format!("skip       {} (name taken)", entry.name),
+        ImportAction::Rename(name) => format!("rename     {} -> {name}", entry.name),
+        ImportAction::Conflict => format!("conflict   {} (name taken)", entry.name),
+    }
+}
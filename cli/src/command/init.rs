@@ -1,9 +1,11 @@
-use anyhow::{Error, Result};
+use anyhow::{anyhow, Error, Result};
 use log::debug;
 use pars_core::config::cli::ParsConfig;
 use pars_core::git::commit::{CommitType, GitCommit};
 use pars_core::git::{add_and_commit, init_repo};
 use pars_core::operation::init::{init, InitConfig};
+use pars_core::operation::public_keys::export_public_keys;
+use pars_core::pgp::groups::expand_recipients;
 use pars_core::pgp::PGPClient;
 use pars_core::util::fs_util::path_to_str;
 
@@ -15,11 +17,13 @@ pub fn cmd_init(
     base_dir: Option<&str>,
     path: Option<&str>,
     pgp_ids: &[String],
+    force: bool,
 ) -> Result<(), (i32, Error)> {
     let root = unwrap_root_path(base_dir, config);
 
     // Create a temporary PGPClient just to get the user information for display purposes
-    let pgp_client = PGPClient::new(config.executable_config.pgp_executable.clone(), pgp_ids)
+    let pgp_client = expand_recipients(&root, pgp_ids, &config.groups)
+        .and_then(|ids| PGPClient::new(config.executable_config.pgp_executable.clone(), &ids))
         .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
 
     println!(
@@ -30,12 +34,21 @@ pub fn cmd_init(
     );
 
     // Create InitConfig for the init function
-    let init_config = InitConfig {
-        pgp_executable: config.executable_config.pgp_executable.clone(),
-        keys_fpr: pgp_ids.to_vec(),
-    };
+    let init_config = InitConfig { force, ..InitConfig::new(config, pgp_ids.to_vec()) };
 
-    init(&init_config, &root, path).map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    let report = init(&init_config, &root, path).map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    if !report.hidden.is_empty() {
+        println!(
+            "Skipped {} entries hiding their recipients, `pars init --force` re-encrypts them",
+            report.hidden.len()
+        );
+    }
+    let exported =
+        export_public_keys(&config.executable_config.pgp_executable, &root, &config.groups)
+            .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    if report.is_empty() && exported.is_empty() {
+        return Ok(());
+    }
 
     if !root.join(".git").exists() {
         init_repo(&config.executable_config.git_executable, &root)
@@ -45,14 +58,26 @@ pub fn cmd_init(
     let commit = GitCommit::new(
         &root,
         CommitType::Init(pgp_client.get_keys_fpr().iter().map(|f| f.to_string()).collect()),
+        &config.git_config,
     );
     debug!("cmd_init: commit {commit}");
-    add_and_commit(
-        &config.executable_config.git_executable,
-        &root,
-        commit.get_commit_msg().as_str(),
-    )
-    .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+    add_and_commit(config, &root, commit.get_commit_msg().as_str())
+        .map_err(|e| (ParsExitCode::GitError.into(), e))?;
 
+    if !report.reencrypted.is_empty() {
+        println!("Re-encrypted {} entries", report.reencrypted.len());
+    }
+    if !report.failed.is_empty() {
+        for (entry, e) in &report.failed {
+            eprintln!("Failed to re-encrypt '{entry}': {e}");
+        }
+        return Err((
+            ParsExitCode::PGPError.into(),
+            anyhow!(
+                "{} entries are left to re-encrypt, run init again to retry",
+                report.failed.len()
+            ),
+        ));
+    }
     Ok(())
 }
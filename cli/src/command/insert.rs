@@ -1,14 +1,15 @@
+use std::fs;
 use std::io::BufReader;
 
-use anyhow::Error;
+use anyhow::{anyhow, Error};
 use log::debug;
 use pars_core::config::cli::ParsConfig;
 use pars_core::git::add_and_commit;
 use pars_core::git::commit::{CommitType, GitCommit};
-use pars_core::operation::insert::{insert_io, PasswdInsertConfig};
+use pars_core::operation::insert::{insert_bytes_io, insert_io, PasswdInsertConfig};
 
 use crate::constants::{ParsExitCode, SECRET_EXTENSION};
-use crate::util::unwrap_root_path;
+use crate::util::unwrap_pass_path;
 
 pub fn cmd_insert(
     config: &ParsConfig,
@@ -16,9 +17,10 @@ pub fn cmd_insert(
     pass_name: &str,
     echo: bool,
     multiline: bool,
+    file: Option<&str>,
     force: bool,
 ) -> Result<(), (i32, Error)> {
-    let root = unwrap_root_path(base_dir, config);
+    let (root, pass_name) = unwrap_pass_path(base_dir, config, pass_name);
 
     let insert_cfg = PasswdInsertConfig {
         echo,
@@ -26,30 +28,40 @@ pub fn cmd_insert(
         force,
         extension: SECRET_EXTENSION.to_string(),
         pgp_executable: config.executable_config.pgp_executable.clone(),
+        pgp_options: config.executable_config.pgp_options.clone(),
+        groups: config.groups.clone(),
     };
 
-    if !insert_io(
-        &root,
-        pass_name,
-        &insert_cfg,
-        &mut BufReader::new(std::io::stdin()),
-        &mut std::io::stdout(),
-        &mut std::io::stderr(),
-    )
-    .map_err(|e| (ParsExitCode::Error.into(), e))?
-    {
+    let mut stdin = BufReader::new(std::io::stdin());
+    let mut stdout = std::io::stdout();
+    let mut stderr = std::io::stderr();
+    let inserted = match file {
+        Some(file) => {
+            let content = fs::read(file)
+                .map_err(|e| (ParsExitCode::Error.into(), anyhow!("Cannot read '{file}': {e}")))?;
+            insert_bytes_io(
+                &root,
+                pass_name,
+                &content,
+                &insert_cfg,
+                &mut stdin,
+                &mut stdout,
+                &mut stderr,
+            )
+        }
+        None => insert_io(&root, pass_name, &insert_cfg, &mut stdin, &mut stdout, &mut stderr),
+    }
+    .map_err(|e| (ParsExitCode::Error.into(), e))?;
+    if !inserted {
         // Failed to insert, cancel git commit
         return Ok(());
     }
 
-    let commit = GitCommit::new(&root, CommitType::Insert(pass_name.to_string()));
+    let commit =
+        GitCommit::new(&root, CommitType::Insert(pass_name.to_string()), &config.git_config);
     debug!("cmd_insert: commit {commit}");
-    add_and_commit(
-        &config.executable_config.git_executable,
-        &root,
-        commit.get_commit_msg().as_str(),
-    )
-    .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+    add_and_commit(config, &root, commit.get_commit_msg().as_str())
+        .map_err(|e| (ParsExitCode::GitError.into(), e))?;
 
     Ok(())
 }
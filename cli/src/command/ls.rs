@@ -1,15 +1,20 @@
+use std::path::Path;
+
 use anyhow::{anyhow, Error, Result};
 use fast_qr::{self, QRBuilder};
 use log::debug;
 use pars_core::clipboard::copy_to_clipboard;
 use pars_core::config::cli::ParsConfig;
-use pars_core::operation::ls_or_show::{ls_io, LsOrShow};
-use pars_core::util::tree::{FilterType, TreeConfig, TreePrintConfig};
+use pars_core::operation::ls_or_show::{decrypt_entry, ls_io, LsOrShow};
+use pars_core::pgp::is_not_text;
+use pars_core::util::fs_util::write_private_file;
+use pars_core::util::tree::{attach_trees, join_trees, FilterType, TreeConfig, TreePrintConfig};
 use secrecy::zeroize::Zeroize;
 use secrecy::{ExposeSecret, SecretString};
 
 use crate::constants::ParsExitCode;
-use crate::util::unwrap_root_path;
+use crate::fuzzy::fuzzy_resolve;
+use crate::util::{existing_repos, mounted_stores, unwrap_pass_path};
 
 pub fn cmd_ls(
     config: &ParsConfig,
@@ -17,29 +22,51 @@ pub fn cmd_ls(
     clip: Option<usize>,
     qrcode: Option<usize>,
     target: Option<&str>,
+    fuzzy: bool,
 ) -> Result<(), (i32, Error)> {
-    let root = unwrap_root_path(base_dir, config);
-    let target_path = root.join(target.unwrap_or_default());
+    let (root, pass_name) = unwrap_pass_path(base_dir, config, target.unwrap_or_default());
+    // `ls` names folders, resolving a mistyped one to an entry would print an unrelated secret
+    let pass_name = if fuzzy {
+        fuzzy_resolve(config, &root, pass_name).map_err(|e| (ParsExitCode::Error.into(), e))?
+    } else {
+        pass_name.into()
+    };
+    let pass_name = &*pass_name;
+    let target_path = root.join(pass_name);
     debug!("cmd_ls: root {root:?}, target_path {target_path:?}");
 
     let tree_cfg = TreeConfig {
         root: &root,
-        target: target.unwrap_or_default(),
+        target: pass_name,
         filter_type: FilterType::Disable,
         filters: Vec::new(),
     };
+    let print_cfg = Into::<TreePrintConfig>::into(&config.print_config);
 
     // Pass the pgp_executable to ls_io instead of a PGPClient instance
     let res = ls_io(
         &config.executable_config.pgp_executable,
+        &config.executable_config.pgp_options,
+        &config.groups,
         &tree_cfg,
-        &Into::<TreePrintConfig>::into(&config.print_config),
+        &print_cfg,
     )
-    .map_err(|e| (ParsExitCode::Error.into(), e))?;
+    .map_err(|e| (ParsExitCode::Error.into(), with_binary_hint(pass_name, e)))?;
 
     match res {
         LsOrShow::DirTree(tree) => {
-            println!("{tree}");
+            let (header, tree) = tree.split_once('\n').unwrap_or((&tree, ""));
+            let header = match target {
+                // A mount point is listed under its own name rather than the mounted root's
+                Some(target) if !target.is_empty() && pass_name.is_empty() => target,
+                _ => header,
+            };
+            if target.unwrap_or_default().is_empty() {
+                let mounts = mounted_trees(base_dir, config, &print_cfg)?;
+                println!("{header}\n{}", attach_trees(tree, &mounts, &print_cfg));
+            } else {
+                println!("{header}\n{tree}");
+            }
             Ok(())
         }
         LsOrShow::Password(mut passwd) => {
@@ -58,6 +85,102 @@ pub fn cmd_ls(
     }
 }
 
+/// Point to `show --output` when the entry cannot be shown because it is binary
+fn with_binary_hint(pass_name: &str, e: Error) -> Error {
+    if is_not_text(&e) {
+        let hint = format!("`pars show --output <file> {pass_name}` writes it to a file");
+        anyhow!("'{pass_name}' is a binary entry, {hint}")
+    } else {
+        e
+    }
+}
+
+/// Write the entry to a new file only the user can read, the way to get binary entries out
+pub fn cmd_show_output(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    target: &str,
+    output: &str,
+) -> Result<(), (i32, Error)> {
+    let (root, pass_name) = unwrap_pass_path(base_dir, config, target);
+    let pass_name =
+        &*fuzzy_resolve(config, &root, pass_name).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let content = decrypt_entry(
+        &config.executable_config.pgp_executable,
+        &config.executable_config.pgp_options,
+        &config.groups,
+        &root,
+        pass_name,
+    )
+    .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    write_private_file(Path::new(output), content.expose_secret(), false)
+        .map_err(|e| (ParsExitCode::Error.into(), e))?;
+    eprintln!("Wrote '{pass_name}' to '{output}'");
+    Ok(())
+}
+
+/// List every named repo, each tree nested under its repo name
+pub fn cmd_ls_all(config: &ParsConfig, sub_folder: Option<&str>) -> Result<(), (i32, Error)> {
+    let print_cfg = Into::<TreePrintConfig>::into(&config.print_config);
+    let mut trees = Vec::new();
+    for (name, root) in existing_repos(config) {
+        if !root.join(sub_folder.unwrap_or_default()).is_dir() {
+            continue;
+        }
+        let tree_cfg = TreeConfig {
+            root: &root,
+            target: sub_folder.unwrap_or_default(),
+            filter_type: FilterType::Disable,
+            filters: Vec::new(),
+        };
+        match ls_io(
+            &config.executable_config.pgp_executable,
+            &config.executable_config.pgp_options,
+            &config.groups,
+            &tree_cfg,
+            &print_cfg,
+        )
+        .map_err(|e| (ParsExitCode::Error.into(), e))?
+        {
+            // Drop the per repo header, a single one is printed for all of them
+            LsOrShow::DirTree(tree) => {
+                trees.push((name, tree.split_once('\n').unwrap_or_default().1.to_string()))
+            }
+            LsOrShow::Password(_) => unreachable!("only directories are listed"),
+        }
+    }
+    println!("{}\n{}", sub_folder.unwrap_or("Password Store"), join_trees(&trees, &print_cfg));
+    Ok(())
+}
+
+fn mounted_trees<'a>(
+    base_dir: Option<&str>,
+    config: &'a ParsConfig,
+    print_cfg: &TreePrintConfig,
+) -> Result<Vec<(&'a str, String)>, (i32, Error)> {
+    let mut trees = Vec::new();
+    for (prefix, root) in mounted_stores(base_dir, config) {
+        let tree_cfg = TreeConfig {
+            root: &root,
+            target: "",
+            filter_type: FilterType::Disable,
+            filters: Vec::new(),
+        };
+        if let LsOrShow::DirTree(tree) = ls_io(
+            &config.executable_config.pgp_executable,
+            &config.executable_config.pgp_options,
+            &config.groups,
+            &tree_cfg,
+            print_cfg,
+        )
+        .map_err(|e| (ParsExitCode::Error.into(), e))?
+        {
+            trees.push((prefix, tree.split_once('\n').unwrap_or_default().1.to_string()));
+        }
+    }
+    Ok(trees)
+}
+
 fn handle_qr_code(
     qrcode: Option<usize>,
     passwd: secrecy::SecretBox<str>,
@@ -93,8 +216,12 @@ fn handle_clip(
         }
 
         if let Some(line_content) = passwd.expose_secret().split('\n').nth(line_num - 1) {
-            copy_to_clipboard(line_content.into(), &config.feature_config.clip_time)
-                .map_err(|e| (ParsExitCode::Error.into(), e))?;
+            copy_to_clipboard(
+                line_content.into(),
+                &config.feature_config.clip_time,
+                &config.feature_config.x_selection,
+            )
+            .map_err(|e| (ParsExitCode::Error.into(), e))?;
         } else {
             return Err((
                 ParsExitCode::Error.into(),
@@ -112,3 +239,21 @@ fn to_qr_code(secret: SecretString) -> Result<SecretString> {
     let qr = QRBuilder::new(secret.expose_secret()).build()?;
     Ok(qr.to_str().into())
 }
+
+#[cfg(test)]
+mod tests {
+    use pars_core::pgp::PGPErr;
+
+    use super::*;
+
+    #[test]
+    fn binary_hint_test() {
+        let e = with_binary_hint("certs/key", PGPErr::NotText.into());
+        assert_eq!(
+            e.to_string(),
+            "'certs/key' is a binary entry, `pars show --output <file> certs/key` writes it to a file"
+        );
+        let e = with_binary_hint("certs/key", anyhow!("PGP decryption failed"));
+        assert_eq!(e.to_string(), "PGP decryption failed");
+    }
+}
@@ -0,0 +1,100 @@
+use anyhow::{anyhow, Error, Result};
+use pars_core::autotype::{autotype, detect_backend, parse_sequence, PASSWORD_FIELD};
+use pars_core::clipboard::copy_to_clipboard;
+use pars_core::config::cli::ParsConfig;
+use pars_core::operation::menu::{detect_picker, pick, picker_command};
+use pars_core::util::str::entry_fields;
+use pars_core::util::tree::{tree_entries, FilterType, TreeConfig};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::constants::ParsExitCode;
+use crate::util::{decrypt_entry, mounted_stores, unwrap_pass_path, unwrap_root_path};
+
+/// Pick an entry with an external picker and copy or type its password, or one of its
+/// fields. `field` is `Some(None)` when the field should be picked too.
+pub fn cmd_menu(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    field: Option<Option<&str>>,
+    picker: Option<&str>,
+    type_it: bool,
+) -> Result<(), (i32, Error)> {
+    let picker = detect_picker(picker.unwrap_or(&config.executable_config.menu_executable))
+        .map_err(|e| (ParsExitCode::CommandNotFound.into(), e))?;
+
+    let names = entry_names(base_dir, config).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let choices: Vec<&str> = names.iter().map(String::as_str).collect();
+    let Some(selected) = pick(&picker_command(&picker, "pars"), &choices)
+        .map_err(|e| (ParsExitCode::Error.into(), e))?
+    else {
+        return Ok(());
+    };
+
+    let (root, pass_name) = unwrap_pass_path(base_dir, config, &selected);
+    let content =
+        decrypt_entry(config, &root, pass_name).map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+
+    let fields = entry_fields(content.expose_secret());
+    let field = match field {
+        None => PASSWORD_FIELD.to_string(),
+        Some(Some(field)) => field.to_string(),
+        Some(None) => {
+            let mut choices = vec![PASSWORD_FIELD];
+            choices.extend(fields.iter().map(|(key, _)| *key));
+            match pick(&picker_command(&picker, "field"), &choices)
+                .map_err(|e| (ParsExitCode::Error.into(), e))?
+            {
+                Some(field) => field,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    if type_it {
+        let sequence = parse_sequence(&format!("{{{field}}}"))
+            .map_err(|e| (ParsExitCode::InvalidArgs.into(), e))?;
+        let mut backend =
+            detect_backend().map_err(|e| (ParsExitCode::CommandNotFound.into(), e))?;
+        return autotype(backend.as_mut(), &sequence, &content)
+            .map_err(|e| (ParsExitCode::Error.into(), e));
+    }
+
+    let value: SecretString = if field == PASSWORD_FIELD {
+        content.expose_secret().lines().next().unwrap_or_default().into()
+    } else {
+        fields.iter().find(|(key, _)| *key == field).map(|(_, value)| (*value).into()).ok_or_else(
+            || (ParsExitCode::Error.into(), anyhow!("'{selected}' has no field '{field}'")),
+        )?
+    };
+    copy_to_clipboard(value, &config.feature_config.clip_time, &config.feature_config.x_selection)
+        .map_err(|e| (ParsExitCode::ClipboardError.into(), e))?;
+
+    Ok(())
+}
+
+/// Every entry of the store, entries of mounted stores under their prefix
+fn entry_names(base_dir: Option<&str>, config: &ParsConfig) -> Result<Vec<String>> {
+    let mut stores = vec![(String::new(), unwrap_root_path(base_dir, config))];
+    stores.extend(
+        mounted_stores(base_dir, config)
+            .into_iter()
+            .map(|(prefix, root)| (format!("{prefix}/"), root)),
+    );
+
+    let mut names = Vec::new();
+    for (prefix, root) in stores {
+        let tree_cfg = TreeConfig {
+            root: &root,
+            target: "",
+            filter_type: FilterType::Disable,
+            filters: Vec::new(),
+        };
+        names.extend(
+            tree_entries(&tree_cfg)?
+                .into_iter()
+                .filter(|entry| !entry.is_dir)
+                .map(|entry| format!("{prefix}{}", entry.path)),
+        );
+    }
+    Ok(names)
+}
@@ -1,12 +1,26 @@
+pub mod autotype;
+pub mod backup;
+pub mod clip;
+pub mod config;
 pub mod cp;
+pub mod doctor;
 pub mod edit;
+pub mod export;
 pub mod find;
+pub mod fsck;
 pub mod generate;
 pub mod git;
 pub mod grep;
+pub mod import;
 pub mod init;
 pub mod insert;
 pub mod ls;
+pub mod menu;
 pub mod mv;
+pub mod recipients;
+pub mod repos;
 pub mod rm;
 pub mod shell;
+pub mod sync;
+pub mod trash;
+pub mod tui;
@@ -8,8 +8,9 @@ use pars_core::git::commit::{CommitType, GitCommit};
 use pars_core::operation::copy_or_rename::{copy_rename_io, CopyRenameConfig};
 use pars_core::operation::generate::IOStreams;
 
+use crate::command::cp::cmd_cross_store;
 use crate::constants::{ParsExitCode, SECRET_EXTENSION};
-use crate::util::unwrap_root_path;
+use crate::util::unwrap_pass_path;
 
 pub fn cmd_mv(
     config: &ParsConfig,
@@ -18,10 +19,29 @@ pub fn cmd_mv(
     old_path: &str,
     new_path: &str,
 ) -> Result<(), (i32, Error)> {
-    let root = unwrap_root_path(base_dir, config);
+    let (root, old_name) = unwrap_pass_path(base_dir, config, old_path);
+    let (new_root, new_name) = unwrap_pass_path(base_dir, config, new_path);
+    if root != new_root {
+        return cmd_cross_store(
+            config,
+            CopyRenameConfig {
+                copy: false,
+                force,
+                file_extension: SECRET_EXTENSION.to_string(),
+                groups: config.groups.clone(),
+            },
+            (&root, old_name, old_path),
+            (&new_root, new_name, new_path),
+        );
+    }
+    let (old_path, new_path) = (old_name, new_name);
 
-    let copy_config =
-        CopyRenameConfig { copy: false, force, file_extension: SECRET_EXTENSION.to_string() };
+    let copy_config = CopyRenameConfig {
+        copy: false,
+        force,
+        file_extension: SECRET_EXTENSION.to_string(),
+        groups: config.groups.clone(),
+    };
 
     let mut stdin = BufReader::new(std::io::stdin());
     let mut stdout = std::io::stdout();
@@ -31,15 +51,14 @@ pub fn cmd_mv(
     copy_rename_io(copy_config, &root, old_path, new_path, io_streams)
         .map_err(|e| (ParsExitCode::Error.into(), e))?;
 
-    let commit =
-        GitCommit::new(&root, CommitType::Rename((old_path.to_string(), new_path.to_string())));
-    debug!("cmd_mv: commit {commit}");
-    add_and_commit(
-        &config.executable_config.git_executable,
+    let commit = GitCommit::new(
         &root,
-        commit.get_commit_msg().as_str(),
-    )
-    .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+        CommitType::Rename((old_path.to_string(), new_path.to_string())),
+        &config.git_config,
+    );
+    debug!("cmd_mv: commit {commit}");
+    add_and_commit(config, &root, commit.get_commit_msg().as_str())
+        .map_err(|e| (ParsExitCode::GitError.into(), e))?;
 
     Ok(())
 }
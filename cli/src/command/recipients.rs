@@ -0,0 +1,271 @@
+use std::io::{self, BufReader};
+use std::path::Path;
+use std::slice;
+
+use anyhow::{anyhow, Error, Result};
+use log::debug;
+use pars_core::config::cli::ParsConfig;
+use pars_core::git::add_and_commit;
+use pars_core::git::commit::{CommitType, GitCommit};
+use pars_core::operation::generate::{generate_io, IOStreams, PasswdGenerateConfig};
+use pars_core::operation::init::InitReport;
+use pars_core::operation::public_keys::{
+    export_public_keys, import_bundled_keys, is_fingerprint_of, missing_bundled_keys,
+};
+use pars_core::operation::recipients::{add_recipients, list_recipients, remove_recipient};
+use pars_core::pgp::groups::{expand_recipients, GROUP_PREFIX};
+use pars_core::pgp::key_management::{import_key_file, lsign_key, read_key_file};
+use pars_core::pgp::PGPClient;
+
+use crate::constants::{ParsExitCode, SECRET_EXTENSION};
+use crate::util::{confirm, unwrap_root_path};
+
+pub fn cmd_recipients_list(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    path: Option<&str>,
+) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+    let (gpg_id, recipients) = list_recipients(&root, &root.join(path.unwrap_or_default()))
+        .map_err(|e| (ParsExitCode::Error.into(), e))?;
+
+    println!("Recipients from '{}':", gpg_id.strip_prefix(&root).unwrap_or(&gpg_id).display());
+    let pgp_exe = &config.executable_config.pgp_executable;
+    for id in recipients {
+        if !id.starts_with(GROUP_PREFIX) {
+            print_recipient(pgp_exe, &id, "  ");
+            continue;
+        }
+        match expand_recipients(&root, slice::from_ref(&id), &config.groups) {
+            Ok(members) => {
+                println!("  {id}");
+                for member in members {
+                    print_recipient(pgp_exe, &member, "    ");
+                }
+            }
+            Err(e) => println!("  {id}  ({e})"),
+        }
+    }
+    Ok(())
+}
+
+fn print_recipient(pgp_exe: &str, id: &str, indent: &str) {
+    match PGPClient::new(pgp_exe, &[id]) {
+        Ok(client) => println!(
+            "{indent}{id}  {} <{}>",
+            client.get_usernames().join(", "),
+            client.get_email().join(", ")
+        ),
+        Err(_) => println!("{indent}{id}  (not in the keyring)"),
+    }
+}
+
+pub fn cmd_recipients_add(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    path: Option<&str>,
+    keys: &[String],
+    yes: bool,
+) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+    let pgp_exe = &config.executable_config.pgp_executable;
+
+    let mut ids = Vec::with_capacity(keys.len());
+    for key in keys {
+        if Path::new(key).is_file() {
+            ids.push(import_checked_key(pgp_exe, Path::new(key), yes)?);
+        } else {
+            ids.push(key.clone());
+        }
+    }
+
+    let report = add_recipients(config, &root, path, &ids)
+        .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    commit_recipients(config, &root, path, &report)
+}
+
+/// Import the single key of `file` once its fingerprint was shown and confirmed, then certify
+/// it locally: adding the key is the statement that it was checked, gpg refuses unverified keys
+fn import_checked_key(pgp_exe: &str, file: &Path, yes: bool) -> Result<String, (i32, Error)> {
+    let found = read_key_file(pgp_exe, file).map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    let (fpr, uids) = match found.as_slice() {
+        [key] => key,
+        [] => {
+            return Err((ParsExitCode::PGPError.into(), anyhow!("No public key in {file:?}")));
+        }
+        keys => {
+            return Err((
+                ParsExitCode::PGPError.into(),
+                anyhow!("{file:?} holds {} keys, pass a file with only the one to add", keys.len()),
+            ));
+        }
+    };
+
+    println!("Key {fpr} in {file:?}:");
+    for uid in uids {
+        println!("  {uid}");
+    }
+    if !yes && !confirm("Is this the fingerprint its owner gave you? Import and locally sign it?") {
+        return Err((ParsExitCode::Error.into(), anyhow!("Key {fpr} not added")));
+    }
+    let imported =
+        import_key_file(pgp_exe, file).map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    if imported.as_slice() != slice::from_ref(fpr) {
+        return Err((
+            ParsExitCode::PGPError.into(),
+            anyhow!("{file:?} changed while it was imported, check it and try again"),
+        ));
+    }
+    lsign_key(pgp_exe, fpr).map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    println!("Imported and locally signed key {fpr}");
+    Ok(fpr.clone())
+}
+
+pub fn cmd_recipients_remove(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    path: Option<&str>,
+    id: &str,
+) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+
+    let (report, exposed) = remove_recipient(config, &root, path, id)
+        .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    commit_recipients(config, &root, path, &report)?;
+
+    if exposed.is_empty() {
+        return Ok(());
+    }
+    eprintln!(
+        "Warning: '{id}' can still read the old versions of these {} entries in the git history:",
+        exposed.len()
+    );
+    for entry in &exposed {
+        eprintln!("  {entry}");
+    }
+    if confirm("Change their passwords now?") {
+        rotate_passwords(config, &root, &exposed)?;
+    } else {
+        eprintln!("Change them later with `pars generate --in-place <entry>`");
+    }
+    Ok(())
+}
+
+/// Import the keys of `.public-keys/` the keyring lacks. A key only named by a user id in the
+/// `.gpg-id` needs its fingerprint in `fingerprints`, as confirmed by its owner.
+pub fn cmd_recipients_import(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    fingerprints: &[String],
+) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+    let pgp_exe = &config.executable_config.pgp_executable;
+    let keys = missing_bundled_keys(pgp_exe, &root, &config.groups)
+        .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    if keys.is_empty() {
+        println!("Every recipient with a key in .public-keys/ is in the keyring");
+        return Ok(());
+    }
+
+    let (keys, unconfirmed): (Vec<_>, Vec<_>) = keys.into_iter().partition(|key| {
+        key.pinned || fingerprints.iter().any(|fpr| is_fingerprint_of(fpr, &key.fpr))
+    });
+    import_bundled_keys(pgp_exe, &keys).map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    for key in &keys {
+        println!("Imported and locally signed key {} for '{}'", key.fpr, key.id);
+    }
+    if unconfirmed.is_empty() {
+        return Ok(());
+    }
+    for key in &unconfirmed {
+        eprintln!("Skipped key {} ({}) for '{}'", key.fpr, key.uids.join(", "), key.id);
+    }
+    Err((
+        ParsExitCode::Error.into(),
+        anyhow!(
+            "{} key(s) are only named by a user id, confirm their fingerprints with their owners \
+             and pass them with --fingerprint",
+            unconfirmed.len()
+        ),
+    ))
+}
+
+/// Commit the new `.gpg-id` with the re-encrypted entries and the updated `.public-keys/`,
+/// then report what could not be done
+fn commit_recipients(
+    config: &ParsConfig,
+    root: &Path,
+    path: Option<&str>,
+    report: &InitReport,
+) -> Result<(), (i32, Error)> {
+    let exported =
+        export_public_keys(&config.executable_config.pgp_executable, root, &config.groups)
+            .map_err(|e| (ParsExitCode::PGPError.into(), e))?;
+    if report.is_empty() && exported.is_empty() {
+        return Ok(());
+    }
+    let (_, recipients) = list_recipients(root, &root.join(path.unwrap_or_default()))
+        .map_err(|e| (ParsExitCode::Error.into(), e))?;
+    let commit = GitCommit::new(root, CommitType::Init(recipients), &config.git_config);
+    debug!("cmd_recipients: commit {commit}");
+    add_and_commit(config, root, commit.get_commit_msg().as_str())
+        .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+
+    if !report.reencrypted.is_empty() {
+        println!("Re-encrypted {} entries", report.reencrypted.len());
+    }
+    if !report.failed.is_empty() {
+        for (entry, e) in &report.failed {
+            eprintln!("Failed to re-encrypt '{entry}': {e}");
+        }
+        return Err((
+            ParsExitCode::PGPError.into(),
+            anyhow!(
+                "{} entries are left to re-encrypt, run `pars init` with the same keys to retry",
+                report.failed.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Replace the first line of every entry with a generated password, as a single commit
+fn rotate_passwords(
+    config: &ParsConfig,
+    root: &Path,
+    entries: &[String],
+) -> Result<(), (i32, Error)> {
+    let gen_cfg = PasswdGenerateConfig {
+        no_symbols: false,
+        in_place: true,
+        force: false,
+        pass_length: config.feature_config.generated_length,
+        character_set: config.feature_config.character_set.clone(),
+        extension: SECRET_EXTENSION.to_string(),
+        pgp_executable: config.executable_config.pgp_executable.clone(),
+        pgp_options: config.executable_config.pgp_options.clone(),
+        groups: config.groups.clone(),
+    };
+    let mut stdin = BufReader::new(io::empty());
+    let mut io_streams =
+        IOStreams { in_s: &mut stdin, out_s: &mut io::sink(), err_s: &mut io::stderr() };
+
+    let mut changes = Vec::with_capacity(entries.len());
+    let mut result = Ok(());
+    for entry in entries {
+        if let Err(e) = generate_io(root, entry, &gen_cfg, &mut io_streams) {
+            result = Err((ParsExitCode::Error.into(), anyhow!("Failed to change '{entry}': {e}")));
+            break;
+        }
+        println!("Changed the password of '{entry}'");
+        changes.push(CommitType::Generate(entry.clone()));
+    }
+
+    if !changes.is_empty() {
+        let commit = GitCommit::new(root, CommitType::Batch(changes), &config.git_config);
+        debug!("cmd_recipients: commit {commit}");
+        add_and_commit(config, root, commit.get_commit_msg().as_str())
+            .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+    }
+    result
+}
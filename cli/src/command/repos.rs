@@ -0,0 +1,12 @@
+use anyhow::{Error, Result};
+use pars_core::config::cli::ParsConfig;
+
+pub fn cmd_repos_list(config: &ParsConfig) -> Result<(), (i32, Error)> {
+    let repos = config.path_config.repo_list();
+    let width = repos.iter().map(|(name, _)| name.len()).max().unwrap_or_default();
+    for (name, path) in repos {
+        let marker = if config.path_config.is_default(name) { '*' } else { ' ' };
+        println!("{marker} {name:<width$}  {}", path.display());
+    }
+    Ok(())
+}
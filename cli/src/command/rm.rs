@@ -5,10 +5,11 @@ use log::debug;
 use pars_core::config::cli::ParsConfig;
 use pars_core::git::add_and_commit;
 use pars_core::git::commit::{CommitType, GitCommit};
-use pars_core::operation::remove::remove_io;
+use pars_core::operation::remove::{remove_io, trash_io};
 
+use crate::command::trash::purge_expired;
 use crate::constants::ParsExitCode;
-use crate::util::unwrap_root_path;
+use crate::util::unwrap_pass_path;
 
 pub fn cmd_rm(
     config: &ParsConfig,
@@ -17,9 +18,10 @@ pub fn cmd_rm(
     force: bool,
     pass_name: &str,
 ) -> Result<(), (i32, Error)> {
-    let root = unwrap_root_path(base_dir, config);
+    let (root, pass_name) = unwrap_pass_path(base_dir, config, pass_name);
 
-    remove_io(
+    let remove = if config.feature_config.trash_retention_days == 0 { remove_io } else { trash_io };
+    remove(
         &root,
         pass_name,
         recursive,
@@ -30,14 +32,12 @@ pub fn cmd_rm(
     )
     .map_err(|e| (ParsExitCode::Error.into(), e))?;
 
-    let commit = GitCommit::new(&root, CommitType::Delete(pass_name.to_string()));
+    let commit =
+        GitCommit::new(&root, CommitType::Delete(pass_name.to_string()), &config.git_config);
     debug!("cmd_rm: commit {commit}");
-    add_and_commit(
-        &config.executable_config.git_executable,
-        &root,
-        commit.get_commit_msg().as_str(),
-    )
-    .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+    add_and_commit(config, &root, commit.get_commit_msg().as_str())
+        .map_err(|e| (ParsExitCode::GitError.into(), e))?;
 
+    purge_expired(config, &root);
     Ok(())
 }
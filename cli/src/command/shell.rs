@@ -1,11 +1,17 @@
+use std::env;
+use std::path::Path;
 use std::process::{Command, Stdio};
 
 use anyhow::{anyhow, Error, Result};
+use log::debug;
 use pars_core::config::cli::ParsConfig;
+use pars_core::operation::extension::{extension_command, find_extension, ExtensionEnv};
 
 use crate::constants::ParsExitCode;
 use crate::util::unwrap_root_path;
 
+/// Run `pars <name> args...`: an extension called `name` if there is one, otherwise `name`
+/// itself as a command inside the store directory
 pub fn cmd_shell(
     config: &ParsConfig,
     base_dir: Option<&str>,
@@ -20,9 +26,27 @@ pub fn cmd_shell(
     let command = &args[0];
     let command_args = &args[1..];
 
-    let status = Command::new(command)
-        .args(command_args)
-        .current_dir(&root)
+    let mut cmd = match find_extension(&root, command, &config.extension_config) {
+        Some(extension) => {
+            debug!("Run extension {extension:?}");
+            let pars_bin =
+                env::current_exe().map_err(|e| (ParsExitCode::Error.into(), e.into()))?;
+            let ext_env = ExtensionEnv {
+                store_dir: &root,
+                repo: &repo_name(base_dir, config, &root),
+                pars_bin: &pars_bin,
+                pgp_executable: &config.executable_config.pgp_executable,
+            };
+            extension_command(&extension, command, &ext_env, command_args)
+        }
+        None => {
+            let mut cmd = Command::new(command);
+            cmd.args(command_args).current_dir(&root);
+            cmd
+        }
+    };
+
+    let status = cmd
         .stdin(Stdio::inherit())
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
@@ -40,3 +64,14 @@ pub fn cmd_shell(
         ))
     }
 }
+
+/// Name of the repo at `root`, its path when it is not a named one
+fn repo_name(base_dir: Option<&str>, config: &ParsConfig, root: &Path) -> String {
+    config
+        .path_config
+        .repo_list()
+        .into_iter()
+        .find(|(name, path)| base_dir == Some(*name) || path == root)
+        .map(|(name, _)| name.to_string())
+        .unwrap_or_else(|| root.display().to_string())
+}
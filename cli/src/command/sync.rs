@@ -0,0 +1,21 @@
+use anyhow::{Error, Result};
+use pars_core::config::cli::ParsConfig;
+use pars_core::git::sync::{sync, SyncConfig};
+
+use crate::constants::ParsExitCode;
+use crate::util::unwrap_root_path;
+
+pub fn cmd_sync(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    verify: bool,
+    no_push: bool,
+) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+
+    let sync_cfg =
+        SyncConfig { verify: verify || config.git_config.verify_signatures, push: !no_push };
+    sync(config, &root, &sync_cfg).map_err(|e| (ParsExitCode::GitError.into(), e))?;
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Error};
+use log::{debug, warn};
+use pars_core::config::cli::ParsConfig;
+use pars_core::git::add_and_commit;
+use pars_core::git::commit::{CommitType, GitCommit};
+use pars_core::operation::trash::{empty_trash, list_trash, restore_from_trash};
+
+use crate::constants::ParsExitCode;
+use crate::util::{confirm, unwrap_root_path};
+
+pub fn cmd_trash_list(config: &ParsConfig, base_dir: Option<&str>) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+    purge_expired(config, &root);
+    let items = list_trash(&root).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    if items.is_empty() {
+        println!("The trash is empty");
+        return Ok(());
+    }
+    for item in items {
+        let suffix = if item.is_dir() { "/" } else { "" };
+        println!(
+            "{}  {}  {}{suffix}",
+            item.id,
+            item.deleted_at.format("%Y-%m-%d %H:%M UTC"),
+            item.pass_name()
+        );
+    }
+    Ok(())
+}
+
+pub fn cmd_trash_restore(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    target: &str,
+) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+    let item = restore_from_trash(&root, target).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    println!("Restored '{}'", item.pass_name());
+
+    let commit =
+        GitCommit::new(&root, CommitType::Insert(item.pass_name().to_string()), &config.git_config);
+    debug!("cmd_trash_restore: commit {commit}");
+    add_and_commit(config, &root, commit.get_commit_msg().as_str())
+        .map_err(|e| (ParsExitCode::GitError.into(), e))?;
+    Ok(())
+}
+
+pub fn cmd_trash_empty(
+    config: &ParsConfig,
+    base_dir: Option<&str>,
+    older_than: Option<u32>,
+    force: bool,
+) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+    let question = match older_than {
+        Some(days) => format!("Delete what was removed more than {days} days ago for good?"),
+        None => "Delete everything in the trash for good?".to_string(),
+    };
+    if !force && !confirm(&question) {
+        return Err((ParsExitCode::Error.into(), anyhow!("Nothing deleted")));
+    }
+    let purged = empty_trash(&root, older_than).map_err(|e| (ParsExitCode::Error.into(), e))?;
+    println!("Deleted {} item(s) from the trash", purged.len());
+    Ok(())
+}
+
+/// Delete what stayed in the trash longer than `trash_retention_days`
+pub(crate) fn purge_expired(config: &ParsConfig, root: &Path) {
+    let days = config.feature_config.trash_retention_days;
+    if days == 0 {
+        return;
+    }
+    match empty_trash(root, Some(days)) {
+        Ok(purged) if !purged.is_empty() => {
+            eprintln!("Purged {} item(s) older than {days} days from the trash", purged.len());
+        }
+        Ok(_) => {}
+        Err(e) => warn!("Failed to purge the trash of {root:?}: {e}"),
+    }
+}
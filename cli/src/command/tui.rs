@@ -0,0 +1,11 @@
+use anyhow::{Error, Result};
+use pars_core::config::cli::ParsConfig;
+
+use crate::constants::ParsExitCode;
+use crate::tui;
+use crate::util::unwrap_root_path;
+
+pub fn cmd_tui(config: &ParsConfig, base_dir: Option<&str>) -> Result<(), (i32, Error)> {
+    let root = unwrap_root_path(base_dir, config);
+    tui::run(config, root).map_err(|e| (ParsExitCode::Error.into(), e))
+}
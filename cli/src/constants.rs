@@ -2,7 +2,6 @@ use log::LevelFilter;
 
 pub const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::Info;
 pub const SECRET_EXTENSION: &str = "gpg";
-pub const DEFAULT_PASS_LENGTH: usize = 20;
 
 #[repr(i32)]
 pub enum ParsExitCode {
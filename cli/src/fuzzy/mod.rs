@@ -1,3 +1,194 @@
+use std::borrow::Cow;
+use std::io::{self, BufRead, IsTerminal, Write};
 use std::path::Path;
 
-pub(crate) fn fuzzy_display(root: &Path, target_str: &str) {}
+use anyhow::{anyhow, Result};
+use nucleo_matcher::pattern::{CaseMatching, Normalization, Pattern};
+use nucleo_matcher::{Config, Matcher};
+use pars_core::config::cli::ParsConfig;
+use pars_core::util::tree::{tree_entries, FilterType, TreeConfig};
+
+use crate::constants::SECRET_EXTENSION;
+
+/// Candidates offered by the selector
+const MAX_CHOICES: usize = 10;
+/// The best match is used right away when it scores at least this many times the runner-up
+const CLEAR_MARGIN: u32 = 2;
+
+/// Rank `candidates` against `pattern`, best match first. Candidates that do not match are
+/// dropped, an empty pattern keeps all of them in their original order.
+pub(crate) fn rank<'a, S: AsRef<str>>(candidates: &'a [S], pattern: &str) -> Vec<(&'a S, u32)> {
+    let mut matcher = Matcher::new(Config::DEFAULT.match_paths());
+    Pattern::parse(pattern, CaseMatching::Smart, Normalization::Smart)
+        .match_list(candidates, &mut matcher)
+}
+
+/// Resolve `pass_name` to an entry of the store at `root` when it does not exist.
+///
+/// A unique or clear best match is used right away, otherwise the ranked candidates are offered
+/// in a selector. Without a terminal to ask on, those candidates are reported in the error.
+/// Names ending with `/` are folders and never resolved.
+pub(crate) fn fuzzy_resolve<'a>(
+    config: &ParsConfig,
+    root: &Path,
+    pass_name: &'a str,
+) -> Result<Cow<'a, str>> {
+    let stdin = io::stdin();
+    let input = if stdin.is_terminal() { Some(stdin.lock()) } else { None };
+    resolve_with(config, root, pass_name, input, &mut io::stderr())
+}
+
+fn resolve_with<'a, R: BufRead, W: Write>(
+    config: &ParsConfig,
+    root: &Path,
+    pass_name: &'a str,
+    input: Option<R>,
+    out_s: &mut W,
+) -> Result<Cow<'a, str>> {
+    let exists = root.join(format!("{pass_name}.{SECRET_EXTENSION}")).is_file()
+        || root.join(pass_name).is_dir();
+    if pass_name.is_empty()
+        || pass_name.ends_with('/')
+        || exists
+        || !config.feature_config.fuzzy_search
+    {
+        return Ok(Cow::Borrowed(pass_name));
+    }
+
+    let tree_cfg =
+        TreeConfig { root, target: "", filter_type: FilterType::Disable, filters: Vec::new() };
+    let names: Vec<String> =
+        tree_entries(&tree_cfg)?.into_iter().filter(|e| !e.is_dir).map(|e| e.path).collect();
+    let ranked = rank(&names, pass_name);
+    let candidates: Vec<&str> = match ranked.as_slice() {
+        [] => return Ok(Cow::Borrowed(pass_name)),
+        [(best, _)] => return auto_select(out_s, pass_name, best),
+        [(best, score), (_, next), ..] if *score >= next.saturating_mul(CLEAR_MARGIN) => {
+            return auto_select(out_s, pass_name, best);
+        }
+        ranked => ranked.iter().take(MAX_CHOICES).map(|(name, _)| name.as_str()).collect(),
+    };
+
+    let Some(mut input) = input else {
+        return Err(anyhow!(
+            "'{pass_name}' is not in the password store, did you mean: {}",
+            candidates.join(", ")
+        ));
+    };
+
+    fuzzy_display(out_s, pass_name, &candidates)?;
+    let mut answer = String::new();
+    input.read_line(&mut answer)?;
+    match answer.trim().parse::<usize>() {
+        Ok(choice) if (1..=candidates.len()).contains(&choice) => {
+            Ok(Cow::Owned(candidates[choice - 1].to_string()))
+        }
+        _ => Err(anyhow!("No entry selected for '{pass_name}'")),
+    }
+}
+
+/// Use the clear best match, telling which entry it is
+fn auto_select<'a, W: Write>(out_s: &mut W, pass_name: &str, best: &str) -> Result<Cow<'a, str>> {
+    writeln!(out_s, "'{pass_name}' is not in the password store, using '{best}'")?;
+    Ok(Cow::Owned(best.to_string()))
+}
+
+/// Print the numbered candidates and the prompt of the selector
+pub(crate) fn fuzzy_display<W: Write>(
+    out_s: &mut W,
+    pass_name: &str,
+    candidates: &[&str],
+) -> Result<()> {
+    writeln!(out_s, "'{pass_name}' is not in the password store, matching entries:")?;
+    for (idx, name) in candidates.iter().enumerate() {
+        writeln!(out_s, "{:>3}) {name}", idx + 1)?;
+    }
+    write!(out_s, "Select an entry [1-{}]: ", candidates.len())?;
+    out_s.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn example_store() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["github.gpg", "gitlab.gpg", "bank/checking.gpg", "mail/work.gpg"] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        dir
+    }
+
+    fn resolve(root: &Path, pass_name: &str, answer: Option<&str>) -> (Result<String>, String) {
+        let config = ParsConfig::default();
+        let mut out = Vec::new();
+        let res = resolve_with(&config, root, pass_name, answer.map(Cursor::new), &mut out)
+            .map(Cow::into_owned);
+        (res, String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn rank_test() {
+        let names = ["github", "gitlab", "bank/checking"];
+        let ranked: Vec<&str> = rank(&names, "gthb").into_iter().map(|(name, _)| *name).collect();
+        assert_eq!(ranked, vec!["github"]);
+
+        let ranked = rank(&names, "git");
+        assert_eq!(ranked.len(), 2);
+        assert!(ranked.iter().all(|(name, _)| name.starts_with("git")));
+
+        let all: Vec<&str> = rank(&names, "").into_iter().map(|(name, _)| *name).collect();
+        assert_eq!(all, names);
+        assert!(rank(&names, "zzz").is_empty());
+    }
+
+    #[test]
+    fn resolve_existing_test() {
+        let dir = example_store();
+        for name in ["github", "bank", "bank/", "", "zzz"] {
+            let (res, out) = resolve(dir.path(), name, None);
+            assert_eq!(res.unwrap(), name);
+            assert!(out.is_empty());
+        }
+
+        let mut config = ParsConfig::default();
+        config.feature_config.fuzzy_search = false;
+        let res = resolve_with(&config, dir.path(), "gthb", None::<Cursor<&str>>, &mut Vec::new());
+        assert_eq!(res.unwrap(), "gthb");
+    }
+
+    #[test]
+    fn resolve_best_test() {
+        let dir = example_store();
+        for answer in [None, Some("")] {
+            let (res, out) = resolve(dir.path(), "gthb", answer);
+            assert_eq!(res.unwrap(), "github");
+            assert_eq!(out, "'gthb' is not in the password store, using 'github'\n");
+        }
+    }
+
+    #[test]
+    fn resolve_asks_test() {
+        let dir = example_store();
+
+        let (res, _) = resolve(dir.path(), "git", None);
+        let err = res.unwrap_err().to_string();
+        assert!(err.starts_with("'git' is not in the password store, did you mean: "), "{err}");
+        assert!(err.contains("github") && err.contains("gitlab"), "{err}");
+
+        let (res, out) = resolve(dir.path(), "git", Some("2\n"));
+        assert!(out.contains("  1) ") && out.contains("  2) "), "{out}");
+        let picked = res.unwrap();
+        assert!(out.contains(&format!("  2) {picked}")), "{out}");
+        let (res, _) = resolve(dir.path(), "git", Some("3\n"));
+        assert!(res.is_err());
+        let (res, _) = resolve(dir.path(), "git", Some("\n"));
+        assert_eq!(res.unwrap_err().to_string(), "No entry selected for 'git'");
+    }
+}
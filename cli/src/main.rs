@@ -2,18 +2,21 @@ mod command;
 mod constants;
 mod fuzzy;
 mod parser;
+mod tui;
 mod util;
 
 use std::env;
-use std::path::PathBuf;
+use std::path::Path;
 
 use clap::Parser;
 use constants::{ParsExitCode, DEFAULT_LOG_LEVEL};
 use log::debug;
-use pars_core::config::cli::{handle_env_config, load_config, ParsConfig};
+use pars_core::config::cli::ParsConfig;
+use pars_core::config::layered::LayeredConfig;
 use pars_core::constants::env_variables::{CONFIG_PATH_ENV, LOG_LEVEL_VAR};
-use pars_core::util::fs_util::default_config_path;
+use pars_core::util::fs_util::{default_config_path, set_umask};
 use pars_core::util::log::{init_logger, set_log_level};
+use parser::sub_command::{ConfigCommands, SubCommands};
 use parser::CliParser;
 
 fn main() {
@@ -77,24 +80,54 @@ fn fix_args(raw: Vec<String>) -> Vec<String> {
     out
 }
 
-fn process_cli(config_path: &str) {
-    let config = if PathBuf::from(&config_path).exists() {
-        match load_config(config_path) {
-            Ok(config) => handle_env_config(config),
-            Err(e) => {
-                eprintln!("Failed to load config file '{config_path}': {e}");
-                std::process::exit(ParsExitCode::Error.into());
-            }
+fn repairs_config(cli_args: &CliParser) -> bool {
+    matches!(
+        cli_args.command,
+        Some(SubCommands::Config {
+            command: ConfigCommands::Edit
+                | ConfigCommands::Validate { .. }
+                | ConfigCommands::Init { .. }
+        }) | Some(SubCommands::Doctor)
+    )
+}
+
+/// The umask of the config, the only setting that is process state rather than passed on
+fn apply_config(config: &ParsConfig) {
+    if !config.feature_config.umask.is_empty() {
+        if let Err(e) = set_umask(&config.feature_config.umask) {
+            eprintln!("{e}");
         }
-    } else {
-        ParsConfig::default()
-    };
+    }
+}
 
+fn process_cli(config_path: &str) {
     let raw_args: Vec<String> = env::args().collect();
     let args = fix_args(raw_args);
     let cli_args = CliParser::parse_from(args);
 
-    if let Err((code, e)) = parser::handle_cli(config, cli_args) {
+    // The repo given with -R decides which `.pars.toml` is applied
+    let mut layered =
+        match LayeredConfig::load(Path::new(config_path), cli_args.base_dir.as_deref()) {
+            Ok(layered) => layered,
+            // These commands are how a broken config gets diagnosed and fixed
+            Err(e) if repairs_config(&cli_args) => {
+                eprintln!("Failed to load config: {e}");
+                let mut layered = LayeredConfig::defaults(Path::new(config_path));
+                layered.load_error = Some(e.to_string());
+                layered
+            }
+            Err(e) => {
+                eprintln!("Failed to load config: {e}");
+                std::process::exit(ParsExitCode::Error.into());
+            }
+        };
+    if let Err(e) = layered.apply_overrides(&cli_args.options) {
+        eprintln!("Invalid option: {e}");
+        std::process::exit(ParsExitCode::InvalidArgs.into());
+    }
+    apply_config(&layered.config);
+
+    if let Err((code, e)) = parser::handle_cli(&layered, cli_args) {
         eprintln!("{e}");
         debug!("Error: {e:?}");
         std::process::exit(code);
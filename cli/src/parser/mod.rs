@@ -1,14 +1,18 @@
 pub(crate) mod sub_command;
 
 use std::env;
+use std::path::PathBuf;
 
 use anyhow::{Error, Result};
 use clap::Parser;
 use pars_core::config::cli::ParsConfig;
-use sub_command::SubCommands;
+use pars_core::config::layered::LayeredConfig;
+use sub_command::{
+    BackupCommands, ConfigCommands, RecipientsCommands, ReposCommands, SubCommands, TrashCommands,
+};
 
 use crate::command;
-use crate::util::{to_relative_path, to_relative_path_opt};
+use crate::util::{offer_key_import, to_relative_path, to_relative_path_opt, unwrap_root_path};
 
 #[derive(Parser)]
 #[command( name = "pars",
@@ -35,60 +39,90 @@ pub struct CliParser {
     #[arg(trailing_var_arg = true)]
     pub args: Vec<String>,
 
-    #[arg(short = 'R', long = "repo", global = true)]
+    #[arg(short = 'R', long = "repo", global = true, value_name = "name|path")]
     pub base_dir: Option<String>,
+
+    #[arg(
+        short = 'O',
+        long = "option",
+        global = true,
+        value_name = "key=value",
+        help = "Override a config key for this run, e.g. -O feature_config.clip_time=10"
+    )]
+    pub options: Vec<String>,
 }
 
-pub fn handle_cli(config: ParsConfig, cli_args: CliParser) -> Result<(), (i32, Error)> {
+pub fn handle_cli(layered: &LayeredConfig, cli_args: CliParser) -> Result<(), (i32, Error)> {
+    let config = &layered.config;
+    if let Some(root) = key_import_store(config, &cli_args) {
+        offer_key_import(config, &root);
+    }
     match cli_args.command {
-        Some(SubCommands::Init { path, gpg_ids: pgp_id }) => {
+        Some(SubCommands::Init { path, force, gpg_ids: pgp_id }) => {
             command::init::cmd_init(
-                &config,
+                config,
                 cli_args.base_dir.as_deref(),
                 path.as_deref(),
                 &pgp_id,
+                force,
             )?;
         }
-        Some(SubCommands::Grep { search_string }) => {
-            command::grep::cmd_grep(&config, cli_args.base_dir.as_deref(), &search_string)?;
+        Some(SubCommands::Grep { all, search_string }) => {
+            command::grep::cmd_grep(config, cli_args.base_dir.as_deref(), &search_string, all)?;
         }
-        Some(SubCommands::Find { names }) => {
-            command::find::cmd_find(&config, cli_args.base_dir.as_deref(), &names)?;
+        Some(SubCommands::Find { all, names }) => {
+            command::find::cmd_find(config, cli_args.base_dir.as_deref(), &names, all)?;
+        }
+        Some(SubCommands::Ls { clip: _, qrcode: _, all: true, sub_folder }) => {
+            let sub_folder = to_relative_path_opt(sub_folder);
+            command::ls::cmd_ls_all(config, sub_folder.as_deref())?;
         }
-        Some(SubCommands::Ls { clip, qrcode, sub_folder }) => {
+        Some(SubCommands::Ls { clip, qrcode, all: false, sub_folder }) => {
             let sub_folder = to_relative_path_opt(sub_folder);
             command::ls::cmd_ls(
-                &config,
+                config,
                 cli_args.base_dir.as_deref(),
                 clip,
                 qrcode,
                 sub_folder.as_deref(),
+                false,
+            )?;
+        }
+        Some(SubCommands::Show { output: Some(output), pass_name, .. }) => {
+            let pass_name = to_relative_path_opt(pass_name);
+            command::ls::cmd_show_output(
+                config,
+                cli_args.base_dir.as_deref(),
+                pass_name.as_deref().unwrap_or_default(),
+                &output,
             )?;
         }
-        Some(SubCommands::Show { clip, qrcode, pass_name }) => {
+        Some(SubCommands::Show { clip, qrcode, output: None, pass_name }) => {
             let pass_name = to_relative_path_opt(pass_name);
             command::ls::cmd_ls(
-                &config,
+                config,
                 cli_args.base_dir.as_deref(),
                 clip,
                 qrcode,
                 pass_name.as_deref(),
+                true,
             )?;
         }
-        Some(SubCommands::Insert { pass_name, echo, multiline, force }) => {
+        Some(SubCommands::Insert { pass_name, echo, multiline, file, force }) => {
             let pass_name = to_relative_path(pass_name);
             command::insert::cmd_insert(
-                &config,
+                config,
                 cli_args.base_dir.as_deref(),
                 &pass_name,
                 echo,
                 multiline,
+                file.as_deref(),
                 force,
             )?;
         }
         Some(SubCommands::Edit { target_pass }) => {
             let target_pass = to_relative_path(target_pass);
-            command::edit::cmd_edit(&config, cli_args.base_dir.as_deref(), &target_pass)?;
+            command::edit::cmd_edit(config, cli_args.base_dir.as_deref(), &target_pass)?;
         }
         Some(SubCommands::Generate {
             no_symbols,
@@ -108,54 +142,203 @@ pub fn handle_cli(config: ParsConfig, cli_args: CliParser) -> Result<(), (i32, E
                 pass_name: &pass_name,
                 pass_length,
             };
-            command::generate::cmd_generate(&config, cmd_config)?;
+            command::generate::cmd_generate(config, cmd_config)?;
         }
         Some(SubCommands::Rm { recursive, force, pass_name }) => {
             let pass_name = to_relative_path(pass_name);
             command::rm::cmd_rm(
-                &config,
+                config,
                 cli_args.base_dir.as_deref(),
                 recursive,
                 force,
                 &pass_name,
             )?;
         }
-        Some(SubCommands::Mv { force, old_path, new_path }) => {
-            let old_path = to_relative_path(old_path);
-            let new_path = to_relative_path(new_path);
-            command::mv::cmd_mv(
-                &config,
+        Some(SubCommands::Trash { command: TrashCommands::List }) => {
+            command::trash::cmd_trash_list(config, cli_args.base_dir.as_deref())?;
+        }
+        Some(SubCommands::Trash { command: TrashCommands::Restore { target } }) => {
+            let target = to_relative_path(target);
+            command::trash::cmd_trash_restore(config, cli_args.base_dir.as_deref(), &target)?;
+        }
+        Some(SubCommands::Trash { command: TrashCommands::Empty { older_than, force } }) => {
+            command::trash::cmd_trash_empty(
+                config,
                 cli_args.base_dir.as_deref(),
+                older_than,
                 force,
-                &old_path,
-                &new_path,
             )?;
         }
+        Some(SubCommands::Mv { force, old_path, new_path }) => {
+            let old_path = to_relative_path(old_path);
+            let new_path = to_relative_path(new_path);
+            command::mv::cmd_mv(config, cli_args.base_dir.as_deref(), force, &old_path, &new_path)?;
+        }
         Some(SubCommands::Cp { force, old_path, new_path }) => {
             let old_path = to_relative_path(old_path);
             let new_path = to_relative_path(new_path);
-            command::cp::cmd_cp(
-                &config,
+            command::cp::cmd_cp(config, cli_args.base_dir.as_deref(), force, &old_path, &new_path)?;
+        }
+        Some(SubCommands::Git { args }) => {
+            command::git::cmd_git(config, cli_args.base_dir.as_deref(), &args)?;
+        }
+        Some(SubCommands::Sync { verify, no_push }) => {
+            command::sync::cmd_sync(config, cli_args.base_dir.as_deref(), verify, no_push)?;
+        }
+        Some(SubCommands::Repos { command: ReposCommands::List }) => {
+            command::repos::cmd_repos_list(config)?;
+        }
+        Some(SubCommands::Recipients { command: RecipientsCommands::List { path } }) => {
+            let path = to_relative_path_opt(path);
+            command::recipients::cmd_recipients_list(
+                config,
+                cli_args.base_dir.as_deref(),
+                path.as_deref(),
+            )?;
+        }
+        Some(SubCommands::Recipients { command: RecipientsCommands::Add { path, yes, keys } }) => {
+            let path = to_relative_path_opt(path);
+            command::recipients::cmd_recipients_add(
+                config,
+                cli_args.base_dir.as_deref(),
+                path.as_deref(),
+                &keys,
+                yes,
+            )?;
+        }
+        Some(SubCommands::Recipients { command: RecipientsCommands::Remove { path, id } }) => {
+            let path = to_relative_path_opt(path);
+            command::recipients::cmd_recipients_remove(
+                config,
+                cli_args.base_dir.as_deref(),
+                path.as_deref(),
+                &id,
+            )?;
+        }
+        Some(SubCommands::Recipients { command: RecipientsCommands::Import { fingerprints } }) => {
+            command::recipients::cmd_recipients_import(
+                config,
                 cli_args.base_dir.as_deref(),
+                &fingerprints,
+            )?;
+        }
+        Some(SubCommands::Backup {
+            command: BackupCommands::Create { git, recipient, force, output },
+        }) => {
+            command::backup::cmd_backup_create(
+                config,
+                cli_args.base_dir.as_deref(),
+                &output,
+                recipient.as_deref(),
+                git,
                 force,
-                &old_path,
-                &new_path,
             )?;
         }
-        Some(SubCommands::Git { args }) => {
-            command::git::cmd_git(&config, cli_args.base_dir.as_deref(), &args)?;
+        Some(SubCommands::Backup { command: BackupCommands::Restore { file } }) => {
+            command::backup::cmd_backup_restore(config, cli_args.base_dir.as_deref(), &file)?;
+        }
+        Some(SubCommands::Config { command: ConfigCommands::Show { origin } }) => {
+            command::config::cmd_config_show(layered, origin)?;
+        }
+        Some(SubCommands::Config { command: ConfigCommands::Get { key } }) => {
+            command::config::cmd_config_get(layered, &key)?;
+        }
+        Some(SubCommands::Config { command: ConfigCommands::Set { key, value } }) => {
+            command::config::cmd_config_set(&layered.user_path, &key, &value)?;
+        }
+        Some(SubCommands::Config { command: ConfigCommands::Unset { key } }) => {
+            command::config::cmd_config_unset(&layered.user_path, &key)?;
+        }
+        Some(SubCommands::Config { command: ConfigCommands::Edit }) => {
+            command::config::cmd_config_edit(config, &layered.user_path)?;
+        }
+        Some(SubCommands::Config { command: ConfigCommands::Validate { file } }) => {
+            command::config::cmd_config_validate(layered, file.as_deref())?;
+        }
+        Some(SubCommands::Config { command: ConfigCommands::Init { force } }) => {
+            command::config::cmd_config_init(&layered.user_path, force)?;
+        }
+        Some(SubCommands::Doctor) => {
+            command::doctor::cmd_doctor(layered, cli_args.base_dir.as_deref())?;
+        }
+        Some(SubCommands::Fsck { decrypt, fix }) => {
+            command::fsck::cmd_fsck(config, cli_args.base_dir.as_deref(), decrypt, fix)?;
+        }
+        Some(SubCommands::Export { path, output, recipient, force, format }) => {
+            let path = to_relative_path_opt(path);
+            let cmd_config = command::export::ExportCommandConfig {
+                base_dir: cli_args.base_dir.as_deref(),
+                path: path.as_deref(),
+                output: output.as_deref(),
+                recipient: recipient.as_deref(),
+                force,
+                format: &format,
+            };
+            command::export::cmd_export(config, cmd_config)?;
+        }
+        Some(SubCommands::Import { path, dry_run, on_conflict, format, file }) => {
+            let path = to_relative_path_opt(path);
+            let cmd_config = command::import::ImportCommandConfig {
+                base_dir: cli_args.base_dir.as_deref(),
+                path: path.as_deref(),
+                dry_run,
+                on_conflict: &on_conflict,
+                format: &format,
+                file: &file,
+            };
+            command::import::cmd_import(config, cmd_config)?;
+        }
+        Some(SubCommands::Menu { field, picker, type_it }) => {
+            command::menu::cmd_menu(
+                config,
+                cli_args.base_dir.as_deref(),
+                field.as_ref().map(Option::as_deref),
+                picker.as_deref(),
+                type_it,
+            )?;
+        }
+        Some(SubCommands::Type { fields, sequence, pass_name }) => {
+            let pass_name = to_relative_path(pass_name);
+            command::autotype::cmd_type(
+                config,
+                cli_args.base_dir.as_deref(),
+                &pass_name,
+                &fields,
+                sequence.as_deref(),
+            )?;
+        }
+        Some(SubCommands::Clip { name }) => {
+            command::clip::cmd_clip(config, name.as_deref())?;
+        }
+        Some(SubCommands::Tui) => {
+            command::tui::cmd_tui(config, cli_args.base_dir.as_deref())?;
         }
         Some(SubCommands::External(args)) => {
-            command::shell::cmd_shell(&config, cli_args.base_dir.as_deref(), &args)?;
+            command::shell::cmd_shell(config, cli_args.base_dir.as_deref(), &args)?;
         }
         None => {
             // If there are trailing arguments, treat them as external shell command
             if !cli_args.args.is_empty() {
-                command::shell::cmd_shell(&config, cli_args.base_dir.as_deref(), &cli_args.args)?;
+                command::shell::cmd_shell(config, cli_args.base_dir.as_deref(), &cli_args.args)?;
             } else {
-                command::ls::cmd_ls(&config, cli_args.base_dir.as_deref(), None, None, None)?;
+                command::ls::cmd_ls(config, cli_args.base_dir.as_deref(), None, None, None, false)?;
             }
         }
     }
     Ok(())
 }
+
+/// Store whose recipients the command sets, the keys missing from `.public-keys/` are offered
+/// then. Looking them up costs a gpg call per recipient, so other commands leave it to
+/// `pars recipients import` and `pars doctor`.
+fn key_import_store(config: &ParsConfig, cli_args: &CliParser) -> Option<PathBuf> {
+    match &cli_args.command {
+        Some(
+            SubCommands::Init { .. }
+            | SubCommands::Recipients {
+                command: RecipientsCommands::Add { .. } | RecipientsCommands::Remove { .. },
+            },
+        ) => Some(unwrap_root_path(cli_args.base_dir.as_deref(), config)),
+        _ => None,
+    }
+}
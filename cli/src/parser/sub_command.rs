@@ -7,16 +7,31 @@ pub enum SubCommands {
         #[arg(short = 'p', long = "path", value_name = "sub-folder")]
         path: Option<String>,
 
+        #[arg(
+            short = 'f',
+            long = "force",
+            help = "Also re-encrypt entries hiding their recipients"
+        )]
+        force: bool,
+
         #[arg(required = true)]
         gpg_ids: Vec<String>,
     },
 
     #[clap(about = "Search for a string in all files, regex is supported")]
-    Grep { search_string: String },
+    Grep {
+        #[arg(short = 'a', long = "all", help = "Search every configured repo")]
+        all: bool,
+
+        search_string: String,
+    },
 
     #[clap(about = "Find a password by name")]
     #[command(alias = "search")]
     Find {
+        #[arg(short = 'a', long = "all", help = "Search every configured repo")]
+        all: bool,
+
         #[arg(required = true)]
         names: Vec<String>,
     },
@@ -43,10 +58,19 @@ pub enum SubCommands {
             num_args = 0..=1
         )]
         qrcode: Option<usize>,
+
+        #[arg(
+            short = 'a',
+            long = "all",
+            help = "List every configured repo",
+            conflicts_with_all = ["clip", "qrcode"]
+        )]
+        all: bool,
+
         sub_folder: Option<String>,
     },
 
-    #[clap(about = "Show a password, optionally clip or qrcode it")]
+    #[clap(about = "Show a password, optionally clip or qrcode it or write it to a file")]
     Show {
         #[arg(
             short = 'c',
@@ -68,10 +92,19 @@ pub enum SubCommands {
         )]
         qrcode: Option<usize>,
 
+        #[arg(
+            short = 'o',
+            long = "output",
+            value_name = "file",
+            conflicts_with_all = ["clip", "qrcode"],
+            help = "Write the entry as it is, binary ones included, to a new file readable only by you"
+        )]
+        output: Option<String>,
+
         pass_name: Option<String>,
     },
 
-    #[clap(about = "Insert a new password")]
+    #[clap(about = "Insert a new password, or the content of a file such as a key")]
     #[command(alias = "add")]
     Insert {
         pass_name: String,
@@ -82,6 +115,14 @@ pub enum SubCommands {
         #[arg(short = 'm', long = "multiline", conflicts_with = "echo")]
         multiline: bool,
 
+        #[arg(
+            long = "file",
+            value_name = "path",
+            conflicts_with_all = ["echo", "multiline"],
+            help = "Encrypt this file as it is, binary ones included"
+        )]
+        file: Option<String>,
+
         #[arg(short = 'f', long = "force")]
         force: bool,
     },
@@ -91,7 +132,7 @@ You can set the editor using the `PARS_EDITOR` environment variable or set it in
     Edit { target_pass: String },
 
     #[clap(
-        about = r#"Generate a new password of pass-length (or 20 if unspecified) with optionally no symbols.
+        about = r#"Generate a new password of pass-length (or the configured length, 20 by default) with optionally no symbols.
 Optionally put it on the clipboard and clear board after 45 seconds.
 Prompt before overwriting existing password unless forced.
 Optionally replace only the first line of an existing file with a new password."#
@@ -115,7 +156,7 @@ Optionally replace only the first line of an existing file with a new password."
     },
 
     #[clap(about = r#"Remove a password or a sub-folder.
-Optionally recursively."#)]
+Optionally recursively. It is kept in the trash for trash_retention_days."#)]
     #[command(alias = "remove", alias = "delete")]
     Rm {
         #[arg(short = 'r', long = "recursive")]
@@ -127,6 +168,12 @@ Optionally recursively."#)]
         pass_name: String,
     },
 
+    #[clap(about = "List, restore or empty what `pars rm` moved to the trash")]
+    Trash {
+        #[command(subcommand)]
+        command: TrashCommands,
+    },
+
     #[clap(about = r#"Move a password or a sub-folder frome old-path to new-path.
 Optionally forcefully, selectively reencrypting."#)]
     #[command(alias = "rename")]
@@ -158,7 +205,334 @@ Optionally forcefully, selectively reencrypting."#)]
         args: Vec<String>,
     },
 
+    #[clap(about = r#"Pull changes from the remote and push local commits.
+Optionally reject incoming commits not signed by an allowed signer."#)]
+    Sync {
+        #[arg(long = "verify")]
+        verify: bool,
+
+        #[arg(long = "no-push")]
+        no_push: bool,
+    },
+
+    #[clap(about = "Manage the configured password stores")]
+    Repos {
+        #[command(subcommand)]
+        command: ReposCommands,
+    },
+
+    #[clap(about = "Manage who can read the store, entries are re-encrypted on every change")]
+    Recipients {
+        #[command(subcommand)]
+        command: RecipientsCommands,
+    },
+
+    #[clap(about = "Back up the store into one verifiable archive, or restore such a backup")]
+    Backup {
+        #[command(subcommand)]
+        command: BackupCommands,
+    },
+
+    #[clap(about = "Inspect the configuration")]
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+
+    #[clap(about = r#"Check gpg, keys, git, clipboard, editor, config and the store for problems.
+Ends with a list of suggested fixes."#)]
+    Doctor,
+
+    #[clap(
+        about = r#"Check that every entry is encrypted to the keys of its .gpg-id, and look for
+leftover backups, broken or recursive symlinks and empty directories."#
+    )]
+    Fsck {
+        #[arg(
+            long = "decrypt",
+            help = "Decrypt every entry instead of only reading its recipients"
+        )]
+        decrypt: bool,
+
+        #[arg(long = "fix", help = "Re-encrypt mismatched entries, restore or delete backups")]
+        fix: bool,
+    },
+
+    #[clap(about = r#"Export the entries of the store or a folder.
+json, csv and keepass-xml hold every password in plain text, bundle is the json encrypted for
+a single recipient."#)]
+    Export {
+        #[arg(
+            short = 'p',
+            long = "path",
+            value_name = "sub-folder",
+            help = "Only export this folder"
+        )]
+        path: Option<String>,
+
+        #[arg(
+            short = 'o',
+            long = "output",
+            value_name = "file",
+            help = "Write to a file, stdout otherwise"
+        )]
+        output: Option<String>,
+
+        #[arg(
+            short = 'r',
+            long = "recipient",
+            value_name = "gpg-id",
+            help = "Recipient of a bundle"
+        )]
+        recipient: Option<String>,
+
+        #[arg(
+            short = 'f',
+            long = "force",
+            help = "Skip the confirmation, replace the output file"
+        )]
+        force: bool,
+
+        #[arg(value_name = "json|csv|bundle|keepass-xml")]
+        format: String,
+    },
+
+    #[clap(about = r#"Import the export of another password manager as a single commit.
+Formats: keepass-xml, keepassxc-csv, bitwarden-json, 1password-csv, chrome-csv, firefox-csv
+and lastpass-csv. Entries get the password line, then login, url and other fields, then notes."#)]
+    Import {
+        #[arg(
+            short = 'p',
+            long = "path",
+            value_name = "sub-folder",
+            help = "Import below this folder"
+        )]
+        path: Option<String>,
+
+        #[arg(short = 'n', long = "dry-run", help = "Show what would be imported, change nothing")]
+        dry_run: bool,
+
+        #[arg(
+            long = "on-conflict",
+            value_name = "abort|skip|overwrite|rename",
+            default_value = "abort",
+            help = "What to do with names that are already taken"
+        )]
+        on_conflict: String,
+
+        format: String,
+
+        #[arg(value_name = "file", help = "The export, - reads stdin")]
+        file: String,
+    },
+
+    #[clap(about = "Browse the password store in an interactive terminal interface")]
+    Tui,
+
+    #[clap(
+        about = r#"Pick an entry with dmenu, rofi, fuzzel, wofi or fzf and copy or type its password.
+Optionally use a field of the entry instead."#
+    )]
+    Menu {
+        #[arg(
+            short = 'f',
+            long = "field",
+            value_name = "name",
+            num_args = 0..=1,
+            help = "Copy the named field, pick one when no name is given"
+        )]
+        field: Option<Option<String>>,
+
+        #[arg(long = "picker", value_name = "command", help = "Override the configured picker")]
+        picker: Option<String>,
+
+        #[arg(short = 't', long = "type", help = "Type the selection instead of copying it")]
+        type_it: bool,
+    },
+
+    #[clap(
+        about = r#"Type fields of a password into the focused window with xdotool, wtype or ydotool.
+Types the password by default."#
+    )]
+    Type {
+        #[arg(
+            short = 'f',
+            long = "field",
+            value_name = "name",
+            value_delimiter = ',',
+            conflicts_with = "sequence",
+            help = "Fields to type separated by tab, e.g. login,password"
+        )]
+        fields: Vec<String>,
+
+        #[arg(
+            short = 's',
+            long = "sequence",
+            value_name = "keys",
+            help = "Keystrokes to send, e.g. \"{login}{TAB}{password}{ENTER}\""
+        )]
+        sequence: Option<String>,
+
+        pass_name: String,
+    },
+
+    #[clap(
+        about = "Copy stdin to the clipboard and clear it after `clip_time`, for pass extensions",
+        hide = true
+    )]
+    Clip {
+        #[arg(value_name = "name", help = "What is copied, shown in the message")]
+        name: Option<String>,
+    },
+
     /// Execute an external shell command in the password store directory
     #[command(external_subcommand)]
     External(Vec<String>),
 }
+
+#[derive(Subcommand)]
+pub enum ReposCommands {
+    #[clap(about = "List the named repos, the default one is marked with '*'")]
+    #[command(alias = "ls")]
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum TrashCommands {
+    #[clap(about = "List the removed passwords and folders, oldest first")]
+    #[command(alias = "ls")]
+    List,
+
+    #[clap(about = r#"Put a password or folder back where it was removed from.
+Takes the id shown by `pars trash list` or the name, which restores its latest removal."#)]
+    Restore {
+        #[arg(value_name = "id|pass-name")]
+        target: String,
+    },
+
+    #[clap(about = "Delete what is in the trash for good")]
+    Empty {
+        #[arg(
+            long = "older-than",
+            value_name = "days",
+            help = "Only what was removed more than this many days ago"
+        )]
+        older_than: Option<u32>,
+
+        #[arg(short = 'f', long = "force", help = "Do not ask for confirmation")]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum BackupCommands {
+    #[clap(
+        about = r#"Pack the entries, .gpg-id files and the rest of the store into a tar archive
+starting with a sha256 manifest. Optionally encrypt the archive again for an offline key."#
+    )]
+    Create {
+        #[arg(long = "git", help = "Include the .git folder")]
+        git: bool,
+
+        #[arg(
+            short = 'r',
+            long = "recipient",
+            value_name = "gpg-id",
+            help = "Encrypt the archive for this key"
+        )]
+        recipient: Option<String>,
+
+        #[arg(short = 'f', long = "force", help = "Replace the output file")]
+        force: bool,
+
+        #[arg(value_name = "file", help = "The archive, - writes stdout")]
+        output: String,
+    },
+
+    #[clap(about = r#"Verify a backup against its manifest, then unpack it as the store.
+The store must be empty or not exist yet, pick another one with --repo <path>."#)]
+    Restore {
+        #[arg(value_name = "file")]
+        file: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RecipientsCommands {
+    #[clap(about = "List the recipients of the store or a folder")]
+    #[command(alias = "ls")]
+    List {
+        #[arg(short = 'p', long = "path", value_name = "sub-folder")]
+        path: Option<String>,
+    },
+
+    #[clap(about = r#"Add recipients and re-encrypt the entries for them.
+A key file holding a single key is imported into the keyring and locally signed once its
+fingerprint is confirmed, anything else is a key already in it."#)]
+    Add {
+        #[arg(short = 'p', long = "path", value_name = "sub-folder")]
+        path: Option<String>,
+
+        #[arg(short = 'y', long = "yes", help = "Sign the keys of key files without asking")]
+        yes: bool,
+
+        #[arg(required = true, value_name = "key-file|gpg-id")]
+        keys: Vec<String>,
+    },
+
+    #[clap(about = r#"Remove a recipient and re-encrypt the entries without it.
+Offers to change the passwords the recipient could read."#)]
+    #[command(alias = "rm")]
+    Remove {
+        #[arg(short = 'p', long = "path", value_name = "sub-folder")]
+        path: Option<String>,
+
+        #[arg(value_name = "gpg-id")]
+        id: String,
+    },
+
+    #[clap(about = r#"Import the recipients' keys missing from the keyring out of .public-keys/.
+They are locally signed. Keys a .gpg-id names by their full fingerprint are imported, the
+others only with the fingerprint their owner confirmed."#)]
+    Import {
+        #[arg(long = "fingerprint", value_name = "fingerprint", help = "Confirmed fingerprint")]
+        fingerprints: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    #[clap(about = "Print the effective configuration, merged from every layer")]
+    Show {
+        #[arg(long = "origin", help = "Print every key with the layer its value comes from")]
+        origin: bool,
+    },
+
+    #[clap(about = "Print the effective value of a key, e.g. feature_config.clip_time")]
+    Get { key: String },
+
+    #[clap(about = "Set a key in your config file, lists take comma separated values")]
+    Set {
+        key: String,
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+
+    #[clap(about = "Remove a key from your config file, its default value applies again")]
+    Unset { key: String },
+
+    #[clap(about = "Edit your config file, it is only saved once it can be loaded")]
+    Edit,
+
+    #[clap(about = "Check the config files for unknown keys, colors and executables")]
+    Validate {
+        #[arg(help = "File to check instead of the ones in use")]
+        file: Option<String>,
+    },
+
+    #[clap(about = "Write the default configuration to your config file")]
+    Init {
+        #[arg(short = 'f', long = "force", help = "Overwrite an existing file")]
+        force: bool,
+    },
+}
@@ -0,0 +1,300 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use pars_core::config::cli::ParsConfig;
+use pars_core::operation::git::git_output;
+use pars_core::util::str::entry_fields;
+use pars_core::util::tree::{tree_entries, FilterType, TreeConfig, TreeEntry};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::fuzzy;
+
+pub(crate) enum Mode {
+    Normal,
+    /// Typing into the filter, the list is updated on every key
+    Filter,
+    /// Waiting for a field number to copy
+    Field,
+    Prompt {
+        kind: PromptKind,
+        input: String,
+    },
+    Confirm {
+        action: Action,
+        question: String,
+    },
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum PromptKind {
+    Generate,
+    Rename,
+}
+
+/// Changes to the store, they run once confirmed
+pub(crate) enum Action {
+    Generate(String),
+    Rename(String, String),
+    Delete(String),
+}
+
+pub(crate) enum Preview {
+    Empty,
+    Secret { path: String, content: SecretString, revealed: bool },
+    QrCode { path: String, code: SecretString },
+}
+
+pub(crate) struct App<'a> {
+    pub config: &'a ParsConfig,
+    pub root: PathBuf,
+    pub entries: Vec<TreeEntry>,
+    /// Indices into `entries` currently listed
+    pub visible: Vec<usize>,
+    pub filter: String,
+    /// Index into `visible`
+    pub selected: usize,
+    pub mode: Mode,
+    pub preview: Preview,
+    pub message: String,
+    pub git_state: String,
+    pub quit: bool,
+}
+
+impl<'a> App<'a> {
+    pub fn new(config: &'a ParsConfig, root: PathBuf) -> Result<Self> {
+        let mut app = Self {
+            config,
+            root,
+            entries: Vec::new(),
+            visible: Vec::new(),
+            filter: String::new(),
+            selected: 0,
+            mode: Mode::Normal,
+            preview: Preview::Empty,
+            message: String::new(),
+            git_state: String::new(),
+            quit: false,
+        };
+        app.reload()?;
+        Ok(app)
+    }
+
+    /// Read the store again after it changed, keeping the selection where possible
+    pub fn reload(&mut self) -> Result<()> {
+        let selected = self.selected_entry().map(|entry| entry.path.clone());
+        let tree_cfg = TreeConfig {
+            root: &self.root,
+            target: "",
+            filter_type: FilterType::Disable,
+            filters: Vec::new(),
+        };
+        self.entries = tree_entries(&tree_cfg)?;
+        self.apply_filter();
+        if let Some(path) = selected {
+            self.select_path(&path);
+        }
+        self.git_state = git_state(self.config, &self.root);
+        Ok(())
+    }
+
+    /// The whole tree without a filter, otherwise the matching entries ranked best first
+    pub fn apply_filter(&mut self) {
+        if self.filter.is_empty() {
+            self.visible = (0..self.entries.len()).collect();
+        } else {
+            let names: Vec<&str> = self
+                .entries
+                .iter()
+                .filter(|entry| !entry.is_dir)
+                .map(|entry| entry.path.as_str())
+                .collect();
+            self.visible = fuzzy::rank(&names, &self.filter)
+                .into_iter()
+                .filter_map(|(name, _)| self.entries.iter().position(|entry| entry.path == *name))
+                .collect();
+        }
+        self.selected = 0;
+        self.clear_preview();
+    }
+
+    pub fn select_path(&mut self, path: &str) {
+        if let Some(pos) = self.visible.iter().position(|idx| self.entries[*idx].path == path) {
+            self.selected = pos;
+        }
+    }
+
+    pub fn selected_entry(&self) -> Option<&TreeEntry> {
+        self.visible.get(self.selected).map(|idx| &self.entries[*idx])
+    }
+
+    /// The selected entry when it is a password rather than a directory
+    pub fn selected_pass(&self) -> Option<String> {
+        self.selected_entry().filter(|entry| !entry.is_dir).map(|entry| entry.path.clone())
+    }
+
+    /// Directory of the selection with a trailing `/`, used to prefill new names
+    pub fn selected_dir(&self) -> String {
+        match self.selected_entry() {
+            Some(entry) if entry.is_dir => format!("{}/", entry.path),
+            Some(entry) => match entry.path.rsplit_once('/') {
+                Some((dir, _)) => format!("{dir}/"),
+                None => String::new(),
+            },
+            None => String::new(),
+        }
+    }
+
+    pub fn move_selection(&mut self, delta: isize) {
+        if self.visible.is_empty() {
+            return;
+        }
+        let last = self.visible.len() as isize - 1;
+        let selected = (self.selected as isize + delta).clamp(0, last) as usize;
+        if selected != self.selected {
+            self.selected = selected;
+            self.clear_preview();
+        }
+    }
+
+    pub fn clear_preview(&mut self) {
+        self.preview = Preview::Empty;
+    }
+
+    /// Decrypted content of the selected entry, if it was decrypted already
+    pub fn loaded_secret(&self) -> Option<&SecretString> {
+        match (&self.preview, self.selected_pass()) {
+            (Preview::Secret { path, content, .. }, Some(selected)) if *path == selected => {
+                Some(content)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The lines shown in the preview, every value hidden unless revealed
+pub(crate) fn preview_lines(content: &SecretString, revealed: bool) -> Vec<String> {
+    const MASK: &str = "••••••••";
+    let mut field_no = 0;
+    content
+        .expose_secret()
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| {
+            if idx == 0 {
+                return format!("password: {}", if revealed { line } else { MASK });
+            }
+            match entry_fields(&format!("\n{line}")).first() {
+                Some((key, value)) => {
+                    field_no += 1;
+                    format!("[{field_no}] {key}: {}", if revealed { value } else { MASK })
+                }
+                None if revealed || line.is_empty() => line.to_string(),
+                None => MASK.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Branch, distance to upstream and number of uncommitted changes
+fn git_state(config: &ParsConfig, root: &std::path::Path) -> String {
+    if !root.join(".git").exists() {
+        return "not a git repository".into();
+    }
+    let output = match git_output(
+        &config.executable_config.git_executable,
+        root,
+        &["status", "--porcelain=v2", "--branch"],
+    ) {
+        Ok(output) => output,
+        Err(e) => return format!("git: {e}"),
+    };
+    summarize_git_status(&output)
+}
+
+/// Summary of the output of `git status --porcelain=v2 --branch`
+fn summarize_git_status(output: &str) -> String {
+    let mut branch = "(detached)";
+    let mut ahead_behind = String::new();
+    let mut changes = 0;
+    for line in output.lines() {
+        if let Some(head) = line.strip_prefix("# branch.head ") {
+            branch = head;
+        } else if let Some(ab) = line.strip_prefix("# branch.ab ") {
+            let mut counts = ab.split_whitespace();
+            let ahead = counts.next().unwrap_or("+0").trim_start_matches('+');
+            let behind = counts.next().unwrap_or("-0").trim_start_matches('-');
+            ahead_behind = format!(" ↑{ahead} ↓{behind}");
+        } else if !line.starts_with('#') {
+            changes += 1;
+        }
+    }
+    let changes = if changes == 0 { "clean".into() } else { format!("{changes} changed") };
+    format!("{branch}{ahead_behind} {changes}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn preview_lines_test() {
+        let content = SecretString::from("hunter2\nuser: bob\nnotes\n\nurl: https://a.b");
+        assert_eq!(
+            preview_lines(&content, false),
+            vec!["password: ••••••••", "[1] user: ••••••••", "••••••••", "", "[2] url: ••••••••"]
+        );
+        assert_eq!(
+            preview_lines(&content, true),
+            vec!["password: hunter2", "[1] user: bob", "notes", "", "[2] url: https://a.b"]
+        );
+        assert!(preview_lines(&SecretString::from(""), true).is_empty());
+    }
+
+    #[test]
+    fn summarize_git_status_test() {
+        let output = "# branch.oid 0123\n# branch.head main\n# branch.upstream origin/main\n\
+                      # branch.ab +2 -1\n1 .M N... 100644 100644 100644 a b a.gpg\n? new.gpg\n";
+        assert_eq!(summarize_git_status(output), "main ↑2 ↓1 2 changed");
+        assert_eq!(summarize_git_status("# branch.head dev\n"), "dev clean");
+        assert_eq!(summarize_git_status("# branch.oid 0123\n"), "(detached) clean");
+    }
+
+    #[test]
+    fn apply_filter_test() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["github.gpg", "gitlab.gpg", "bank/checking.gpg"] {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let config = ParsConfig::default();
+        let mut app = App::new(&config, dir.path().to_path_buf()).unwrap();
+        assert_eq!(app.git_state, "not a git repository");
+        assert_eq!(app.visible.len(), app.entries.len());
+        assert!(app.entries.iter().any(|entry| entry.is_dir && entry.path == "bank"));
+
+        app.move_selection(1);
+        app.filter = "gthb".into();
+        app.apply_filter();
+        assert_eq!(app.selected, 0);
+        assert_eq!(app.selected_pass().as_deref(), Some("github"));
+        assert_eq!(app.selected_dir(), "");
+
+        app.filter = "check".into();
+        app.apply_filter();
+        assert_eq!(app.selected_pass().as_deref(), Some("bank/checking"));
+        assert_eq!(app.selected_dir(), "bank/");
+
+        app.filter = "zzz".into();
+        app.apply_filter();
+        assert!(app.visible.is_empty() && app.selected_entry().is_none());
+        app.move_selection(1);
+        assert_eq!(app.selected, 0);
+
+        app.filter.clear();
+        app.apply_filter();
+        assert_eq!(app.visible.len(), app.entries.len());
+    }
+}
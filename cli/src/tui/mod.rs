@@ -0,0 +1,377 @@
+mod app;
+mod ui;
+
+use std::env;
+use std::io::{self, BufReader};
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use app::{Action, App, Mode, Preview, PromptKind};
+use fast_qr::QRBuilder;
+use pars_core::clipboard::copy_to_clipboard;
+use pars_core::config::cli::ParsConfig;
+use pars_core::git::add_and_commit;
+use pars_core::git::commit::{CommitType, GitCommit};
+use pars_core::operation::copy_or_rename::{copy_rename_io, CopyRenameConfig};
+use pars_core::operation::edit::edit;
+use pars_core::operation::generate::{generate_io, IOStreams, PasswdGenerateConfig};
+use pars_core::operation::ls_or_show::{ls_io, LsOrShow};
+use pars_core::operation::remove::remove_io;
+use pars_core::util::str::entry_fields;
+use pars_core::util::tree::{FilterType, TreeConfig, TreePrintConfig};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::DefaultTerminal;
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::constants::SECRET_EXTENSION;
+
+/// Run the interactive browser on the store at `root` until the user quits
+pub(crate) fn run(config: &ParsConfig, root: PathBuf) -> Result<()> {
+    let mut app = App::new(config, root)?;
+    let mut terminal = ratatui::init();
+    let res = event_loop(&mut terminal, &mut app);
+    ratatui::restore();
+    res
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, app: &mut App) -> Result<()> {
+    while !app.quit {
+        terminal.draw(|frame| ui::draw(frame, app))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if let Err(e) = handle_key(terminal, app, key) {
+                app.message = format!("Error: {e}");
+            }
+        }
+    }
+    Ok(())
+}
+
+fn handle_key(terminal: &mut DefaultTerminal, app: &mut App, key: KeyEvent) -> Result<()> {
+    if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+        app.quit = true;
+        return Ok(());
+    }
+
+    match std::mem::replace(&mut app.mode, Mode::Normal) {
+        Mode::Normal => handle_normal_key(terminal, app, key.code),
+        Mode::Filter => {
+            match key.code {
+                KeyCode::Esc => {
+                    app.filter.clear();
+                    app.apply_filter();
+                }
+                KeyCode::Enter => {}
+                KeyCode::Up => app.move_selection(-1),
+                KeyCode::Down => app.move_selection(1),
+                KeyCode::Backspace => {
+                    app.filter.pop();
+                    app.apply_filter();
+                    app.mode = Mode::Filter;
+                }
+                KeyCode::Char(c) => {
+                    app.filter.push(c);
+                    app.apply_filter();
+                    app.mode = Mode::Filter;
+                }
+                _ => app.mode = Mode::Filter,
+            }
+            Ok(())
+        }
+        Mode::Field => {
+            if let KeyCode::Char(c @ '1'..='9') = key.code {
+                copy_field(app, c as usize - '0' as usize)?;
+            }
+            Ok(())
+        }
+        Mode::Prompt { kind, mut input } => {
+            match key.code {
+                KeyCode::Esc => app.message.clear(),
+                KeyCode::Enter => submit_prompt(terminal, app, kind, input.trim())?,
+                KeyCode::Backspace => {
+                    input.pop();
+                    app.mode = Mode::Prompt { kind, input };
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    app.mode = Mode::Prompt { kind, input };
+                }
+                _ => app.mode = Mode::Prompt { kind, input },
+            }
+            Ok(())
+        }
+        Mode::Confirm { action, .. } => {
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                run_action(terminal, app, action, true)
+            } else {
+                app.message = String::from("Canceled");
+                Ok(())
+            }
+        }
+    }
+}
+
+fn handle_normal_key(terminal: &mut DefaultTerminal, app: &mut App, code: KeyCode) -> Result<()> {
+    match code {
+        KeyCode::Char('q') => app.quit = true,
+        KeyCode::Esc if !app.filter.is_empty() => {
+            app.filter.clear();
+            app.apply_filter();
+        }
+        KeyCode::Esc => app.quit = true,
+        KeyCode::Char('/') => app.mode = Mode::Filter,
+        KeyCode::Char('j') | KeyCode::Down => app.move_selection(1),
+        KeyCode::Char('k') | KeyCode::Up => app.move_selection(-1),
+        KeyCode::PageDown => app.move_selection(10),
+        KeyCode::PageUp => app.move_selection(-10),
+        KeyCode::Home => app.move_selection(-(app.visible.len() as isize)),
+        KeyCode::End => app.move_selection(app.visible.len() as isize),
+        KeyCode::Enter => {
+            load_secret(terminal, app)?;
+        }
+        KeyCode::Char('r') => {
+            load_secret(terminal, app)?;
+            if let Preview::Secret { revealed, .. } = &mut app.preview {
+                *revealed = !*revealed;
+            }
+        }
+        KeyCode::Char('c') => {
+            let line = first_line(terminal, app)?;
+            copy_to_clipboard(
+                line,
+                &app.config.feature_config.clip_time,
+                &app.config.feature_config.x_selection,
+            )?;
+            app.message = String::from("Password copied to clipboard");
+        }
+        KeyCode::Char('f') => {
+            if load_secret(terminal, app)? {
+                app.mode = Mode::Field;
+            }
+        }
+        KeyCode::Char('v') => {
+            if let Preview::QrCode { .. } = app.preview {
+                app.clear_preview();
+            } else if let Some(path) = app.selected_pass() {
+                let line = first_line(terminal, app)?;
+                let code = QRBuilder::new(line.expose_secret()).build()?.to_str();
+                app.preview = Preview::QrCode { path, code: code.into() };
+            }
+        }
+        KeyCode::Char('e') => {
+            if let Some(path) = app.selected_pass() {
+                edit_entry(terminal, app, &path)?;
+            }
+        }
+        KeyCode::Char('g') => {
+            app.mode = Mode::Prompt { kind: PromptKind::Generate, input: app.selected_dir() }
+        }
+        KeyCode::Char('m') => {
+            if let Some(entry) = app.selected_entry() {
+                let input = entry.path.clone();
+                app.mode = Mode::Prompt { kind: PromptKind::Rename, input };
+            }
+        }
+        KeyCode::Char('d') => {
+            if let Some(entry) = app.selected_entry() {
+                let question = format!("Delete '{}'?", entry.path);
+                app.mode = Mode::Confirm { action: Action::Delete(entry.path.clone()), question };
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Leave the alternate screen while `f` runs, so gpg's pinentry or an editor can use the terminal
+fn suspend<T>(terminal: &mut DefaultTerminal, f: impl FnOnce() -> T) -> Result<T> {
+    ratatui::restore();
+    let res = f();
+    *terminal = ratatui::init();
+    terminal.clear()?;
+    Ok(res)
+}
+
+/// Decrypt the selected entry into the preview, returns false if a directory is selected
+fn load_secret(terminal: &mut DefaultTerminal, app: &mut App) -> Result<bool> {
+    let Some(path) = app.selected_pass() else {
+        return Ok(false);
+    };
+    if app.loaded_secret().is_some() {
+        return Ok(true);
+    }
+
+    let tree_cfg = TreeConfig {
+        root: &app.root,
+        target: &path,
+        filter_type: FilterType::Disable,
+        filters: Vec::new(),
+    };
+    let executable_config = &app.config.executable_config;
+    let print_cfg = TreePrintConfig::from(&app.config.print_config);
+    let show = || {
+        ls_io(
+            &executable_config.pgp_executable,
+            &executable_config.pgp_options,
+            &app.config.groups,
+            &tree_cfg,
+            &print_cfg,
+        )
+    };
+    match suspend(terminal, show)?? {
+        LsOrShow::Password(content) => {
+            app.preview = Preview::Secret { path, content, revealed: false };
+            Ok(true)
+        }
+        LsOrShow::DirTree(_) => Ok(false),
+    }
+}
+
+fn first_line(terminal: &mut DefaultTerminal, app: &mut App) -> Result<SecretString> {
+    if !load_secret(terminal, app)? {
+        return Err(anyhow!("Select a password first"));
+    }
+    let content = app.loaded_secret().ok_or_else(|| anyhow!("Nothing decrypted"))?;
+    Ok(content.expose_secret().lines().next().unwrap_or_default().into())
+}
+
+fn copy_field(app: &mut App, number: usize) -> Result<()> {
+    let content = app.loaded_secret().ok_or_else(|| anyhow!("Nothing decrypted"))?;
+    let (key, value) = entry_fields(content.expose_secret())
+        .get(number - 1)
+        .map(|(key, value)| (key.to_string(), SecretString::from(value.to_string())))
+        .ok_or_else(|| anyhow!("There is no field {number}"))?;
+    copy_to_clipboard(
+        value,
+        &app.config.feature_config.clip_time,
+        &app.config.feature_config.x_selection,
+    )?;
+    app.message = format!("Field '{key}' copied to clipboard");
+    Ok(())
+}
+
+fn submit_prompt(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    kind: PromptKind,
+    input: &str,
+) -> Result<()> {
+    let name = input.trim_matches('/');
+    if name.is_empty() {
+        app.message = String::from("Canceled");
+        return Ok(());
+    }
+    let action = match kind {
+        PromptKind::Generate => Action::Generate(name.to_string()),
+        PromptKind::Rename => match app.selected_entry() {
+            Some(entry) if entry.path != name => Action::Rename(entry.path.clone(), name.into()),
+            _ => return Ok(()),
+        },
+    };
+    run_action(terminal, app, action, false)
+}
+
+/// Run a change and commit it, without `confirmed` an existing target asks first
+fn run_action(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    action: Action,
+    confirmed: bool,
+) -> Result<()> {
+    let exists = |name: &str| {
+        app.root.join(format!("{name}.{SECRET_EXTENSION}")).exists() || app.root.join(name).is_dir()
+    };
+    let config = app.config;
+    let mut in_s = BufReader::new(io::empty());
+    let (mut out_s, mut err_s) = (io::sink(), io::sink());
+    let mut io_streams = IOStreams { in_s: &mut in_s, out_s: &mut out_s, err_s: &mut err_s };
+
+    let (commit_type, selected) = match action {
+        Action::Generate(name) => {
+            if exists(&name) && !confirmed {
+                let question = format!("'{name}' exists, overwrite?");
+                app.mode = Mode::Confirm { action: Action::Generate(name), question };
+                return Ok(());
+            }
+            let gen_cfg = PasswdGenerateConfig {
+                no_symbols: false,
+                in_place: false,
+                force: true,
+                pass_length: config.feature_config.generated_length,
+                character_set: config.feature_config.character_set.clone(),
+                extension: SECRET_EXTENSION.to_string(),
+                pgp_executable: config.executable_config.pgp_executable.clone(),
+                pgp_options: config.executable_config.pgp_options.clone(),
+                groups: config.groups.clone(),
+            };
+            generate_io(&app.root, &name, &gen_cfg, &mut io_streams)?;
+            (CommitType::Generate(name.clone()), Some(name))
+        }
+        Action::Rename(from, to) => {
+            if exists(&to) && !confirmed {
+                let question = format!("'{to}' exists, overwrite?");
+                app.mode = Mode::Confirm { action: Action::Rename(from, to), question };
+                return Ok(());
+            }
+            let copy_cfg = CopyRenameConfig {
+                copy: false,
+                force: true,
+                file_extension: SECRET_EXTENSION.to_string(),
+                groups: app.config.groups.clone(),
+            };
+            copy_rename_io(copy_cfg, &app.root, &from, &to, io_streams)?;
+            (CommitType::Rename((from, to.clone())), Some(to))
+        }
+        Action::Delete(name) => {
+            let recursive = app.root.join(&name).is_dir();
+            remove_io(&app.root, &name, recursive, true, &mut in_s, &mut out_s, &mut err_s)?;
+            (CommitType::Delete(name), None)
+        }
+    };
+
+    app.message =
+        GitCommit::new(&app.root, commit_type.clone(), &config.git_config).get_commit_msg();
+    commit(terminal, app, commit_type)?;
+    app.reload()?;
+    if let Some(path) = selected {
+        app.select_path(&path);
+    }
+    Ok(())
+}
+
+fn edit_entry(terminal: &mut DefaultTerminal, app: &mut App, path: &str) -> Result<()> {
+    let config = app.config;
+    let editor =
+        env::var("PARS_EDITOR").unwrap_or(config.executable_config.editor_executable.clone());
+    let changed = suspend(terminal, || {
+        edit(
+            &app.root,
+            path,
+            SECRET_EXTENSION,
+            &editor,
+            &config.executable_config.pgp_executable,
+            &config.executable_config.pgp_options,
+            &config.groups,
+        )
+    })??;
+    app.clear_preview();
+    if changed {
+        commit(terminal, app, CommitType::Update(path.to_string()))?;
+        app.message = format!("Updated '{path}'");
+    }
+    Ok(())
+}
+
+/// Commit the change when the store is a git repository, git's output would garble the
+/// screen so it is redrawn afterwards
+fn commit(terminal: &mut DefaultTerminal, app: &mut App, commit_type: CommitType) -> Result<()> {
+    if !app.root.join(".git").exists() {
+        return Ok(());
+    }
+    let commit = GitCommit::new(&app.root, commit_type, &app.config.git_config);
+    let res = add_and_commit(app.config, &app.root, commit.get_commit_msg().as_str());
+    terminal.clear()?;
+    res
+}
@@ -0,0 +1,123 @@
+use std::str::FromStr;
+
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap};
+use ratatui::Frame;
+use secrecy::ExposeSecret;
+
+use super::app::{preview_lines, App, Mode, Preview, PromptKind};
+
+const HELP: &str = "/ filter  ⏎ show  r reveal  c copy  f field  v qr  e edit  g generate  m move  d delete  q quit";
+
+pub(crate) fn draw(frame: &mut Frame, app: &App) {
+    let [main, status] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(2)]).areas(frame.area());
+    let [tree, preview] =
+        Layout::horizontal([Constraint::Percentage(40), Constraint::Percentage(60)]).areas(main);
+
+    draw_tree(frame, app, tree);
+    draw_preview(frame, app, preview);
+    draw_status(frame, app, status);
+}
+
+fn color(name: &str) -> Style {
+    Color::from_str(name).map(|color| Style::default().fg(color)).unwrap_or_default()
+}
+
+fn draw_tree(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let print_cfg = &app.config.print_config;
+    let items: Vec<ListItem> = app
+        .visible
+        .iter()
+        .map(|idx| &app.entries[*idx])
+        .map(|entry| {
+            // Filtered results are listed flat with their full path
+            let (indent, name) = if app.filter.is_empty() {
+                ("  ".repeat(entry.depth), entry.path.rsplit('/').next().unwrap_or_default())
+            } else {
+                (String::new(), entry.path.as_str())
+            };
+            if entry.is_dir {
+                ListItem::new(Line::from(vec![
+                    Span::raw(indent),
+                    Span::styled(format!("{name}/"), color(&print_cfg.dir_color)),
+                ]))
+            } else {
+                ListItem::new(Line::from(vec![
+                    Span::raw(indent),
+                    Span::styled(name.to_string(), color(&print_cfg.file_color)),
+                ]))
+            }
+        })
+        .collect();
+
+    let title = match app.mode {
+        Mode::Filter => format!(" Filter: {}▏", app.filter),
+        _ if !app.filter.is_empty() => format!(" Filter: {} ", app.filter),
+        _ => format!(" {} ", app.root.display()),
+    };
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+    let mut state = ListState::default().with_selected(Some(app.selected));
+    frame.render_stateful_widget(list, area, &mut state);
+}
+
+fn draw_preview(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let (title, lines) = match &app.preview {
+        Preview::Secret { path, content, revealed } => (
+            format!(" {path} "),
+            preview_lines(content, *revealed).into_iter().map(Line::from).collect(),
+        ),
+        Preview::QrCode { path, code } => (
+            format!(" {path} "),
+            code.expose_secret().lines().map(|line| Line::from(line.to_string())).collect(),
+        ),
+        Preview::Empty => match app.selected_pass() {
+            Some(path) => (format!(" {path} "), vec![Line::from("Press ⏎ to decrypt")]),
+            None => (String::from(" Preview "), Vec::new()),
+        },
+    };
+    let preview = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .wrap(Wrap { trim: false });
+    frame.render_widget(preview, area);
+}
+
+fn draw_status(frame: &mut Frame, app: &App, area: ratatui::layout::Rect) {
+    let [message, bar] =
+        Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(area);
+
+    let prompt = match &app.mode {
+        Mode::Prompt { kind: PromptKind::Generate, input } => format!("Generate: {input}▏"),
+        Mode::Prompt { kind: PromptKind::Rename, input } => format!("Move to: {input}▏"),
+        Mode::Confirm { question, .. } => format!("{question} [y/N]"),
+        Mode::Field => String::from("Copy field number [1-9], any other key to cancel"),
+        _ => app.message.clone(),
+    };
+    frame.render_widget(Paragraph::new(prompt), message);
+
+    let bar_line = Line::from(vec![
+        Span::styled(
+            format!(" {} ", app.git_state),
+            Style::default().add_modifier(Modifier::REVERSED),
+        ),
+        Span::raw(" "),
+        Span::styled(HELP, Style::default().fg(Color::DarkGray)),
+    ]);
+    frame.render_widget(Paragraph::new(bar_line), bar);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color_test() {
+        assert_eq!(color("red"), Style::default().fg(Color::Red));
+        assert_eq!(color("#ff8800"), Style::default().fg(Color::Rgb(0xff, 0x88, 0x00)));
+        assert_eq!(color("no such color"), Style::default());
+    }
+}
@@ -1,11 +1,145 @@
-use std::path::PathBuf;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
 use pars_core::config::cli::ParsConfig;
+use pars_core::operation::ls_or_show::{ls_io, LsOrShow};
+use pars_core::operation::public_keys::{import_bundled_keys, missing_bundled_keys};
+use pars_core::util::tree::{FilterType, TreeConfig, TreePrintConfig};
+use secrecy::SecretString;
 
+/// Resolve `-R <name|path>`, falling back to the default repo
 pub(crate) fn unwrap_root_path(root: Option<&str>, config: &ParsConfig) -> PathBuf {
-    match root {
-        Some(path) => path.into(),
-        None => config.path_config.default_repo.clone().into(),
+    config.path_config.resolve_repo(root)
+}
+
+/// Resolve the store `pass_name` lives in and its name inside that store.
+/// `<repo>:<path>` selects a named repo, mounts only apply to the default repo.
+pub(crate) fn unwrap_pass_path<'a>(
+    root: Option<&str>,
+    config: &ParsConfig,
+    pass_name: &'a str,
+) -> (PathBuf, &'a str) {
+    if let Some((repo, rest)) = pass_name.split_once(':') {
+        if config.path_config.repos.contains_key(repo) {
+            return unwrap_pass_path(Some(repo), config, rest.trim_start_matches(['/', '\\']));
+        }
+    }
+    let is_default = root.is_none_or(|root| config.path_config.is_default(root));
+    match config.path_config.route_mount(pass_name) {
+        Some((mount_root, rest)) if is_default => {
+            debug!("{pass_name:?} routed to mount {mount_root:?} as {rest:?}");
+            (mount_root, rest)
+        }
+        _ => (unwrap_root_path(root, config), pass_name),
+    }
+}
+
+/// Mounted stores of the default repo, empty for any other repo
+pub(crate) fn mounted_stores<'a>(
+    root: Option<&str>,
+    config: &'a ParsConfig,
+) -> Vec<(&'a str, PathBuf)> {
+    if root.is_none_or(|root| config.path_config.is_default(root)) {
+        config.path_config.mount_list().into_iter().filter(|(_, path)| path.is_dir()).collect()
+    } else {
+        Vec::new()
+    }
+}
+
+/// Every named repo that exists on disk, for commands run with `--all`
+pub(crate) fn existing_repos(config: &ParsConfig) -> Vec<(&str, PathBuf)> {
+    config
+        .path_config
+        .repo_list()
+        .into_iter()
+        .filter(|(name, path)| {
+            if !path.is_dir() {
+                warn!("Repo '{name}' at {path:?} does not exist, skipped");
+            }
+            path.is_dir()
+        })
+        .collect()
+}
+
+/// Decrypt the entry `pass_name` of the store at `root`
+pub(crate) fn decrypt_entry(
+    config: &ParsConfig,
+    root: &Path,
+    pass_name: &str,
+) -> Result<SecretString> {
+    let tree_cfg = TreeConfig {
+        root,
+        target: pass_name,
+        filter_type: FilterType::Disable,
+        filters: Vec::new(),
+    };
+    let print_cfg = Into::<TreePrintConfig>::into(&config.print_config);
+    match ls_io(
+        &config.executable_config.pgp_executable,
+        &config.executable_config.pgp_options,
+        &config.groups,
+        &tree_cfg,
+        &print_cfg,
+    )? {
+        LsOrShow::Password(content) => Ok(content),
+        LsOrShow::DirTree(_) => Err(anyhow!("'{pass_name}' is not a password")),
+    }
+}
+
+/// Ask to import the recipients' keys the keyring lacks from the store's `.public-keys/`, so
+/// encrypting for them does not fail. Only keys pinned by their full fingerprint in a `.gpg-id`
+/// are offered, the others could have been put there by anyone able to push. Only asks on a
+/// terminal, stdin may carry a password.
+pub(crate) fn offer_key_import(config: &ParsConfig, root: &Path) {
+    let pgp_exe = &config.executable_config.pgp_executable;
+    let (pinned, unpinned): (Vec<_>, Vec<_>) =
+        match missing_bundled_keys(pgp_exe, root, &config.groups) {
+            Ok(keys) => keys.into_iter().partition(|key| key.pinned),
+            Err(e) => {
+                debug!("Failed to look for missing keys in {root:?}: {e}");
+                return;
+            }
+        };
+
+    if !unpinned.is_empty() {
+        eprintln!("These recipients are not in your keyring and only named by a user id:");
+        for key in &unpinned {
+            eprintln!("  {}  {}  for '{}'", key.fpr, key.uids.join(", "), key.id);
+        }
+        eprintln!(
+            "Confirm each fingerprint with its owner, then run `pars recipients import \
+             --fingerprint <fingerprint>`"
+        );
+    }
+    if pinned.is_empty() {
+        return;
+    }
+    eprintln!("These recipients are not in your keyring, their keys are in .public-keys/:");
+    for key in &pinned {
+        eprintln!("  {}  {}", key.fpr, key.uids.join(", "));
+    }
+    if !io::stdin().is_terminal() {
+        eprintln!("Check the fingerprints, then run `pars recipients import`");
+        return;
+    }
+    if !confirm("Import and locally sign them?") {
+        return;
+    }
+    match import_bundled_keys(pgp_exe, &pinned) {
+        Ok(()) => eprintln!("Imported {} key(s)", pinned.len()),
+        Err(e) => warn!("Failed to import the keys: {e}"),
+    }
+}
+
+pub(crate) fn confirm(question: &str) -> bool {
+    eprint!("{question} [y/N] ");
+    let _ = io::stderr().flush();
+    let mut input = String::new();
+    match io::stdin().lock().read_line(&mut input) {
+        Ok(0) | Err(_) => false,
+        Ok(_) => input.trim().eq_ignore_ascii_case("y"),
     }
 }
 
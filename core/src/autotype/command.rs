@@ -0,0 +1,115 @@
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Result};
+use secrecy::{ExposeSecret, SecretString};
+
+use super::{Key, TypeBackend};
+use crate::util::fs_util::find_executable_in_path;
+
+/// Keystroke tools, each reads the text to type from stdin so it never shows up in `ps`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Tool {
+    Xdotool,
+    Wtype,
+    Ydotool,
+}
+
+impl Tool {
+    fn executable(self) -> &'static str {
+        match self {
+            Tool::Xdotool => "xdotool",
+            Tool::Wtype => "wtype",
+            Tool::Ydotool => "ydotool",
+        }
+    }
+
+    fn type_args(self) -> &'static [&'static str] {
+        match self {
+            Tool::Xdotool => &["type", "--clearmodifiers", "--file", "-"],
+            Tool::Wtype => &["-"],
+            Tool::Ydotool => &["type", "--file", "-"],
+        }
+    }
+
+    fn key_args(self, key: Key) -> Vec<&'static str> {
+        match self {
+            Tool::Xdotool => vec!["key", "--clearmodifiers", key_name(key)],
+            Tool::Wtype => vec!["-k", key_name(key)],
+            Tool::Ydotool => {
+                // ydotool takes linux input event codes, pressed then released
+                let (down, up) = match key {
+                    Key::Tab => ("15:1", "15:0"),
+                    Key::Enter => ("28:1", "28:0"),
+                    Key::Space => ("57:1", "57:0"),
+                    Key::Escape => ("1:1", "1:0"),
+                    Key::Backspace => ("14:1", "14:0"),
+                };
+                vec!["key", down, up]
+            }
+        }
+    }
+}
+
+fn key_name(key: Key) -> &'static str {
+    match key {
+        Key::Tab => "Tab",
+        Key::Enter => "Return",
+        Key::Space => "space",
+        Key::Escape => "Escape",
+        Key::Backspace => "BackSpace",
+    }
+}
+
+pub(super) struct CommandBackend {
+    tool: Tool,
+}
+
+impl TypeBackend for CommandBackend {
+    fn type_text(&mut self, text: &SecretString) -> Result<()> {
+        let executable = self.tool.executable();
+        let mut child =
+            Command::new(executable).args(self.tool.type_args()).stdin(Stdio::piped()).spawn()?;
+        let child_stdin =
+            child.stdin.as_mut().ok_or(anyhow!("Cannot get stdin for '{executable}'"))?;
+        child_stdin.write_all(text.expose_secret().as_bytes())?;
+        drop(child.stdin.take());
+
+        let exit_status = child.wait()?;
+        if !exit_status.success() {
+            return Err(anyhow!("{executable} exit failed: {exit_status}"));
+        }
+        Ok(())
+    }
+
+    fn press(&mut self, key: Key) -> Result<()> {
+        let executable = self.tool.executable();
+        let exit_status = Command::new(executable).args(self.tool.key_args(key)).status()?;
+        if !exit_status.success() {
+            return Err(anyhow!("{executable} exit failed: {exit_status}"));
+        }
+        Ok(())
+    }
+}
+
+pub(super) fn detect() -> Result<Box<dyn TypeBackend>> {
+    let candidates: &[Tool] = if env::var("WAYLAND_DISPLAY").is_ok() {
+        &[Tool::Wtype, Tool::Ydotool]
+    } else if env::var("XDG_SESSION_TYPE").is_ok() || env::var("DISPLAY").is_ok() {
+        &[Tool::Xdotool]
+    } else {
+        return Err(anyhow!(
+            "Unknown display server, only X11 and Wayland are supported on unix systems"
+        ));
+    };
+
+    let tool = candidates
+        .iter()
+        .find(|tool| find_executable_in_path(tool.executable()).is_some())
+        .ok_or_else(|| {
+            let names: Vec<_> = candidates.iter().map(|tool| tool.executable()).collect();
+            anyhow!("Cannot find '{}' in PATH", names.join("' or '"))
+        })?;
+    Ok(Box::new(CommandBackend { tool: *tool }))
+}
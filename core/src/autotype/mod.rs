@@ -0,0 +1,213 @@
+#[cfg(all(unix, not(target_os = "macos")))]
+mod command;
+
+use anyhow::{anyhow, Result};
+use secrecy::{ExposeSecret, SecretString};
+
+use crate::util::str::entry_fields;
+
+/// Name of the first line of an entry in a sequence
+pub const PASSWORD_FIELD: &str = "password";
+pub const DEFAULT_SEQUENCE: &str = "{password}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Tab,
+    Enter,
+    Space,
+    Escape,
+    Backspace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// Literal text between placeholders
+    Text(String),
+    /// `{name}`, a field of the entry or `{password}` for its first line
+    Field(String),
+    /// `{TAB}`, `{ENTER}` and friends
+    Key(Key),
+}
+
+/// Something able to send keystrokes to the focused window
+pub trait TypeBackend {
+    fn type_text(&mut self, text: &SecretString) -> Result<()>;
+    fn press(&mut self, key: Key) -> Result<()>;
+}
+
+/// Parse a sequence like `{login}{TAB}{password}{ENTER}`. Upper case names are keys,
+/// anything else in braces is a field, `{{` and `}}` are literal braces.
+pub fn parse_sequence(sequence: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut text = String::new();
+    let mut chars = sequence.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                text.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                text.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    name.push(c);
+                }
+                if !closed || name.is_empty() || name.contains('{') {
+                    return Err(anyhow!("Invalid placeholder '{{{name}}}' in '{sequence}'"));
+                }
+                if !text.is_empty() {
+                    tokens.push(Token::Text(std::mem::take(&mut text)));
+                }
+                tokens.push(match name.as_str() {
+                    "TAB" => Token::Key(Key::Tab),
+                    "ENTER" | "RETURN" => Token::Key(Key::Enter),
+                    "SPACE" => Token::Key(Key::Space),
+                    "ESC" | "ESCAPE" => Token::Key(Key::Escape),
+                    "BACKSPACE" | "BS" => Token::Key(Key::Backspace),
+                    _ if name.chars().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()) => {
+                        return Err(anyhow!("Unknown key '{{{name}}}' in '{sequence}'"))
+                    }
+                    _ => Token::Field(name),
+                });
+            }
+            '}' => return Err(anyhow!("Unmatched '}}' in '{sequence}'")),
+            c => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        tokens.push(Token::Text(text));
+    }
+    Ok(tokens)
+}
+
+/// Sequence typing `fields` separated by tab, as given to `--field login,password`
+pub fn fields_sequence<S: AsRef<str>>(fields: &[S]) -> String {
+    fields.iter().map(|field| format!("{{{}}}", field.as_ref())).collect::<Vec<_>>().join("{TAB}")
+}
+
+/// Type `sequence` with the values of the decrypted entry `content`. Every field is looked up
+/// before the first keystroke, so a missing one types nothing.
+pub fn autotype(
+    backend: &mut dyn TypeBackend,
+    sequence: &[Token],
+    content: &SecretString,
+) -> Result<()> {
+    let content = content.expose_secret();
+    let fields = entry_fields(content);
+    let mut values = Vec::with_capacity(sequence.len());
+    for token in sequence {
+        if let Token::Field(name) = token {
+            let value = if name == PASSWORD_FIELD {
+                content.lines().next().unwrap_or_default()
+            } else {
+                fields
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| *value)
+                    .ok_or_else(|| anyhow!("The entry has no field '{name}'"))?
+            };
+            values.push(Some(SecretString::from(value)));
+        } else {
+            values.push(None);
+        }
+    }
+
+    for (token, value) in sequence.iter().zip(values) {
+        match (token, value) {
+            (Token::Text(text), _) => backend.type_text(&text.as_str().into())?,
+            (Token::Key(key), _) => backend.press(*key)?,
+            (Token::Field(_), Some(value)) => backend.type_text(&value)?,
+            (Token::Field(_), None) => unreachable!("fields are resolved above"),
+        }
+    }
+    Ok(())
+}
+
+/// The keystroke backend for the display server, picked the same way as
+/// [`crate::clipboard::copy_to_clipboard`] picks its backend
+pub fn detect_backend() -> Result<Box<dyn TypeBackend>> {
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        command::detect()
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    {
+        Err(anyhow!("Autotype is only supported on X11 and Wayland"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[derive(Default)]
+    struct MockBackend {
+        events: Vec<String>,
+    }
+
+    impl TypeBackend for MockBackend {
+        fn type_text(&mut self, text: &SecretString) -> Result<()> {
+            self.events.push(format!("type {}", text.expose_secret()));
+            Ok(())
+        }
+
+        fn press(&mut self, key: Key) -> Result<()> {
+            self.events.push(format!("key {key:?}"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn parse_sequence_test() {
+        assert_eq!(
+            parse_sequence("{login}{TAB}{password}{ENTER}").unwrap(),
+            vec![
+                Token::Field("login".into()),
+                Token::Key(Key::Tab),
+                Token::Field("password".into()),
+                Token::Key(Key::Enter),
+            ]
+        );
+        assert_eq!(
+            parse_sequence("id={{{user}}} ").unwrap(),
+            vec![Token::Text("id={".into()), Token::Field("user".into()), Token::Text("} ".into()),]
+        );
+        assert_eq!(parse_sequence("").unwrap(), vec![]);
+        assert!(parse_sequence("{login").is_err());
+        assert!(parse_sequence("{}").is_err());
+        assert!(parse_sequence("a}b").is_err());
+        assert!(parse_sequence("{F13}").is_err());
+    }
+
+    #[test]
+    fn autotype_test() {
+        let content = SecretString::from("s3cret\nlogin: alice\nurl: https://example.com");
+        let mut backend = MockBackend::default();
+        let sequence = parse_sequence("{login}{TAB}{password}{ENTER}").unwrap();
+        autotype(&mut backend, &sequence, &content).unwrap();
+        assert_eq!(backend.events, vec!["type alice", "key Tab", "type s3cret", "key Enter"]);
+
+        let sequence = parse_sequence(&fields_sequence(&["url", "password"])).unwrap();
+        let mut backend = MockBackend::default();
+        autotype(&mut backend, &sequence, &content).unwrap();
+        assert_eq!(backend.events, vec!["type https://example.com", "key Tab", "type s3cret"]);
+
+        // Nothing is typed when a field is missing
+        let mut backend = MockBackend::default();
+        let sequence = parse_sequence("{password}{TAB}{otp}").unwrap();
+        assert!(autotype(&mut backend, &sequence, &content).is_err());
+        assert!(backend.events.is_empty());
+    }
+}
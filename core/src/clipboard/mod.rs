@@ -19,36 +19,63 @@ use crate::constants::default_constants::{
 };
 use crate::util::fs_util::find_executable_in_path;
 
-pub fn copy_to_clipboard(content: SecretString, sec_to_clear: &Option<usize>) -> Result<()> {
+/// Copy `content` and clear it after `sec_to_clear`. `x_selection` is the X selection used by
+/// xclip, `primary`, `secondary` or `clipboard`, on Wayland only `primary` makes a difference.
+pub fn copy_to_clipboard(
+    content: SecretString,
+    sec_to_clear: &Option<usize>,
+    x_selection: &str,
+) -> Result<()> {
+    let backend = clipboard_backend()?;
+
     #[cfg(target_os = "macos")]
     {
-        check_executable("pbcopy")?;
+        let _ = (backend, x_selection);
         mac::copy_to_clip_board(content, sec_to_clear)?;
     }
 
     #[cfg(all(unix, not(target_os = "macos")))]
     {
+        if backend == WAYLAND_COPY_EXECUTABLE {
+            wayland::copy_to_clip_board(content, sec_to_clear, x_selection)?;
+        } else {
+            xorg::copy_to_clip_board(content, sec_to_clear, x_selection)?;
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let _ = (backend, x_selection);
+        windows::copy_to_clip_board(content, sec_to_clear)?;
+    }
+
+    Ok(())
+}
+
+/// Executable used to reach the clipboard of the current session
+pub fn clipboard_backend() -> Result<&'static str> {
+    #[cfg(target_os = "macos")]
+    let backend = "pbcopy";
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let backend = {
         use std::env;
         if env::var("WAYLAND_DISPLAY").is_ok() {
-            check_executable(WAYLAND_COPY_EXECUTABLE)?;
-            wayland::copy_to_clip_board(content.clone(), sec_to_clear)?;
+            WAYLAND_COPY_EXECUTABLE
         } else if env::var("XDG_SESSION_TYPE").is_ok() {
-            check_executable(X11_COPY_EXECUTABLE)?;
-            xorg::copy_to_clip_board(content, sec_to_clear)?;
+            X11_COPY_EXECUTABLE
         } else {
             return Err(anyhow!(
                 "Unknown display server, only X11 and Wayland are supported on unix systems"
             ));
         }
-    }
+    };
 
     #[cfg(target_os = "windows")]
-    {
-        check_executable("powershell")?;
-        windows::copy_to_clip_board(content, sec_to_clear)?;
-    }
+    let backend = "powershell";
 
-    Ok(())
+    check_executable(backend)?;
+    Ok(backend)
 }
 
 fn check_executable(executable: &str) -> Result<()> {
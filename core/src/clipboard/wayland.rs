@@ -9,9 +9,16 @@ use zeroize::Zeroize;
 use crate::constants::default_constants::WAYLAND_COPY_EXECUTABLE;
 use crate::util::str::fit_to_unix;
 
-pub(crate) fn copy_to_clip_board(mut secret: SecretString, timeout: &Option<usize>) -> Result<()> {
+pub(crate) fn copy_to_clip_board(
+    mut secret: SecretString,
+    timeout: &Option<usize>,
+    selection: &str,
+) -> Result<()> {
     let mut cmd = Command::new(WAYLAND_COPY_EXECUTABLE);
     cmd.arg("-n");
+    if selection == "primary" {
+        cmd.arg("--primary");
+    }
 
     let mut child = cmd.stdin(std::process::Stdio::piped()).spawn()?;
 
@@ -57,7 +64,7 @@ mod tests {
     fn wayland_clipboard_test() {
         const TIMEOUT: usize = 1;
         let content = SecretString::new("Hello, pars".into());
-        let res = copy_to_clip_board(content, &Some(TIMEOUT));
+        let res = copy_to_clip_board(content, &Some(TIMEOUT), "clipboard");
         assert!(res.is_ok());
 
         let cmd = Command::new("wl-paste").output().unwrap();
@@ -8,10 +8,14 @@ use zeroize::Zeroize;
 use crate::constants::default_constants::X11_COPY_EXECUTABLE;
 use crate::util::str::fit_to_unix;
 
-pub(crate) fn copy_to_clip_board(mut secret: SecretString, timeout: &Option<usize>) -> Result<()> {
+pub(crate) fn copy_to_clip_board(
+    mut secret: SecretString,
+    timeout: &Option<usize>,
+    selection: &str,
+) -> Result<()> {
     let mut child = Command::new(X11_COPY_EXECUTABLE)
         .arg("-selection")
-        .arg("clipboard")
+        .arg(selection)
         .stdin(std::process::Stdio::piped())
         .spawn()?;
 
@@ -25,8 +29,8 @@ pub(crate) fn copy_to_clip_board(mut secret: SecretString, timeout: &Option<usiz
     }
 
     if let Some(secs) = timeout {
-        let cmd = format!("sleep {secs} && echo -n '' | xclip -selection clipboard");
-        let _ = Command::new("sh").arg("-c").arg(cmd).spawn();
+        let cmd = format!("sleep {secs} && echo -n '' | xclip -selection \"$1\"");
+        let _ = Command::new("sh").arg("-c").arg(cmd).arg("sh").arg(selection).spawn();
     }
 
     Ok(())
@@ -42,7 +46,7 @@ mod tests {
     fn xorg_clipboard_test() {
         const TIMEOUT: usize = 1;
         let content = SecretString::new("Hello, pars".into());
-        let res = copy_to_clip_board(content, &Some(TIMEOUT));
+        let res = copy_to_clip_board(content, &Some(TIMEOUT), "clipboard");
         assert!(res.is_ok());
 
         let cmd =
@@ -1,13 +1,17 @@
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 #[allow(dead_code)]
 use std::{env, path};
 
 use log::warn;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
-use crate::constants::default_constants::{EDITOR, GIT_EXECUTABLE, PGP_EXECUTABLE};
+use crate::constants::default_constants::{
+    CLIP_TIME, EDITOR, GENERATED_LENGTH, GIT_EXECUTABLE, PGP_EXECUTABLE, TRASH_RETENTION_DAYS,
+    X_SELECTION,
+};
 
 #[derive(Debug, Serialize, Deserialize, Default, Eq, PartialEq)]
 #[serde(default)]
@@ -20,9 +24,18 @@ pub struct ParsConfig {
     pub executable_config: ExecutableConfig,
     #[serde(default = "FeatureConfig::default")]
     pub feature_config: FeatureConfig,
+    #[serde(default = "GitConfig::default")]
+    pub git_config: GitConfig,
+    #[serde(default = "ExtensionConfig::default")]
+    pub extension_config: ExtensionConfig,
+    /// Recipient groups, `@<name>` in a `.gpg-id` stands for their members. The store's
+    /// `.pars-groups` overrides groups of the same name.
+    #[serde(default)]
+    pub groups: BTreeMap<String, Vec<String>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(default)]
 pub struct PrintConfig {
     pub dir_color: String,
     pub file_color: String,
@@ -33,22 +46,109 @@ pub struct PrintConfig {
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default)]
 pub struct PathConfig {
+    /// Name of an entry in `repos` or a path
     pub default_repo: String,
-    pub repos: Vec<String>,
+    /// Named stores, e.g. `work = "~/.work-store"`. A plain list of paths is accepted too,
+    /// each store is then named after its directory.
+    #[serde(deserialize_with = "deserialize_repos")]
+    pub repos: BTreeMap<String, String>,
+    /// Stores mounted into the default repo, e.g. `shared = "~/.team-store"` makes
+    /// `shared/db/root` refer to `db/root` in that store. Values are repo names or paths.
+    pub mounts: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default)]
 pub struct ExecutableConfig {
     pub pgp_executable: String,
     pub editor_executable: String,
     pub git_executable: String,
+    /// Extra arguments for every encryption and decryption, e.g. `["--armor"]`
+    pub pgp_options: Vec<String>,
+    /// Picker used by `pars menu`, e.g. `rofi` or `fzf --height 40%`. Empty picks one
+    /// matching the display server.
+    pub menu_executable: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(default)]
 pub struct FeatureConfig {
     pub clip_time: Option<usize>,
     pub fuzzy_search: bool,
+    /// Length of generated passwords when none is given
+    pub generated_length: usize,
+    /// Characters generated passwords are made of, in `tr` syntax like
+    /// `[:alnum:]_-`. Empty uses letters, digits and symbols without look-alikes.
+    pub character_set: String,
+    /// X11 selection passwords are copied to, `clipboard` or `primary`
+    pub x_selection: String,
+    /// Octal umask for files pars creates, empty keeps the inherited one
+    pub umask: String,
+    /// Read the `PASSWORD_STORE_*` environment variables of pass, `PARS_PASS_ENV=1` enables
+    /// it as well
+    pub pass_env: bool,
+    /// Days `pars rm` keeps entries in the store's `.trash/`, 0 deletes them right away
+    pub trash_retention_days: u32,
+}
+
+/// Options applied to the commits pars creates and to `pars sync`.
+/// Empty strings mean "not set", git's own configuration is used then.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Default)]
+#[serde(default)]
+pub struct GitConfig {
+    pub author_name: String,
+    pub author_email: String,
+    pub sign_commits: bool,
+    /// Key used with `git commit -S`, defaults to the first key in the root `.gpg-id`
+    pub signing_key: String,
+    /// File listing the fingerprints allowed to sign incoming commits, relative to the store root
+    pub allowed_signers: String,
+    pub verify_signatures: bool,
+    /// How entry names appear in commit messages
+    pub entry_names: EntryNameMode,
+    pub commit_templates: CommitTemplates,
+}
+
+/// Where `pars <name>` looks for extensions besides `pars-<name>` executables in PATH
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(default)]
+pub struct ExtensionConfig {
+    /// Run `<store>/.extensions/<name>.bash`. Off by default, anyone able to push to the store
+    /// could otherwise run code on your machine. `PASSWORD_STORE_ENABLE_EXTENSIONS=true`
+    /// enables it too, like in pass.
+    pub store_extensions: bool,
+    /// Directories searched for `<name>.bash`, pass installs its extensions in the first one
+    pub system_dirs: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum EntryNameMode {
+    #[default]
+    Plain,
+    /// Replaced by the first 16 hex digits of their HMAC-SHA256, keyed with a secret of the
+    /// store that is kept in `.git` and never committed
+    Hash,
+    /// Left out, along with the space around them
+    Omit,
+}
+
+/// Commit message for each kind of change. Placeholders: `{path}`, `{src}`, `{dst}`, `{keys}`,
+/// `{summary}`, `{hostname}` and `{user}`.
+#[derive(Debug, Serialize, Deserialize, Eq, PartialEq, Clone)]
+#[serde(default)]
+pub struct CommitTemplates {
+    pub init: String,
+    pub insert: String,
+    pub generate: String,
+    pub update: String,
+    pub delete: String,
+    pub copy: String,
+    pub rename: String,
+    /// Summary line of a batch, `{summary}` counts the changes by kind
+    pub batch: String,
 }
 
 impl Default for PrintConfig {
@@ -83,12 +183,41 @@ impl PrintConfig {
     }
 }
 
+impl Default for CommitTemplates {
+    fn default() -> Self {
+        Self {
+            init: "Init password with {keys}".into(),
+            insert: "Insert password {path}".into(),
+            generate: "Generate password {path}".into(),
+            update: "Update password {path}".into(),
+            delete: "Delete password {path}".into(),
+            copy: "Copy {src} to {dst}".into(),
+            rename: "Rename {src} to {dst}".into(),
+            batch: "Batch update: {summary}".into(),
+        }
+    }
+}
+
+impl Default for ExtensionConfig {
+    fn default() -> Self {
+        Self {
+            store_extensions: false,
+            system_dirs: vec![
+                "/usr/lib/password-store/extensions".into(),
+                "/usr/local/lib/password-store/extensions".into(),
+            ],
+        }
+    }
+}
+
 impl Default for ExecutableConfig {
     fn default() -> Self {
         Self {
             pgp_executable: PGP_EXECUTABLE.into(),
             editor_executable: EDITOR.into(),
             git_executable: GIT_EXECUTABLE.into(),
+            pgp_options: Vec::new(),
+            menu_executable: String::new(),
         }
     }
 }
@@ -117,13 +246,115 @@ impl Default for PathConfig {
                 )
             }
         };
-        PathConfig { default_repo: default_path.clone(), repos: vec![default_path] }
+        PathConfig {
+            default_repo: "default".into(),
+            repos: BTreeMap::from([("default".into(), default_path)]),
+            mounts: BTreeMap::new(),
+        }
     }
 }
 
+impl PathConfig {
+    /// Resolve a repo name or path, `None` selects the default repo
+    pub fn resolve_repo(&self, name_or_path: Option<&str>) -> PathBuf {
+        let target = name_or_path.unwrap_or(&self.default_repo);
+        let path = self.repos.get(target).map(String::as_str).unwrap_or(target);
+        expand_home(path)
+    }
+
+    /// All named repos with their resolved paths, sorted by name
+    pub fn repo_list(&self) -> Vec<(&str, PathBuf)> {
+        self.repos.iter().map(|(name, path)| (name.as_str(), expand_home(path))).collect()
+    }
+
+    /// Mount prefixes with the root of the mounted store, sorted by prefix
+    pub fn mount_list(&self) -> Vec<(&str, PathBuf)> {
+        self.mounts
+            .iter()
+            .map(|(prefix, target)| (prefix.trim_matches('/'), self.resolve_repo(Some(target))))
+            .collect()
+    }
+
+    /// Find the mount `pass_name` lives in, returns the mounted root and the name inside it.
+    /// The longest matching prefix wins, so mounts can be nested.
+    pub fn route_mount<'n>(&self, pass_name: &'n str) -> Option<(PathBuf, &'n str)> {
+        let name = pass_name.trim_start_matches(['/', '\\']);
+        self.mount_list()
+            .into_iter()
+            .filter_map(|(prefix, root)| {
+                let rest = name.strip_prefix(prefix)?;
+                if rest.is_empty() || rest.starts_with(['/', '\\']) {
+                    Some((prefix.len(), root, rest.trim_start_matches(['/', '\\'])))
+                } else {
+                    None
+                }
+            })
+            .max_by_key(|(len, _, _)| *len)
+            .map(|(_, root, rest)| (root, rest))
+    }
+
+    /// Whether `name` refers to the default repo, either by name or by path
+    pub fn is_default(&self, name: &str) -> bool {
+        self.default_repo == name || self.resolve_repo(None) == self.resolve_repo(Some(name))
+    }
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => match dirs::home_dir() {
+            Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+            None => path.into(),
+        },
+        _ => path.into(),
+    }
+}
+
+fn deserialize_repos<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repos {
+        Named(BTreeMap<String, String>),
+        Paths(Vec<String>),
+    }
+
+    Ok(match Repos::deserialize(deserializer)? {
+        Repos::Named(repos) => repos,
+        Repos::Paths(paths) => {
+            let mut repos = BTreeMap::new();
+            for path in paths {
+                let base = Path::new(&path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().trim_start_matches('.').to_string())
+                    .filter(|name| !name.is_empty())
+                    .unwrap_or_else(|| "repo".into());
+                let mut name = base.clone();
+                let mut index = 1;
+                while repos.contains_key(&name) {
+                    index += 1;
+                    name = format!("{base}-{index}");
+                }
+                repos.insert(name, path);
+            }
+            repos
+        }
+    })
+}
+
 impl Default for FeatureConfig {
     fn default() -> Self {
-        FeatureConfig { clip_time: Some(45), fuzzy_search: true }
+        FeatureConfig {
+            clip_time: Some(CLIP_TIME),
+            fuzzy_search: true,
+            generated_length: GENERATED_LENGTH,
+            character_set: String::new(),
+            x_selection: X_SELECTION.into(),
+            umask: String::new(),
+            pass_env: false,
+            trash_retention_days: TRASH_RETENTION_DAYS,
+        }
     }
 }
 
@@ -134,17 +365,22 @@ pub fn load_config<P: AsRef<Path>>(path: P) -> Result<ParsConfig, Box<dyn Error>
 }
 
 pub fn save_config<P: AsRef<Path>>(config: &ParsConfig, path: P) -> Result<(), Box<dyn Error>> {
-    let toml_str = toml::to_string_pretty(config)?;
-    fs::write(path, toml_str)?;
+    fs::write(path, config_to_string(config)?)?;
     Ok(())
 }
 
+pub fn config_to_string(config: &ParsConfig) -> Result<String, Box<dyn Error>> {
+    Ok(toml::to_string_pretty(config)?)
+}
+
 pub fn handle_env_config(config: ParsConfig) -> ParsConfig {
     use env_var_handler::*;
 
     let mut new_conf = config;
     let config = &mut new_conf;
 
+    // pass's variables come first, so the ones of pars win
+    handle_pass_env(config);
     handle_clip_time(config);
     handle_fuzzy(config);
 
@@ -152,7 +388,11 @@ pub fn handle_env_config(config: ParsConfig) -> ParsConfig {
 }
 
 mod env_var_handler {
+    use std::fmt::Display;
+    use std::str::FromStr;
+
     use super::*;
+    use crate::constants::env_variables::PASS_ENV;
 
     pub(super) fn handle_clip_time(config: &mut ParsConfig) {
         if let Ok(sec_str) = env::var("PARS_CLIP_TIME") {
@@ -178,13 +418,61 @@ mod env_var_handler {
             config.feature_config.fuzzy_search = false;
         }
     }
+
+    /// Map the variables of pass onto the config, only when opted in
+    pub(super) fn handle_pass_env(config: &mut ParsConfig) {
+        let enabled = config.feature_config.pass_env
+            || env::var(PASS_ENV).is_ok_and(|value| value == "1" || value == "true");
+        if !enabled {
+            return;
+        }
+
+        let feature_config = &mut config.feature_config;
+        if let Some(dir) = env::var("PASSWORD_STORE_DIR").ok().filter(|dir| !dir.is_empty()) {
+            config.path_config.default_repo = dir;
+        }
+        if let Some(sec) = parse_env::<usize>("PASSWORD_STORE_CLIP_TIME") {
+            feature_config.clip_time = if sec == 0 { None } else { Some(sec) };
+        }
+        if let Some(length) = parse_env::<usize>("PASSWORD_STORE_GENERATED_LENGTH") {
+            feature_config.generated_length = length;
+        }
+        if let Ok(character_set) = env::var("PASSWORD_STORE_CHARACTER_SET") {
+            feature_config.character_set = character_set;
+        }
+        if let Ok(selection) = env::var("PASSWORD_STORE_X_SELECTION") {
+            feature_config.x_selection = selection;
+        }
+        if let Ok(umask) = env::var("PASSWORD_STORE_UMASK") {
+            match u32::from_str_radix(&umask, 8) {
+                Ok(_) => feature_config.umask = umask,
+                Err(e) => warn!("Parse env variable 'PASSWORD_STORE_UMASK' met error {e}"),
+            }
+        }
+        if let Ok(options) = env::var("PASSWORD_STORE_GPG_OPTS") {
+            config.executable_config.pgp_options =
+                options.split_whitespace().map(String::from).collect();
+        }
+    }
+
+    fn parse_env<T>(name: &str) -> Option<T>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        let value = env::var(name).ok()?;
+        value.parse().map_err(|e| warn!("Parse env variable '{name}' met error {e}")).ok()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
+    use serial_test::serial;
 
     use super::*;
+    use crate::constants::env_variables::PASS_ENV;
+    use crate::util::defer::Defer;
     use crate::util::test_util::gen_unique_temp_dir;
 
     #[test]
@@ -201,8 +489,8 @@ mod tests {
     #[test]
     fn generate_default_config_test() {
         let mut default_config = ParsConfig::default();
-        default_config.path_config.default_repo = "<Your Home>/.password-store".into();
-        default_config.path_config.repos = vec!["<Your Home>/.password-store".into()];
+        default_config.path_config.repos =
+            BTreeMap::from([("default".into(), "<Your Home>/.password-store".into())]);
         let root = env!("CARGO_MANIFEST_DIR");
         let save_path = Path::new(root).parent().unwrap().join("config").join("cli");
         if !save_path.exists() {
@@ -212,6 +500,103 @@ mod tests {
             .expect("Failed to save default config");
     }
 
+    #[test]
+    #[serial]
+    fn pass_env_test() {
+        let vars = [
+            ("PASSWORD_STORE_DIR", "/stores/pass"),
+            ("PASSWORD_STORE_CLIP_TIME", "10"),
+            ("PASSWORD_STORE_GENERATED_LENGTH", "32"),
+            ("PASSWORD_STORE_CHARACTER_SET", "[:alnum:]"),
+            ("PASSWORD_STORE_GPG_OPTS", "--armor  --no-throw-keyids"),
+            ("PASSWORD_STORE_UMASK", "027"),
+            ("PASSWORD_STORE_X_SELECTION", "primary"),
+        ];
+        for (name, value) in vars {
+            env::set_var(name, value);
+        }
+        let _cleaner = Defer::new(|| {
+            for (name, _) in vars {
+                env::remove_var(name);
+            }
+            env::remove_var(PASS_ENV);
+        });
+
+        // Ignored unless opted in
+        env::remove_var(PASS_ENV);
+        assert_eq!(handle_env_config(ParsConfig::default()), ParsConfig::default());
+
+        env::set_var(PASS_ENV, "1");
+        let config = handle_env_config(ParsConfig::default());
+        assert_eq!(config.path_config.resolve_repo(None), PathBuf::from("/stores/pass"));
+        assert_eq!(config.feature_config.clip_time, Some(10));
+        assert_eq!(config.feature_config.generated_length, 32);
+        assert_eq!(config.feature_config.character_set, "[:alnum:]");
+        assert_eq!(config.executable_config.pgp_options, vec!["--armor", "--no-throw-keyids"]);
+        assert_eq!(config.feature_config.umask, "027");
+        assert_eq!(config.feature_config.x_selection, "primary");
+
+        // An invalid value keeps the configured one
+        env::set_var("PASSWORD_STORE_UMASK", "999");
+        assert_eq!(handle_env_config(ParsConfig::default()).feature_config.umask, "");
+    }
+
+    #[test]
+    fn named_repos_test() {
+        let config: ParsConfig = toml::from_str(
+            r#"
+[path_config]
+default_repo = "personal"
+repos = { personal = "/stores/personal", work = "~/.work-store" }
+"#,
+        )
+        .unwrap();
+        let paths = &config.path_config;
+        assert_eq!(paths.resolve_repo(None), PathBuf::from("/stores/personal"));
+        assert_eq!(paths.resolve_repo(Some("work")), dirs::home_dir().unwrap().join(".work-store"));
+        assert_eq!(paths.resolve_repo(Some("/tmp/other")), PathBuf::from("/tmp/other"));
+        assert!(paths.is_default("/stores/personal"));
+        assert!(!paths.is_default("work"));
+
+        let legacy: ParsConfig = toml::from_str(
+            r#"
+[path_config]
+default_repo = "/a/.password-store"
+repos = ["/a/.password-store", "/b/.password-store", "/c/team"]
+"#,
+        )
+        .unwrap();
+        let names: Vec<_> =
+            legacy.path_config.repo_list().into_iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["password-store", "password-store-2", "team"]);
+        assert!(legacy.path_config.is_default("password-store"));
+    }
+
+    #[test]
+    fn mount_route_test() {
+        let config: ParsConfig = toml::from_str(
+            r#"
+[path_config]
+default_repo = "/stores/main"
+repos = { team = "/stores/team" }
+mounts = { shared = "team", "shared/ops" = "/stores/ops", "/misc/" = "/stores/misc" }
+"#,
+        )
+        .unwrap();
+        let paths = &config.path_config;
+        assert_eq!(
+            paths.route_mount("shared/db/root"),
+            Some((PathBuf::from("/stores/team"), "db/root"))
+        );
+        assert_eq!(
+            paths.route_mount("shared/ops/key"),
+            Some((PathBuf::from("/stores/ops"), "key"))
+        );
+        assert_eq!(paths.route_mount("/misc"), Some((PathBuf::from("/stores/misc"), "")));
+        assert_eq!(paths.route_mount("sharedkey"), None);
+        assert_eq!(paths.route_mount("web/github"), None);
+    }
+
     #[test]
     fn invalid_path_test() {
         let test_config = ParsConfig::default();
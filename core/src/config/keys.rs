@@ -0,0 +1,438 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use toml::Table;
+pub use toml::Value;
+use toml_edit::{DocumentMut, Item};
+
+use super::cli::ParsConfig;
+use super::layered::{flatten_config, flatten_table};
+use crate::util::fs_util::{find_executable_in_path, is_executable};
+use crate::util::str::edit_distance;
+use crate::util::tree::string_to_color_opt;
+
+/// Tables whose keys are names chosen by the user
+const USER_TABLES: &[&str] = &["path_config.repos", "path_config.mounts", "groups"];
+
+/// Something wrong with a key of an otherwise readable config
+#[derive(Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub key: String,
+    pub message: String,
+}
+
+/// Every key of the config, dotted like `feature_config.clip_time`, sorted
+pub fn known_keys() -> Vec<String> {
+    let mut keys: Vec<String> = flatten_config(&ParsConfig::default())
+        .unwrap_or_default()
+        .into_keys()
+        .filter(|key| !USER_TABLES.iter().any(|table| key.starts_with(&format!("{table}."))))
+        .chain(USER_TABLES.iter().map(|table| table.to_string()))
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Fail with a suggestion when `key` is neither a key nor a section of the config
+pub fn check_key(key: &str) -> Result<()> {
+    if is_known(key) {
+        return Ok(());
+    }
+    match suggest_key(key) {
+        Some(suggestion) => Err(anyhow!("Unknown key '{key}', did you mean '{suggestion}'?")),
+        None => Err(anyhow!("Unknown key '{key}'")),
+    }
+}
+
+fn is_known(key: &str) -> bool {
+    let in_user_table = USER_TABLES.iter().any(|table| {
+        key.strip_prefix(table)
+            .and_then(|rest| rest.strip_prefix('.'))
+            .is_some_and(|name| !name.is_empty() && !name.contains('.'))
+    });
+    in_user_table
+        || known_keys().iter().any(|known| known == key || known.starts_with(&format!("{key}.")))
+}
+
+/// The known key closest to `key`, a key without section is compared to the last part only
+pub fn suggest_key(key: &str) -> Option<String> {
+    known_keys()
+        .into_iter()
+        .map(|known| {
+            let short = known.rsplit('.').next().unwrap_or(&known);
+            let distance = edit_distance(key, &known).min(edit_distance(key, short));
+            (distance, known)
+        })
+        .filter(|(distance, _)| *distance <= 2.max(key.len() / 4))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known)
+}
+
+/// Value of `key` in `config`, a section gives a table. `None` when the key is not set.
+pub fn get_key(config: &ParsConfig, key: &str) -> Result<Option<Value>> {
+    check_key(key)?;
+    Ok(lookup(&to_table(config)?, key).cloned())
+}
+
+/// `config` with `key` set to `raw`, parsed as the type the key has. Lists take a TOML array
+/// or comma separated values.
+pub fn set_key(config: &ParsConfig, key: &str, raw: &str) -> Result<ParsConfig> {
+    check_key(key)?;
+    let defaults = to_table(&ParsConfig::default())?;
+    let mut table = to_table(config)?;
+    let like = lookup(&table, key).or_else(|| lookup(&defaults, key));
+    if let Some(Value::Table(_)) = like {
+        return Err(anyhow!("'{key}' is a section, set one of its keys"));
+    }
+    // Repos and mounts are paths or names, whatever they look like, groups lists of keys
+    let in_user_table = USER_TABLES.iter().any(|table| key.starts_with(&format!("{table}.")));
+    let value = if key.starts_with("groups.") {
+        parse_value(raw, Some(&Value::Array(Vec::new())))?
+    } else if in_user_table {
+        Value::String(raw.into())
+    } else {
+        parse_value(raw, like)?
+    };
+
+    let mut parent = &mut table;
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or(key);
+    for part in parts {
+        parent = match parent.entry(part).or_insert_with(|| Value::Table(Table::new())) {
+            Value::Table(table) => table,
+            _ => return Err(anyhow!("'{part}' in '{key}' is not a section")),
+        };
+    }
+    parent.insert(last.to_string(), value);
+    Value::Table(table).try_into().map_err(|e| anyhow!("Invalid value '{raw}' for '{key}': {e}"))
+}
+
+/// `config` without `key`, which takes its default value again. A section is reset as a whole.
+pub fn unset_key(config: &ParsConfig, key: &str) -> Result<ParsConfig> {
+    check_key(key)?;
+    let mut table = to_table(config)?;
+    let (parent_key, last) = key.rsplit_once('.').unwrap_or(("", key));
+    let parent = if parent_key.is_empty() {
+        Some(&mut table)
+    } else {
+        match lookup_mut(&mut table, parent_key) {
+            Some(Value::Table(parent)) => Some(parent),
+            _ => None,
+        }
+    };
+    if parent.and_then(|parent| parent.remove(last)).is_none() {
+        return Err(anyhow!("'{key}' is not set"));
+    }
+    Ok(Value::Table(table).try_into()?)
+}
+
+/// Set `key` in `content`, the text of a config file, and return the new text. Only that key
+/// changes, the other keys and the comments stay as they are, so a key left out of the file
+/// keeps coming from the layers below it. The value is parsed like [`set_key`] does.
+pub fn set_key_in_toml(content: &str, key: &str, raw: &str) -> Result<(String, ParsConfig)> {
+    let config: ParsConfig = toml::from_str(content)?;
+    let config = set_key(&config, key, raw)?;
+    let value =
+        lookup(&to_table(&config)?, key).ok_or_else(|| anyhow!("'{key}' is not set"))?.clone();
+
+    let mut document: DocumentMut = content.parse()?;
+    let mut parent = document.as_table_mut();
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or(key);
+    for part in parts {
+        let item = parent.entry(part).or_insert_with(|| {
+            let mut table = toml_edit::Table::new();
+            table.set_implicit(true);
+            Item::Table(table)
+        });
+        parent =
+            item.as_table_mut().ok_or_else(|| anyhow!("'{part}' in '{key}' is not a section"))?;
+    }
+    // Replacing the item rather than the key keeps the comments written above it
+    match parent.get_mut(last) {
+        Some(item) => *item = to_item(&value)?,
+        None => {
+            parent.insert(last, to_item(&value)?);
+        }
+    }
+    Ok((document.to_string(), config))
+}
+
+/// Remove `key` from `content`, the text of a config file, leaving everything else untouched.
+/// A section is removed as a whole.
+pub fn unset_key_in_toml(content: &str, key: &str) -> Result<String> {
+    check_key(key)?;
+    let mut document: DocumentMut = content.parse()?;
+    let mut parent = document.as_table_mut();
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().unwrap_or(key);
+    for part in parts {
+        parent = match parent.get_mut(part).and_then(Item::as_table_mut) {
+            Some(table) => table,
+            None => return Err(anyhow!("'{key}' is not set")),
+        };
+    }
+    if parent.remove(last).is_none() {
+        return Err(anyhow!("'{key}' is not set"));
+    }
+    Ok(document.to_string())
+}
+
+/// Turn a value of [`toml`] into one of [`toml_edit`], through its text
+fn to_item(value: &Value) -> Result<Item> {
+    let mut table = Table::new();
+    table.insert("value".into(), value.clone());
+    let mut document: DocumentMut = toml::to_string(&table)?.parse()?;
+    document.remove("value").ok_or_else(|| anyhow!("Cannot write {value}"))
+}
+
+/// Colors and executables of `config` that cannot be used
+pub fn check_config(config: &ParsConfig) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    let print = &config.print_config;
+    let colors = [
+        ("dir_color", &print.dir_color),
+        ("file_color", &print.file_color),
+        ("symbol_color", &print.symbol_color),
+        ("tree_color", &print.tree_color),
+        ("grep_pass_color", &print.grep_pass_color),
+        ("grep_match_color", &print.grep_match_color),
+    ];
+    for (key, color) in colors {
+        if !color.is_empty() && string_to_color_opt(color).is_none() {
+            problems.push(Problem {
+                key: format!("print_config.{key}"),
+                message: format!("unknown color '{color}'"),
+            });
+        }
+    }
+
+    let executables = &config.executable_config;
+    let commands = [
+        ("pgp_executable", &executables.pgp_executable),
+        ("editor_executable", &executables.editor_executable),
+        ("git_executable", &executables.git_executable),
+        ("menu_executable", &executables.menu_executable),
+    ];
+    for (key, command) in commands {
+        // An empty picker is detected when `pars menu` runs
+        let Some(program) = command.split_whitespace().next() else {
+            if key != "menu_executable" {
+                problems.push(Problem {
+                    key: format!("executable_config.{key}"),
+                    message: "is empty".into(),
+                });
+            }
+            continue;
+        };
+        let path = Path::new(program);
+        let found = if path.components().count() > 1 {
+            is_executable(path).unwrap_or(false)
+        } else {
+            find_executable_in_path(program).is_some()
+        };
+        if !found {
+            problems.push(Problem {
+                key: format!("executable_config.{key}"),
+                message: format!("'{program}' is not an executable"),
+            });
+        }
+    }
+    problems
+}
+
+/// Read the config file at `path`, failing when it cannot be loaded at all, and report its
+/// unknown keys and the problems found by [`check_config`]
+pub fn validate_file(path: &Path) -> Result<Vec<Problem>> {
+    let content = fs::read_to_string(path)?;
+    let table: Table = toml::from_str(&content)?;
+
+    let mut problems = Vec::new();
+    for key in flatten_table(&table).into_keys() {
+        if !is_known(&key) {
+            let message = match suggest_key(&key) {
+                Some(suggestion) => format!("unknown key, did you mean '{suggestion}'?"),
+                None => "unknown key".into(),
+            };
+            problems.push(Problem { key, message });
+        }
+    }
+    let config: ParsConfig = Value::Table(table).try_into()?;
+    problems.extend(check_config(&config));
+    Ok(problems)
+}
+
+fn to_table(config: &ParsConfig) -> Result<Table> {
+    match Value::try_from(config)? {
+        Value::Table(table) => Ok(table),
+        _ => Err(anyhow!("The config is not a table")),
+    }
+}
+
+fn lookup<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let (first, rest) = key.split_once('.').unwrap_or((key, ""));
+    let value = table.get(first)?;
+    match value {
+        _ if rest.is_empty() => Some(value),
+        Value::Table(table) => lookup(table, rest),
+        _ => None,
+    }
+}
+
+fn lookup_mut<'a>(table: &'a mut Table, key: &str) -> Option<&'a mut Value> {
+    let (first, rest) = key.split_once('.').unwrap_or((key, ""));
+    let value = table.get_mut(first)?;
+    match value {
+        _ if rest.is_empty() => Some(value),
+        Value::Table(table) => lookup_mut(table, rest),
+        _ => None,
+    }
+}
+
+fn parse_value(raw: &str, like: Option<&Value>) -> Result<Value> {
+    Ok(match like {
+        Some(Value::String(_)) => Value::String(raw.into()),
+        Some(Value::Integer(_)) => Value::Integer(
+            raw.trim().parse().map_err(|_| anyhow!("'{raw}' is not a whole number"))?,
+        ),
+        Some(Value::Boolean(_)) => Value::Boolean(
+            raw.trim().parse().map_err(|_| anyhow!("'{raw}' is not 'true' or 'false'"))?,
+        ),
+        Some(Value::Array(_)) if raw.trim_start().starts_with('[') => parse_toml(raw)?,
+        Some(Value::Array(_)) => Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.into()))
+                .collect(),
+        ),
+        _ => parse_toml(raw).unwrap_or_else(|_| Value::String(raw.into())),
+    })
+}
+
+fn parse_toml(raw: &str) -> Result<Value> {
+    let mut table: Table = toml::from_str(&format!("value = {raw}"))
+        .map_err(|e| anyhow!("'{raw}' is not a valid value: {e}"))?;
+    table.remove("value").ok_or_else(|| anyhow!("'{raw}' is not a valid value"))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::config::cli::EntryNameMode;
+    use crate::util::test_util::gen_unique_temp_dir;
+
+    #[test]
+    fn keys_test() {
+        assert!(known_keys().contains(&"feature_config.clip_time".to_string()));
+        assert!(check_key("feature_config").is_ok());
+        assert!(check_key("path_config.repos.work").is_ok());
+        assert!(check_key("path_config.repos.work.path").is_err());
+        assert_eq!(suggest_key("feature_config.clip_tme").unwrap(), "feature_config.clip_time");
+        assert_eq!(suggest_key("generated_length").unwrap(), "feature_config.generated_length");
+        assert_eq!(suggest_key("nothing_alike_at_all"), None);
+        let err = check_key("featur_config.fuzy_search").unwrap_err();
+        assert!(err.to_string().contains("did you mean 'feature_config.fuzzy_search'"), "{err}");
+    }
+
+    #[test]
+    fn set_unset_test() {
+        let config = ParsConfig::default();
+        let config = set_key(&config, "feature_config.clip_time", "10").unwrap();
+        assert_eq!(config.feature_config.clip_time, Some(10));
+        let config = set_key(&config, "feature_config.fuzzy_search", "false").unwrap();
+        assert!(!config.feature_config.fuzzy_search);
+        let config = set_key(&config, "executable_config.pgp_options", "--armor, -q").unwrap();
+        assert_eq!(config.executable_config.pgp_options, vec!["--armor", "-q"]);
+        let config = set_key(&config, "path_config.repos.work", "~/work").unwrap();
+        assert_eq!(config.path_config.repos["work"], "~/work");
+        assert_eq!(
+            get_key(&config, "git_config.entry_names").unwrap(),
+            Some(Value::String("plain".into()))
+        );
+
+        assert!(set_key(&config, "feature_config.clip_time", "soon").is_err());
+        assert!(set_key(&config, "git_config.entry_names", "secret").is_err());
+        let config = set_key(&config, "git_config.entry_names", "hash").unwrap();
+        assert_eq!(config.git_config.entry_names, EntryNameMode::Hash);
+        assert!(set_key(&config, "feature_config", "1").is_err());
+        assert!(set_key(&config, "feature_config.clip", "1").is_err());
+
+        let config = unset_key(&config, "feature_config.clip_time").unwrap();
+        assert_eq!(config.feature_config.clip_time, ParsConfig::default().feature_config.clip_time);
+        let config = unset_key(&config, "path_config.repos.work").unwrap();
+        assert!(!config.path_config.repos.contains_key("work"));
+        assert!(unset_key(&config, "path_config.mounts.none").is_err());
+    }
+
+    #[test]
+    fn set_unset_in_toml_test() {
+        let content = "# mine\n[feature_config]\n# how long\nclip_time = 10\n";
+        let (content, config) =
+            set_key_in_toml(content, "feature_config.fuzzy_search", "false").unwrap();
+        assert!(!config.feature_config.fuzzy_search);
+        assert_eq!(
+            content,
+            "# mine\n[feature_config]\n# how long\nclip_time = 10\nfuzzy_search = false\n"
+        );
+
+        let (content, _) = set_key_in_toml(&content, "path_config.repos.work", "~/work").unwrap();
+        let (content, _) = set_key_in_toml(&content, "feature_config.clip_time", "20").unwrap();
+        let table: Table = toml::from_str(&content).unwrap();
+        assert_eq!(flatten_table(&table).len(), 3);
+        assert!(content.contains("# how long\nclip_time = 20\n"), "{content}");
+        assert!(content.contains("[path_config.repos]\nwork = \"~/work\"\n"), "{content}");
+        assert!(set_key_in_toml(&content, "feature_config.clip_time", "soon").is_err());
+
+        let content = unset_key_in_toml(&content, "feature_config.clip_time").unwrap();
+        assert!(!content.contains("clip_time"));
+        assert!(content.starts_with("# mine\n"));
+        assert!(unset_key_in_toml(&content, "feature_config.clip_time").is_err());
+        assert!(unset_key_in_toml(&content, "git_config.entry_names").is_err());
+        assert!(unset_key_in_toml(&content, "featur_config").is_err());
+        let content = unset_key_in_toml(&content, "path_config").unwrap();
+        assert!(!content.contains("repos"));
+    }
+
+    #[test]
+    fn validate_test() {
+        let mut config = ParsConfig::default();
+        config.executable_config.pgp_executable = "sh".into();
+        config.executable_config.editor_executable = "sh -e".into();
+        config.executable_config.git_executable = "pars-no-such-git".into();
+        config.print_config.dir_color = "blurple".into();
+        assert_eq!(
+            check_config(&config),
+            vec![
+                Problem {
+                    key: "print_config.dir_color".into(),
+                    message: "unknown color 'blurple'".into()
+                },
+                Problem {
+                    key: "executable_config.git_executable".into(),
+                    message: "'pars-no-such-git' is not an executable".into()
+                },
+            ]
+        );
+
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let path = root.join("config.toml");
+        fs::write(
+            &path,
+            "[feature_config]\nclip_tim = 3\n[executable_config]\ngit_executable = \"sh\"\n",
+        )
+        .unwrap();
+        let problems = validate_file(&path).unwrap();
+        assert_eq!(problems[0].key, "feature_config.clip_tim");
+        assert!(problems[0].message.contains("feature_config.clip_time"));
+
+        fs::write(&path, "[feature_config]\nclip_time = \"soon\"\n").unwrap();
+        assert!(validate_file(&path).is_err());
+        fs::write(&path, "[feature_config\n").unwrap();
+        assert!(validate_file(&path).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use toml::{Table, Value};
+
+use super::cli::{handle_env_config, ParsConfig};
+use super::keys::set_key;
+use crate::constants::default_constants::REPO_CONFIG_FILENAME;
+use crate::util::fs_util::system_config_path;
+
+/// Keys a store may set in its `.pars.toml`, together with everything below them. Executables
+/// and paths are left out, anyone able to push to a shared store could otherwise run code.
+const REPO_KEYS: &[&str] = &[
+    "print_config",
+    "feature_config.clip_time",
+    "feature_config.generated_length",
+    "feature_config.character_set",
+    "git_config.entry_names",
+    "git_config.commit_templates",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layer {
+    System,
+    User,
+    /// `.pars.toml` at the root of the store
+    Repo,
+}
+
+/// Where the effective value of a key comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File(Layer, PathBuf),
+    Env,
+    /// `-O key=value` of the command line
+    Cli,
+}
+
+/// The config merged from every layer: defaults, system file, user file, the `.pars.toml` of
+/// the store, the environment and the command line, later ones winning
+#[derive(Debug)]
+pub struct LayeredConfig {
+    pub config: ParsConfig,
+    /// The user's config file, whether it exists or not
+    pub user_path: PathBuf,
+    /// Files read, in the order they were applied
+    pub files: Vec<(Layer, PathBuf)>,
+    /// Why the files could not be loaded when falling back to the defaults
+    pub load_error: Option<String>,
+    /// Keys set by a layer, dotted like `feature_config.clip_time`
+    origins: BTreeMap<String, Origin>,
+}
+
+impl Display for Layer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Layer::System => write!(f, "system"),
+            Layer::User => write!(f, "user"),
+            Layer::Repo => write!(f, "repo"),
+        }
+    }
+}
+
+impl Display for Origin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::File(layer, path) => write!(f, "{layer} {}", path.display()),
+            Origin::Env => write!(f, "env"),
+            Origin::Cli => write!(f, "cli"),
+        }
+    }
+}
+
+impl LayeredConfig {
+    /// Load every layer, `repo` selects the store whose `.pars.toml` is read like `-R` does
+    pub fn load(user_path: &Path, repo: Option<&str>) -> Result<Self> {
+        Self::load_from(&system_config_path(), user_path, repo)
+    }
+
+    /// Only the defaults, for when the files cannot be loaded
+    pub fn defaults(user_path: &Path) -> Self {
+        LayeredConfig {
+            config: ParsConfig::default(),
+            user_path: user_path.to_path_buf(),
+            files: Vec::new(),
+            load_error: None,
+            origins: BTreeMap::new(),
+        }
+    }
+
+    fn load_from(system_path: &Path, user_path: &Path, repo: Option<&str>) -> Result<Self> {
+        let mut layered = Self::defaults(user_path);
+        let mut merged = Table::new();
+
+        for (layer, path) in [(Layer::System, system_path), (Layer::User, user_path)] {
+            if let Some(table) = read_table(path)? {
+                layered.apply_file(&mut merged, table, layer, path)?;
+            }
+        }
+
+        // The environment may select another store, so it is taken into account to find it
+        let root = handle_env_config(Value::Table(merged.clone()).try_into()?)
+            .path_config
+            .resolve_repo(repo);
+        let repo_path = root.join(REPO_CONFIG_FILENAME);
+        if let Some(table) = read_table(&repo_path)? {
+            let table = filter_repo_keys(table, "", &repo_path);
+            layered.apply_file(&mut merged, table, Layer::Repo, &repo_path)?;
+        }
+
+        let config: ParsConfig = Value::Table(merged).try_into()?;
+        let before = flatten_config(&config)?;
+        layered.config = handle_env_config(config);
+        for (key, value) in flatten_config(&layered.config)? {
+            if before.get(&key) != Some(&value) {
+                layered.set_origin(key, Origin::Env);
+            }
+        }
+        Ok(layered)
+    }
+
+    /// Apply the `key=value` overrides of the command line on top of every other layer. They
+    /// are parsed like `config set` does, the store whose `.pars.toml` was read stays the same.
+    pub fn apply_overrides(&mut self, overrides: &[String]) -> Result<()> {
+        for item in overrides {
+            let (key, raw) = item
+                .split_once('=')
+                .ok_or_else(|| anyhow!("Expect 'key=value' for an option, got '{item}'"))?;
+            let key = key.trim();
+            self.config = set_key(&self.config, key, raw.trim())?;
+            self.set_origin(key.to_string(), Origin::Cli);
+        }
+        Ok(())
+    }
+
+    /// Effective value of every key with its origin, sorted by key
+    pub fn entries(&self) -> Result<Vec<(String, Value, &Origin)>> {
+        Ok(flatten_config(&self.config)?
+            .into_iter()
+            .map(|(key, value)| {
+                let origin = self.origin(&key);
+                (key, value, origin)
+            })
+            .collect())
+    }
+
+    /// Origin of `key`, a layer setting one of its parents counts as well
+    pub fn origin(&self, key: &str) -> &Origin {
+        let mut key = key;
+        loop {
+            if let Some(origin) = self.origins.get(key) {
+                return origin;
+            }
+            match key.rsplit_once('.') {
+                Some((parent, _)) => key = parent,
+                None => return &Origin::Default,
+            }
+        }
+    }
+
+    fn apply_file(
+        &mut self,
+        merged: &mut Table,
+        table: Table,
+        layer: Layer,
+        path: &Path,
+    ) -> Result<()> {
+        debug!("Apply {layer} config '{}'", path.display());
+        for key in flatten_table(&table).into_keys() {
+            self.set_origin(key, Origin::File(layer, path.to_path_buf()));
+        }
+        merge_table(merged, table);
+        // Checking after each layer tells which file holds the bad value
+        Value::Table(merged.clone())
+            .try_into::<ParsConfig>()
+            .map_err(|e| anyhow!("Invalid config file '{}': {e}", path.display()))?;
+        self.files.push((layer, path.to_path_buf()));
+        Ok(())
+    }
+
+    fn set_origin(&mut self, key: String, origin: Origin) {
+        // A value replacing a whole table, like a list of repos, hides what was set below it
+        let prefix = format!("{key}.");
+        self.origins.retain(|k, _| !k.starts_with(&prefix));
+        self.origins.insert(key, origin);
+    }
+}
+
+fn read_table(path: &Path) -> Result<Option<Table>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(path)?;
+    let table = toml::from_str(&content)
+        .map_err(|e| anyhow!("Failed to parse config file '{}': {e}", path.display()))?;
+    Ok(Some(table))
+}
+
+/// Merge `other` into `base`, tables are merged key by key and anything else is replaced
+fn merge_table(base: &mut Table, other: Table) {
+    for (key, value) in other {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(table)) => merge_table(base_table, table),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn filter_repo_keys(table: Table, prefix: &str, path: &Path) -> Table {
+    let mut res = Table::new();
+    for (key, value) in table {
+        let full_key = join_key(prefix, &key);
+        let allowed = REPO_KEYS
+            .iter()
+            .any(|allowed| full_key == *allowed || full_key.starts_with(&format!("{allowed}.")));
+        let has_allowed_child =
+            REPO_KEYS.iter().any(|allowed| allowed.starts_with(&format!("{full_key}.")));
+        match value {
+            value if allowed => {
+                res.insert(key, value);
+            }
+            Value::Table(table) if has_allowed_child => {
+                let table = filter_repo_keys(table, &full_key, path);
+                if !table.is_empty() {
+                    res.insert(key, Value::Table(table));
+                }
+            }
+            _ => warn!("Ignore '{full_key}' in '{}', a store cannot set it", path.display()),
+        }
+    }
+    res
+}
+
+pub(super) fn flatten_config(config: &ParsConfig) -> Result<BTreeMap<String, Value>> {
+    match Value::try_from(config)? {
+        Value::Table(table) => Ok(flatten_table(&table)),
+        _ => Err(anyhow!("The config is not a table")),
+    }
+}
+
+/// Dotted keys of every value in `table`, arrays are values rather than nested keys
+pub(super) fn flatten_table(table: &Table) -> BTreeMap<String, Value> {
+    fn walk(table: &Table, prefix: &str, res: &mut BTreeMap<String, Value>) {
+        for (key, value) in table {
+            let key = join_key(prefix, key);
+            match value {
+                Value::Table(table) => walk(table, &key, res),
+                value => {
+                    res.insert(key, value.clone());
+                }
+            }
+        }
+    }
+
+    let mut res = BTreeMap::new();
+    walk(table, "", &mut res);
+    res
+}
+
+fn join_key(prefix: &str, key: &str) -> String {
+    if prefix.is_empty() {
+        key.to_string()
+    } else {
+        format!("{prefix}.{key}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use pretty_assertions::assert_eq;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::constants::env_variables::CLIP_TIME_ENV;
+    use crate::util::test_util::gen_unique_temp_dir;
+
+    #[test]
+    #[serial]
+    fn layered_config_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let store = root.join("store");
+        fs::create_dir_all(&store).unwrap();
+        let system = root.join("system.toml");
+        let user = root.join("user.toml");
+        fs::write(
+            &system,
+            "[feature_config]\nclip_time = 10\ngenerated_length = 30\n\n\
+             [path_config.repos]\nteam = \"/stores/team\"\n",
+        )
+        .unwrap();
+        fs::write(
+            &user,
+            format!(
+                "[feature_config]\nclip_time = 20\n\n[path_config.repos]\ndefault = {:?}\n",
+                store.display().to_string()
+            ),
+        )
+        .unwrap();
+        fs::write(
+            store.join(REPO_CONFIG_FILENAME),
+            "[feature_config]\ngenerated_length = 40\nfuzzy_search = false\n\n\
+             [executable_config]\npgp_executable = \"evil\"\n\n\
+             [git_config.commit_templates]\ninsert = \"Add {path}\"\n",
+        )
+        .unwrap();
+        env::remove_var(CLIP_TIME_ENV);
+
+        let layered = LayeredConfig::load_from(&system, &user, None).unwrap();
+        let config = &layered.config;
+        assert_eq!(config.feature_config.clip_time, Some(20));
+        assert_eq!(config.feature_config.generated_length, 40);
+        assert_eq!(config.git_config.commit_templates.insert, "Add {path}");
+        // Not settable by a store
+        assert!(config.feature_config.fuzzy_search);
+        assert_eq!(config.executable_config, ParsConfig::default().executable_config);
+        // Tables of the layers are merged
+        assert_eq!(config.path_config.resolve_repo(Some("team")), PathBuf::from("/stores/team"));
+        assert_eq!(config.path_config.resolve_repo(None), store);
+
+        let repo_file = Origin::File(Layer::Repo, store.join(REPO_CONFIG_FILENAME));
+        assert_eq!(
+            layered.origin("feature_config.clip_time"),
+            &Origin::File(Layer::User, user.clone())
+        );
+        assert_eq!(layered.origin("feature_config.generated_length"), &repo_file);
+        assert_eq!(layered.origin("git_config.commit_templates.insert"), &repo_file);
+        assert_eq!(
+            layered.origin("path_config.repos.team"),
+            &Origin::File(Layer::System, system.clone())
+        );
+        assert_eq!(layered.origin("executable_config.pgp_executable"), &Origin::Default);
+        assert_eq!(layered.files.len(), 3);
+
+        env::set_var(CLIP_TIME_ENV, "5");
+        let layered = LayeredConfig::load_from(&system, &user, None).unwrap();
+        env::remove_var(CLIP_TIME_ENV);
+        assert_eq!(layered.config.feature_config.clip_time, Some(5));
+        assert_eq!(layered.origin("feature_config.clip_time"), &Origin::Env);
+        assert!(layered.entries().unwrap().contains(&(
+            "feature_config.clip_time".into(),
+            Value::Integer(5),
+            &Origin::Env
+        )));
+
+        // The file holding a bad value is named
+        fs::write(&user, "[feature_config]\nclip_time = \"soon\"\n").unwrap();
+        let err = LayeredConfig::load_from(&system, &user, None).unwrap_err();
+        assert!(err.to_string().contains("user.toml"), "{err}");
+    }
+
+    #[test]
+    #[serial]
+    fn apply_overrides_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let user = root.join("user.toml");
+        fs::write(&user, "[feature_config]\nclip_time = 20\ngenerated_length = 30\n").unwrap();
+        env::remove_var(CLIP_TIME_ENV);
+
+        let mut layered = LayeredConfig::load_from(&root.join("system.toml"), &user, None).unwrap();
+        layered
+            .apply_overrides(&["feature_config.clip_time=5".into(), "groups.ops = a, b".into()])
+            .unwrap();
+        assert_eq!(layered.config.feature_config.clip_time, Some(5));
+        assert_eq!(layered.config.groups["ops"], vec!["a", "b"]);
+        assert_eq!(layered.origin("feature_config.clip_time"), &Origin::Cli);
+        assert_eq!(layered.origin("groups.ops"), &Origin::Cli);
+        assert_eq!(
+            layered.origin("feature_config.generated_length"),
+            &Origin::File(Layer::User, user.clone())
+        );
+
+        assert!(layered.apply_overrides(&["feature_config.clip_time".into()]).is_err());
+        assert!(layered.apply_overrides(&["feature_config.clip_tim=5".into()]).is_err());
+        assert!(layered.apply_overrides(&["feature_config.clip_time=soon".into()]).is_err());
+        assert_eq!(layered.config.feature_config.clip_time, Some(5));
+    }
+}
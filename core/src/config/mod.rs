@@ -1 +1,3 @@
 pub mod cli;
+pub mod keys;
+pub mod layered;
@@ -1,6 +1,8 @@
 #[allow(dead_code)]
 pub(crate) mod default_constants {
     pub const CLIP_TIME: usize = 45;
+    pub const GENERATED_LENGTH: usize = 20;
+    pub const X_SELECTION: &str = "clipboard";
     pub const PGP_EXECUTABLE: &str = "gpg2";
     pub const GIT_EXECUTABLE: &str = "git";
     pub const EDITOR: &str = {
@@ -18,10 +20,17 @@ pub(crate) mod default_constants {
     pub const X11_COPY_EXECUTABLE: &str = "xclip";
     pub const FPR_FILENAME: &str = ".gpg-id";
     pub const BACKUP_EXTENSION: &str = "parsbak";
+    pub const REPO_CONFIG_FILENAME: &str = ".pars.toml";
+    pub const PUBLIC_KEYS_DIR: &str = ".public-keys";
+    pub const GROUPS_FILENAME: &str = ".pars-groups";
+    pub const TRASH_DIR: &str = ".trash";
+    pub const TRASH_RETENTION_DAYS: u32 = 30;
+    pub const ENTRY_NAME_KEY_FILENAME: &str = "pars-entry-names.key";
 }
 
 pub mod env_variables {
     pub const LOG_LEVEL_VAR: &str = "PARS_LOG_LEVEL";
     pub const CONFIG_PATH_ENV: &str = "PARS_CONFIG_PATH";
     pub const CLIP_TIME_ENV: &str = "PARS_CLIP_TIME";
+    pub const PASS_ENV: &str = "PARS_PASS_ENV";
 }
@@ -1,6 +1,19 @@
 use std::fmt::{self, Display, Formatter};
 use std::path::Path;
+use std::process::Command;
+use std::{env, fs, io};
 
+use anyhow::Result;
+use hmac::{Hmac, Mac};
+use log::warn;
+use rand::Rng;
+use sha2::Sha256;
+
+use crate::config::cli::{EntryNameMode, GitConfig};
+use crate::constants::default_constants::ENTRY_NAME_KEY_FILENAME;
+use crate::util::fs_util::write_private_file;
+
+#[derive(Clone)]
 pub enum CommitType {
     Init(Vec<String>),
     Insert(String),
@@ -9,36 +22,66 @@ pub enum CommitType {
     Delete(String),
     Copy((String, String)),
     Rename((String, String)),
+    /// Several changes committed at once
+    Batch(Vec<CommitType>),
 }
 
 pub struct GitCommit<'a> {
     repo_base: &'a Path,
     commit_type: CommitType,
+    git_cfg: &'a GitConfig,
 }
 
 impl<'a> GitCommit<'a> {
-    pub fn new(repo_base: &'a Path, commit_type: CommitType) -> Self {
-        Self { repo_base, commit_type }
+    pub fn new(repo_base: &'a Path, commit_type: CommitType, git_cfg: &'a GitConfig) -> Self {
+        Self { repo_base, commit_type, git_cfg }
     }
 
     pub fn get_commit_msg(&self) -> String {
         match &self.commit_type {
-            CommitType::Init(keys) => {
-                let mut msg =
-                    format!("Init password with {}", keys.first().unwrap_or(&"".to_string()));
-                for key in keys[1..].iter() {
-                    msg.push_str(&format!(", {key}"));
-                }
-                msg
+            CommitType::Batch(changes) => {
+                let summary = render_message(
+                    &self.git_cfg.commit_templates.batch,
+                    vec![("summary", batch_summary(changes))],
+                    self.git_cfg,
+                );
+                changes.iter().fold(format!("{summary}\n"), |mut msg, change| {
+                    msg.push_str(&format!("\n- {}", self.render(change)));
+                    msg
+                })
             }
-            CommitType::Insert(path) => format!("Insert password {path}"),
-            CommitType::Generate(path) => format!("Generate password {path}"),
-            CommitType::Update(path) => format!("Update password {path}"),
-            CommitType::Delete(path) => format!("Delete password {path}"),
-            CommitType::Copy((src, dst)) => format!("Copy {src} to {dst}"),
-            CommitType::Rename((src, dst)) => format!("Rename {src} to {dst}"),
+            commit_type => self.render(commit_type),
         }
     }
+
+    fn render(&self, commit_type: &CommitType) -> String {
+        let templates = &self.git_cfg.commit_templates;
+        let key = match self.git_cfg.entry_names {
+            EntryNameMode::Hash => entry_name_key(self.repo_base)
+                .inspect_err(|e| warn!("Cannot load the key hashing entry names, omit them: {e}"))
+                .ok(),
+            _ => None,
+        };
+        let entry = |name: &str| hide_entry_name(name, &self.git_cfg.entry_names, key.as_deref());
+
+        let (template, vars) = match commit_type {
+            CommitType::Init(keys) => (&templates.init, vec![("keys", keys.join(", "))]),
+            CommitType::Insert(path) => (&templates.insert, vec![("path", entry(path))]),
+            CommitType::Generate(path) => (&templates.generate, vec![("path", entry(path))]),
+            CommitType::Update(path) => (&templates.update, vec![("path", entry(path))]),
+            CommitType::Delete(path) => (&templates.delete, vec![("path", entry(path))]),
+            CommitType::Copy((src, dst)) => {
+                (&templates.copy, vec![("src", entry(src)), ("dst", entry(dst))])
+            }
+            CommitType::Rename((src, dst)) => {
+                (&templates.rename, vec![("src", entry(src)), ("dst", entry(dst))])
+            }
+            CommitType::Batch(changes) => {
+                (&templates.batch, vec![("summary", batch_summary(changes))])
+            }
+        };
+        render_message(template, vars, self.git_cfg)
+    }
 }
 
 impl Display for GitCommit<'_> {
@@ -46,3 +89,230 @@ impl Display for GitCommit<'_> {
         write!(f, "{} for repo {}", self.get_commit_msg(), self.repo_base.display())
     }
 }
+
+fn render_message(template: &str, mut vars: Vec<(&str, String)>, git_cfg: &GitConfig) -> String {
+    if template.contains("{hostname}") {
+        vars.push(("hostname", hostname()));
+    }
+    if template.contains("{user}") {
+        vars.push(("user", username()));
+    }
+    render_template(template, &vars, git_cfg.entry_names != EntryNameMode::Plain)
+}
+
+/// Count changes by kind, e.g. "2 inserted, 1 renamed"
+fn batch_summary(changes: &[CommitType]) -> String {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for change in changes {
+        let kind = match change {
+            CommitType::Init(_) => "initialized",
+            CommitType::Insert(_) => "inserted",
+            CommitType::Generate(_) => "generated",
+            CommitType::Update(_) => "updated",
+            CommitType::Delete(_) => "deleted",
+            CommitType::Copy(_) => "copied",
+            CommitType::Rename(_) => "renamed",
+            CommitType::Batch(_) => "batched",
+        };
+        match counts.iter_mut().find(|(name, _)| *name == kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((kind, 1)),
+        }
+    }
+    counts.iter().map(|(kind, count)| format!("{count} {kind}")).collect::<Vec<_>>().join(", ")
+}
+
+/// Replace every `{name}` in `template` with its value, unknown placeholders are kept as is.
+/// Values are inserted verbatim, braces inside them are not expanded again. With `tidy_empty`,
+/// the spaces around an empty value shrink to one, none at the start or end of a line.
+fn render_template(template: &str, vars: &[(&str, String)], tidy_empty: bool) -> String {
+    let is_blank = |c: char| c == ' ' || c == '\t';
+    let mut result = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                let key = &after[..end];
+                rest = &after[end + 1..];
+                match vars.iter().find(|(name, _)| *name == key) {
+                    Some((_, value)) if tidy_empty && value.is_empty() => {
+                        result.truncate(result.trim_end_matches(is_blank).len());
+                        let trimmed = rest.trim_start_matches(is_blank);
+                        let line_start = result.is_empty() || result.ends_with('\n');
+                        if !line_start && trimmed.len() < rest.len() && !trimmed.starts_with('\n') {
+                            result.push(' ');
+                        }
+                        rest = trimmed;
+                    }
+                    Some((_, value)) => result.push_str(value),
+                    None => {
+                        result.push('{');
+                        result.push_str(key);
+                        result.push('}');
+                    }
+                }
+            }
+            None => {
+                result.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// `name` as `mode` shows it, a hashed name is omitted without a `key`
+fn hide_entry_name(name: &str, mode: &EntryNameMode, key: Option<&[u8]>) -> String {
+    match (mode, key) {
+        (EntryNameMode::Plain, _) => name.to_string(),
+        (EntryNameMode::Hash, Some(key)) => {
+            let mut mac =
+                Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes keys of any length");
+            mac.update(name.as_bytes());
+            mac.finalize().into_bytes().iter().take(8).map(|b| format!("{b:02x}")).collect()
+        }
+        _ => String::new(),
+    }
+}
+
+/// Secret keying the hashed entry names of the store, created on first use. Kept in `.git` so
+/// it is never pushed: without it, guessed names cannot be matched against the history.
+fn entry_name_key(repo_base: &Path) -> Result<Vec<u8>> {
+    let path = repo_base.join(".git").join(ENTRY_NAME_KEY_FILENAME);
+    match fs::read(&path) {
+        Ok(key) if !key.is_empty() => return Ok(key),
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    let key: [u8; 32] = rand::rng().random();
+    write_private_file(&path, &key, true)?;
+    Ok(key.to_vec())
+}
+
+fn hostname() -> String {
+    if let Ok(name) = env::var(if cfg!(windows) { "COMPUTERNAME" } else { "HOSTNAME" }) {
+        return name;
+    }
+    if let Ok(name) = fs::read_to_string("/etc/hostname") {
+        return name.trim().to_string();
+    }
+    Command::new("hostname")
+        .output()
+        .ok()
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+fn username() -> String {
+    env::var(if cfg!(windows) { "USERNAME" } else { "USER" }).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::util::test_util::gen_unique_temp_dir;
+
+    #[test]
+    fn default_templates() {
+        let git_cfg = GitConfig::default();
+        let root = Path::new("/");
+        let msg = |commit_type| GitCommit::new(root, commit_type, &git_cfg).get_commit_msg();
+
+        assert_eq!(msg(CommitType::Init(vec!["A".into(), "B".into()])), "Init password with A, B");
+        assert_eq!(msg(CommitType::Insert("web/github".into())), "Insert password web/github");
+        assert_eq!(msg(CommitType::Rename(("a".into(), "b".into()))), "Rename a to b");
+    }
+
+    #[test]
+    fn custom_templates() {
+        let mut git_cfg = GitConfig::default();
+        git_cfg.commit_templates.insert = "[{user}] add {path} {unknown}".into();
+        git_cfg.commit_templates.copy = "cp {src} -> {dst}".into();
+        let root = Path::new("/");
+
+        let msg =
+            GitCommit::new(root, CommitType::Insert("{dst}".into()), &git_cfg).get_commit_msg();
+        assert_eq!(msg, format!("[{}] add {{dst}} {{unknown}}", username()));
+
+        git_cfg.entry_names = EntryNameMode::Omit;
+        let msg =
+            GitCommit::new(root, CommitType::Delete("bank/pin".into()), &git_cfg).get_commit_msg();
+        assert_eq!(msg, "Delete password");
+        let msg = GitCommit::new(root, CommitType::Copy(("a".into(), "b".into())), &git_cfg)
+            .get_commit_msg();
+        assert_eq!(msg, "cp ->");
+
+        git_cfg.commit_templates.rename = "Rename {src}  to {dst}\n\n  moved {dst}\n".into();
+        let msg = GitCommit::new(root, CommitType::Rename(("a".into(), "b".into())), &git_cfg)
+            .get_commit_msg();
+        assert_eq!(msg, "Rename to\n\n  moved\n");
+    }
+
+    #[test]
+    fn hashed_names() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let (_other_dir, other) = gen_unique_temp_dir();
+        fs::create_dir(root.join(".git")).unwrap();
+        fs::create_dir(other.join(".git")).unwrap();
+        let mut git_cfg = GitConfig { entry_names: EntryNameMode::Hash, ..Default::default() };
+        git_cfg.commit_templates.copy = "cp {src} -> {dst}".into();
+        let msg = |root| {
+            GitCommit::new(root, CommitType::Copy(("a".into(), "b".into())), &git_cfg)
+                .get_commit_msg()
+        };
+
+        let first = msg(&root);
+        let (src, dst) = first.strip_prefix("cp ").unwrap().split_once(" -> ").unwrap();
+        assert!(src.len() == 16 && src.chars().all(|c| c.is_ascii_hexdigit()), "{first}");
+        assert_ne!(src, dst);
+        // Not the plain sha256 of the name, which anyone could compute
+        assert_ne!(src, "ca978112ca1bbdca");
+        assert_eq!(msg(&root), first);
+        assert_ne!(msg(&other), first);
+
+        let key_file = root.join(".git").join(ENTRY_NAME_KEY_FILENAME);
+        assert_eq!(fs::read(&key_file).unwrap().len(), 32);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&key_file).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // Without a place for the key the names are left out
+        let (_bare_dir, bare) = gen_unique_temp_dir();
+        assert_eq!(msg(&bare), "cp ->");
+    }
+
+    #[test]
+    fn render_template_test() {
+        let vars = [("a", "x".to_string()), ("e", String::new())];
+        assert_eq!(render_template("{a} {e} {b}", &vars, false), "x  {b}");
+        assert_eq!(render_template("{a} {e} {b}", &vars, true), "x {b}");
+        assert_eq!(render_template("{e} {a}\n\t{e}\n{a} {e}", &vars, true), "x\n\nx");
+        assert_eq!(render_template("{a}:{e}: {a", &vars, true), "x:: {a");
+    }
+
+    #[test]
+    fn batch_message() {
+        let git_cfg = GitConfig::default();
+        let changes = vec![
+            CommitType::Insert("a".into()),
+            CommitType::Rename(("b".into(), "c".into())),
+            CommitType::Insert("d".into()),
+        ];
+        let msg =
+            GitCommit::new(Path::new("/"), CommitType::Batch(changes), &git_cfg).get_commit_msg();
+        assert_eq!(
+            msg,
+            "Batch update: 2 inserted, 1 renamed\n\n- Insert password a\n- Rename b to c\n- Insert password d"
+        );
+    }
+}
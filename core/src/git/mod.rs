@@ -1,16 +1,134 @@
 pub mod commit;
+pub mod sync;
 
 use std::path::Path;
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 
+use crate::config::cli::ParsConfig;
 use crate::operation::git::git_io;
+use crate::pgp::utils::has_secret_key;
+use crate::util::fs_util::get_dir_gpg_id_content;
 
 pub fn init_repo(git_exe: &str, repo_base: &Path) -> Result<()> {
     git_io(git_exe, repo_base, &["init"])
 }
 
-pub fn add_and_commit(git_exe: &str, repo_base: &Path, commit_msg: &str) -> Result<()> {
+pub fn add_and_commit(config: &ParsConfig, repo_base: &Path, commit_msg: &str) -> Result<()> {
+    let git_exe = &config.executable_config.git_executable;
     git_io(git_exe, repo_base, &["add", "-A"])?;
-    git_io(git_exe, repo_base, &["commit", "-m", commit_msg])
+
+    let mut args = config_args(config, config.git_config.sign_commits);
+    args.push("commit".into());
+    if let Some(key) = signing_key(config, repo_base)? {
+        args.push(format!("--gpg-sign={key}"));
+    }
+    args.push("-m".into());
+    args.push(commit_msg.into());
+    git_io(git_exe, repo_base, &args.iter().map(String::as_str).collect::<Vec<&str>>())
+}
+
+/// Build the `-c key=value` arguments carrying the configured identity, and the pgp program
+/// git should use when `with_gpg` is set. They must precede the git sub command.
+pub(crate) fn config_args(config: &ParsConfig, with_gpg: bool) -> Vec<String> {
+    let git_cfg = &config.git_config;
+    let mut args = Vec::new();
+    if !git_cfg.author_name.is_empty() {
+        args.push("-c".into());
+        args.push(format!("user.name={}", git_cfg.author_name));
+    }
+    if !git_cfg.author_email.is_empty() {
+        args.push("-c".into());
+        args.push(format!("user.email={}", git_cfg.author_email));
+    }
+    if with_gpg {
+        args.push("-c".into());
+        args.push(format!("gpg.program={}", config.executable_config.pgp_executable));
+    }
+    args
+}
+
+/// The key commits should be signed with, `None` if signing is disabled
+pub(crate) fn signing_key(config: &ParsConfig, repo_base: &Path) -> Result<Option<String>> {
+    let git_cfg = &config.git_config;
+    if !git_cfg.sign_commits {
+        return Ok(None);
+    }
+    if !git_cfg.signing_key.is_empty() {
+        return Ok(Some(git_cfg.signing_key.clone()));
+    }
+    // Only a key whose secret part is here can sign, the others belong to other recipients
+    let pgp_exe = &config.executable_config.pgp_executable;
+    match get_dir_gpg_id_content(repo_base, repo_base, &config.groups)?
+        .into_iter()
+        .find(|key| has_secret_key(pgp_exe, key))
+    {
+        Some(key) => Ok(Some(key)),
+        None => Err(anyhow!(
+            "None of the keys in '.gpg-id' of {:?} has a secret key here to sign commits with, \
+             set `git_config.signing_key`",
+            repo_base
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::pgp::key_management::key_gen_batch;
+    use crate::pgp::PGPClient;
+    use crate::util::defer::cleanup;
+    use crate::util::test_util::{
+        clean_up_test_key, gen_unique_temp_dir, get_test_email, get_test_executable,
+        gpg_key_gen_example_batch, write_gpg_id,
+    };
+
+    fn signing_config(executable: &str) -> ParsConfig {
+        let mut config = ParsConfig::default();
+        config.git_config.sign_commits = true;
+        config.executable_config.pgp_executable = executable.to_string();
+        config
+    }
+
+    #[test]
+    fn signing_key_without_secret_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        write_gpg_id(&root, &["nobody@rs.pass"]);
+        let mut config = signing_config(&get_test_executable());
+
+        let err = signing_key(&config, &root).unwrap_err();
+        assert!(err.to_string().contains("set `git_config.signing_key`"), "{err}");
+
+        config.git_config.signing_key = "ABCD".into();
+        assert_eq!(signing_key(&config, &root).unwrap().as_deref(), Some("ABCD"));
+        config.git_config.sign_commits = false;
+        assert_eq!(signing_key(&config, &root).unwrap(), None);
+    }
+
+    #[test]
+    #[serial]
+    #[ignore = "need run interactively"]
+    fn signing_key_test() {
+        let executable = get_test_executable();
+        let email = get_test_email();
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+
+        cleanup!(
+            {
+                key_gen_batch(&executable, &gpg_key_gen_example_batch()).unwrap();
+                let client = PGPClient::new(&executable, &[&email]).unwrap();
+                let fpr = client.get_keys_fpr()[0];
+                write_gpg_id(&root, &["nobody@rs.pass", fpr]);
+
+                let config = signing_config(&executable);
+                assert_eq!(signing_key(&config, &root).unwrap().as_deref(), Some(fpr));
+            },
+            {
+                clean_up_test_key(&executable, &[&email]).unwrap();
+            }
+        );
+    }
 }
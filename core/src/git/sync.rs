@@ -0,0 +1,154 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use log::debug;
+
+use super::{config_args, signing_key};
+use crate::config::cli::ParsConfig;
+use crate::operation::git::{git_io, git_output};
+
+pub struct SyncConfig {
+    pub verify: bool,
+    pub push: bool,
+}
+
+/// Fetch the upstream branch, merge it and push local commits back.
+/// With `verify` set, the merge is refused if any incoming commit is not signed by a key
+/// listed in the allowed signers file.
+pub fn sync(config: &ParsConfig, repo_base: &Path, sync_cfg: &SyncConfig) -> Result<()> {
+    let git_exe = &config.executable_config.git_executable;
+    git_io(git_exe, repo_base, &["fetch"])?;
+
+    if sync_cfg.verify {
+        verify_incoming(config, repo_base)?;
+    }
+
+    let mut args = config_args(config, config.git_config.sign_commits);
+    args.push("merge".into());
+    args.push("--no-edit".into());
+    if let Some(key) = signing_key(config, repo_base)? {
+        args.push(format!("--gpg-sign={key}"));
+    }
+    args.push("@{u}".into());
+    git_io(git_exe, repo_base, &args.iter().map(String::as_str).collect::<Vec<&str>>())?;
+
+    if sync_cfg.push {
+        git_io(git_exe, repo_base, &["push"])?;
+    }
+    Ok(())
+}
+
+/// Check every fetched commit that is not yet part of `HEAD`
+pub fn verify_incoming(config: &ParsConfig, repo_base: &Path) -> Result<()> {
+    let git_exe = &config.executable_config.git_executable;
+    let allowed = load_allowed_signers(repo_base, &config.git_config.allowed_signers)?;
+
+    let pending = git_output(git_exe, repo_base, &["rev-list", "HEAD..@{u}"])?;
+    let mut rejected = Vec::new();
+    for commit in pending.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let mut args = config_args(config, true);
+        args.extend(["log", "-1", "--format=%G?%n%GF%n%GP", commit].map(String::from));
+        let info =
+            git_output(git_exe, repo_base, &args.iter().map(String::as_str).collect::<Vec<_>>())?;
+        debug!("Signature info of {commit}: {info:?}");
+
+        let mut lines = info.lines().map(str::trim);
+        let status = lines.next().unwrap_or("N");
+        // "G" is a good signature, "U" a good one from a key without ownertrust,
+        // the allowed signers list takes the place of the trust model.
+        let trusted = matches!(status, "G" | "U")
+            && lines.filter(|fpr| !fpr.is_empty()).any(|fpr| is_allowed_signer(&allowed, fpr));
+        if !trusted {
+            rejected.push(format!("{commit} (signature status '{status}')"));
+        }
+    }
+
+    if rejected.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!(format!(
+            "Rejected incoming commits not signed by an allowed signer:\n{}",
+            rejected.join("\n")
+        )))
+    }
+}
+
+fn load_allowed_signers(repo_base: &Path, signers_file: &str) -> Result<Vec<String>> {
+    if signers_file.is_empty() {
+        return Err(anyhow!("Signature verification requires 'allowed_signers' in [git_config]"));
+    }
+    let content = fs::read_to_string(repo_base.join(signers_file))
+        .map_err(|e| anyhow!(format!("Cannot read allowed signers '{signers_file}': {e}")))?;
+    Ok(parse_allowed_signers(&content))
+}
+
+/// One fingerprint or long key id per line, anything after it or after a `#` is ignored
+fn parse_allowed_signers(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|id| id.to_uppercase())
+        .collect()
+}
+
+fn is_allowed_signer(allowed: &[String], fpr: &str) -> bool {
+    let fpr = fpr.to_uppercase();
+    allowed.iter().any(|id| *id == fpr || (id.len() >= 16 && fpr.ends_with(id.as_str())))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::git::add_and_commit;
+    use crate::util::test_util::gen_unique_temp_dir;
+
+    #[test]
+    fn allowed_signers_parse() {
+        let content = r#"# team signers
+0123456789ABCDEF0123456789ABCDEF01234567 alice@example.com
+  fedcba9876543210 # bob's long key id
+
+"#;
+        let allowed = parse_allowed_signers(content);
+        assert_eq!(allowed, vec!["0123456789ABCDEF0123456789ABCDEF01234567", "FEDCBA9876543210"]);
+        assert!(is_allowed_signer(&allowed, "0123456789abcdef0123456789abcdef01234567"));
+        assert!(is_allowed_signer(&allowed, "AAAAAAAAAAAAAAAAAAAAAAAAFEDCBA9876543210"));
+        assert!(!is_allowed_signer(&allowed, "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA"));
+    }
+
+    #[test]
+    fn sync_rejects_unsigned_commits() {
+        let (_tmp_dir, base) = gen_unique_temp_dir();
+        let mut config = ParsConfig::default();
+        config.git_config.author_name = "pars-test".into();
+        config.git_config.author_email = "pars@test.local".into();
+        let git = config.executable_config.git_executable.clone();
+
+        let local = base.join("local");
+        let remote = base.join("remote");
+        git_io(&git, &base, &["init", "--bare", "upstream.git"]).unwrap();
+        git_io(&git, &base, &["clone", "upstream.git", "remote"]).unwrap();
+        fs::write(remote.join("first.gpg"), "first").unwrap();
+        add_and_commit(&config, &remote, "first").unwrap();
+        git_io(&git, &remote, &["push", "-u", "origin", "HEAD"]).unwrap();
+
+        git_io(&git, &base, &["clone", "upstream.git", "local"]).unwrap();
+        fs::write(remote.join("second.gpg"), "second").unwrap();
+        add_and_commit(&config, &remote, "second").unwrap();
+        git_io(&git, &remote, &["push"]).unwrap();
+
+        fs::write(local.join(".allowed-signers"), "0123456789ABCDEF\n").unwrap();
+        config.git_config.allowed_signers = ".allowed-signers".into();
+        let verify = SyncConfig { verify: true, push: false };
+        assert!(sync(&config, &local, &verify).is_err());
+        assert!(!local.join("second.gpg").exists());
+
+        let no_verify = SyncConfig { verify: false, push: false };
+        sync(&config, &local, &no_verify).unwrap();
+        assert!(local.join("second.gpg").exists());
+    }
+}
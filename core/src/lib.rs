@@ -1,3 +1,4 @@
+pub mod autotype;
 pub mod clipboard;
 pub mod config;
 pub mod constants;
@@ -0,0 +1,386 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, Write};
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use tar::{Archive, Builder, EntryType, Header};
+use tempfile::NamedTempFile;
+use walkdir::WalkDir;
+
+use crate::pgp::PGPClient;
+use crate::util::fs_util::path_to_str;
+
+/// Hashes of every file of the archive, in the format of `sha256sum`, so an unpacked backup can
+/// also be checked with `sha256sum -c`
+pub const MANIFEST_NAME: &str = "MANIFEST.sha256";
+/// Folder of the archive the store is packed into
+const STORE_PREFIX: &str = "store";
+const ENTRY_EXTENSION: &str = ".gpg";
+
+/// What a backup holds, `files` counts entries, `.gpg-id` files and everything else
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BackupSummary {
+    pub files: usize,
+    pub entries: usize,
+}
+
+/// The plain archive of a backup, a decrypted copy is deleted once this is dropped
+pub struct BackupArchive {
+    path: PathBuf,
+    _decrypted: Option<NamedTempFile>,
+}
+
+impl BackupArchive {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Hashes what is read through it
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        Ok(read)
+    }
+}
+
+/// Pack the store into a tar archive written to `out`, hashing every file while it is appended
+/// and the manifest last. `.git` is only packed on request, the entries are kept as they are,
+/// encrypted.
+pub fn create_backup(root: &Path, include_git: bool, out: impl Write) -> Result<BackupSummary> {
+    let mut summary = BackupSummary::default();
+    let mut manifest = String::new();
+    let mut builder = Builder::new(out);
+    builder.follow_symlinks(false);
+
+    let walker = WalkDir::new(root).follow_links(false).sort_by_file_name().min_depth(1);
+    for entry in walker
+        .into_iter()
+        .filter_entry(|entry| include_git || !(entry.depth() == 1 && entry.file_name() == ".git"))
+    {
+        let entry = entry?;
+        let name = format!(
+            "{STORE_PREFIX}/{}",
+            path_to_str(entry.path().strip_prefix(root)?)?.replace('\\', "/")
+        );
+        let file_type = entry.file_type();
+        let hash = if file_type.is_file() {
+            let mut header = Header::new_gnu();
+            header.set_metadata(&entry.metadata()?);
+            let mut reader =
+                HashingReader { inner: File::open(entry.path())?, hasher: Sha256::new() };
+            builder.append_data(&mut header, &name, &mut reader)?;
+            reader.hasher.finalize()
+        } else if file_type.is_symlink() {
+            builder.append_path_with_name(entry.path(), &name)?;
+            Sha256::digest(path_to_str(&fs::read_link(entry.path())?)?.as_bytes())
+        } else {
+            if file_type.is_dir() {
+                builder.append_path_with_name(entry.path(), &name)?;
+            }
+            continue;
+        };
+        manifest.push_str(&format!("{hash:x}  {name}\n"));
+        summary.files += 1;
+        if file_type.is_file() && name.ends_with(ENTRY_EXTENSION) {
+            summary.entries += 1;
+        }
+    }
+
+    let mut header = Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    builder.append_data(&mut header, MANIFEST_NAME, manifest.as_bytes())?;
+    builder.into_inner()?.flush()?;
+    Ok(summary)
+}
+
+/// Like [`create_backup`], encrypting the archive with `client` into `output_path`. The plain
+/// archive goes through a private temporary file rather than memory.
+pub fn create_encrypted_backup(
+    client: &PGPClient,
+    root: &Path,
+    include_git: bool,
+    output_path: &str,
+) -> Result<BackupSummary> {
+    let mut archive = NamedTempFile::new()?;
+    let summary = create_backup(root, include_git, BufWriter::new(archive.as_file_mut()))?;
+    archive.rewind()?;
+    client.encrypt_reader(BufReader::new(archive.as_file()), output_path)?;
+    Ok(summary)
+}
+
+/// Check every file of the archive against the manifest in a single pass, nothing may be
+/// missing or added. The manifest may come first, as older backups have it, or last.
+pub fn verify_backup(archive: impl Read) -> Result<BackupSummary> {
+    let mut archive = Archive::new(archive);
+    let mut manifest = None;
+    let mut hashes = BTreeMap::new();
+    let mut summary = BackupSummary::default();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let name = path_to_str(&path)?.to_string();
+        if path == Path::new(MANIFEST_NAME) {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            if manifest.replace(parse_manifest(&content)?).is_some() {
+                return Err(anyhow!("The backup holds more than one {MANIFEST_NAME}"));
+            }
+            continue;
+        }
+        let mut components = path.components();
+        if components.next() != Some(Component::Normal(STORE_PREFIX.as_ref()))
+            || !components.all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(anyhow!("Refusing '{name}', it would be unpacked outside the store"));
+        }
+
+        let hash = match entry.header().entry_type() {
+            EntryType::Directory => continue,
+            EntryType::Regular => {
+                let mut hasher = Sha256::new();
+                io::copy(&mut entry, &mut hasher)?;
+                hasher.finalize()
+            }
+            EntryType::Symlink => match entry.link_name()? {
+                Some(target) if link_stays_in_store(&path, &target) => {
+                    Sha256::digest(path_to_str(&target)?.as_bytes())
+                }
+                Some(target) => {
+                    return Err(anyhow!(
+                        "Refusing symlink '{name}', its target {target:?} is outside the store"
+                    ))
+                }
+                None => return Err(anyhow!("Symlink '{name}' has no target")),
+            },
+            other => return Err(anyhow!("Unexpected {other:?} '{name}' in the backup")),
+        };
+        summary.files += 1;
+        if entry.header().entry_type() == EntryType::Regular && name.ends_with(ENTRY_EXTENSION) {
+            summary.entries += 1;
+        }
+        if hashes.insert(name.clone(), format!("{hash:x}")).is_some() {
+            return Err(anyhow!("'{name}' is in the backup more than once"));
+        }
+    }
+
+    let Some(mut expected) = manifest else {
+        return Err(anyhow!("Not a pars backup, it has no {MANIFEST_NAME}"));
+    };
+    for (name, hash) in hashes {
+        match expected.remove(&name) {
+            Some(listed) if listed == hash => {}
+            Some(_) => return Err(anyhow!("'{name}' does not match its hash in the manifest")),
+            None => return Err(anyhow!("'{name}' is not listed in the manifest")),
+        }
+    }
+    match expected.into_keys().next() {
+        Some(name) => {
+            Err(anyhow!("'{name}' is listed in the manifest but missing from the backup"))
+        }
+        None => Ok(summary),
+    }
+}
+
+/// Verify the archive at `archive`, then unpack it into `root`, which must be empty or not exist
+/// yet. Nothing is written when the verification fails.
+pub fn restore_backup(archive: &Path, root: &Path) -> Result<BackupSummary> {
+    let summary = verify_backup(BufReader::new(File::open(archive)?))?;
+    if root.exists() && (!root.is_dir() || fs::read_dir(root)?.next().is_some()) {
+        return Err(anyhow!("{root:?} is not empty, restore into an empty folder"));
+    }
+
+    let parent = match root.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+    let unpack_dir = tempfile::Builder::new().prefix(".pars-restore").tempdir_in(parent)?;
+    let mut archive = Archive::new(BufReader::new(File::open(archive)?));
+    archive.set_preserve_permissions(true);
+    archive.unpack(unpack_dir.path())?;
+    // The manifest stays behind with the unpack folder
+    let store = unpack_dir.path().join(STORE_PREFIX);
+    if root.exists() {
+        fs::remove_dir(root)?;
+    }
+    if store.exists() {
+        fs::rename(store, root)?;
+    } else {
+        fs::create_dir_all(root)?;
+    }
+    Ok(summary)
+}
+
+/// Open a backup written by `create_backup`, decrypting it into a private temporary file first
+/// if it is not a plain archive
+pub fn read_backup(
+    pgp_executable: &str,
+    pgp_options: &[String],
+    path: &Path,
+) -> Result<BackupArchive> {
+    let mut head = Vec::with_capacity(262);
+    File::open(path)?.take(262).read_to_end(&mut head)?;
+    if head.get(257..262) == Some(b"ustar".as_slice()) {
+        return Ok(BackupArchive { path: path.to_path_buf(), _decrypted: None });
+    }
+    let decrypted = NamedTempFile::new()?;
+    let client = PGPClient::new(pgp_executable, &[] as &[&str])?.with_options(pgp_options);
+    client.decrypt_into(Path::new("."), path_to_str(path)?, decrypted.as_file().try_clone()?)?;
+    Ok(BackupArchive { path: decrypted.path().to_path_buf(), _decrypted: Some(decrypted) })
+}
+
+/// Whether the relative `target` of the symlink at `link` resolves below [`STORE_PREFIX`]
+fn link_stays_in_store(link: &Path, target: &Path) -> bool {
+    // Folders between the store and the link
+    let mut depth = link.components().count().saturating_sub(2);
+    for component in target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return false,
+        }
+    }
+    true
+}
+
+fn parse_manifest(content: &str) -> Result<BTreeMap<String, String>> {
+    content
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| match line.split_once("  ") {
+            Some((hash, name)) => Ok((name.to_string(), hash.to_string())),
+            None => Err(anyhow!("Malformed manifest line '{line}'")),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::util::test_util::{create_dir_structure, gen_unique_temp_dir, write_gpg_id};
+
+    fn example_store(root: &Path) {
+        let structure: &[(Option<&str>, &[&str])] =
+            &[(None, &["a.gpg"]), (Some("team"), &["b.gpg"]), (Some(".git"), &["HEAD"])];
+        create_dir_structure(root, structure);
+        write_gpg_id(root, &["alice@rs.pass"]);
+        fs::write(root.join("team/b.gpg"), "ciphertext of b").unwrap();
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let (_tmp_dir, dir) = gen_unique_temp_dir();
+        let root = dir.join("store");
+        example_store(&root);
+
+        let mut archive = Vec::new();
+        let summary = create_backup(&root, false, &mut archive).unwrap();
+        assert_eq!(summary, BackupSummary { files: 3, entries: 2 });
+        assert_eq!(verify_backup(archive.as_slice()).unwrap(), summary);
+
+        let file = dir.join("backup.tar");
+        fs::write(&file, &archive).unwrap();
+        let plain = read_backup("gpg", &[], &file).unwrap();
+        assert_eq!(plain.path(), file);
+        let restored = dir.join("restored");
+        assert_eq!(restore_backup(plain.path(), &restored).unwrap(), summary);
+        assert_eq!(fs::read(restored.join("team/b.gpg")).unwrap(), b"ciphertext of b");
+        assert_eq!(
+            fs::read_to_string(restored.join(".gpg-id")).unwrap(),
+            fs::read_to_string(root.join(".gpg-id")).unwrap()
+        );
+        assert!(!restored.join(".git").exists());
+
+        let mut archive = Vec::new();
+        let summary = create_backup(&root, true, &mut archive).unwrap();
+        assert_eq!(summary.files, 4);
+        fs::write(&file, &archive).unwrap();
+        let with_git = dir.join("with-git");
+        restore_backup(&file, &with_git).unwrap();
+        assert!(with_git.join(".git/HEAD").exists());
+    }
+
+    #[test]
+    fn refuse_test() {
+        let (_tmp_dir, dir) = gen_unique_temp_dir();
+        let root = dir.join("store");
+        example_store(&root);
+        let mut archive = Vec::new();
+        create_backup(&root, false, &mut archive).unwrap();
+        let file = dir.join("backup.tar");
+        fs::write(&file, &archive).unwrap();
+
+        let err = restore_backup(&file, &root).unwrap_err();
+        assert!(err.to_string().contains("is not empty"));
+
+        let offset = archive
+            .windows(b"ciphertext of b".len())
+            .position(|window| window == b"ciphertext of b")
+            .unwrap();
+        let mut tampered = archive.clone();
+        tampered[offset] = b'C';
+        fs::write(&file, &tampered).unwrap();
+        let restored = dir.join("restored");
+        let err = restore_backup(&file, &restored).unwrap_err();
+        assert_eq!(err.to_string(), "'store/team/b.gpg' does not match its hash in the manifest");
+        assert!(!restored.exists());
+
+        let err = verify_backup(b"not an archive".as_slice()).unwrap_err();
+        assert!(!err.to_string().is_empty());
+    }
+
+    fn archive_with_link(name: &str, target: &str) -> Vec<u8> {
+        let manifest = format!("{:x}  {name}\n", Sha256::digest(target.as_bytes()));
+        let mut builder = Builder::new(Vec::new());
+        let mut header = Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o600);
+        builder.append_data(&mut header, MANIFEST_NAME, manifest.as_bytes()).unwrap();
+        let mut header = Header::new_gnu();
+        header.set_entry_type(EntryType::Symlink);
+        header.set_size(0);
+        builder.append_link(&mut header, name, target).unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn symlink_test() {
+        for (name, target) in [("store/a.gpg", "team/b.gpg"), ("store/team/c.gpg", "../a.gpg")] {
+            let summary = verify_backup(archive_with_link(name, target).as_slice()).unwrap();
+            assert_eq!(summary, BackupSummary { files: 1, entries: 0 });
+        }
+        for (name, target) in [
+            ("store/a.gpg", "/etc/passwd"),
+            ("store/a.gpg", "../outside"),
+            ("store/team/c.gpg", "../../store/a.gpg"),
+            ("store/team/c.gpg", "./../sub/../../x"),
+        ] {
+            let err = verify_backup(archive_with_link(name, target).as_slice()).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("Refusing symlink '{name}', its target {target:?} is outside the store")
+            );
+        }
+    }
+
+    #[test]
+    fn parse_manifest_test() {
+        let manifest = parse_manifest("abc  store/a.gpg\ndef  store/dir/b c.gpg\n").unwrap();
+        assert_eq!(manifest.get("store/dir/b c.gpg").map(String::as_str), Some("def"));
+        assert!(parse_manifest("abc store/a.gpg").is_err());
+    }
+}
@@ -0,0 +1,425 @@
+use std::fs;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use secrecy::{ExposeSecret, SecretString};
+use tempfile::TempDir;
+
+use crate::config::cli::ParsConfig;
+use crate::git::add_and_commit;
+use crate::git::commit::{CommitType, GitCommit};
+use crate::operation::copy_or_rename::{copy_rename_io, CopyRenameConfig};
+use crate::operation::generate::IOStreams;
+use crate::operation::git::git_io;
+use crate::pgp::PGPClient;
+use crate::util::fs_util::{
+    better_rename, copy_dir_recursive, create_or_overwrite_bytes, get_dir_gpg_id_content,
+    path_attack_check,
+};
+use crate::{IOErr, IOErrType};
+
+/// A password store on disk, used to group several changes into one commit
+pub struct PasswordStore<'a> {
+    pub root: PathBuf,
+    pub extension: String,
+    pub config: &'a ParsConfig,
+}
+
+/// Collects the changes made inside [`PasswordStore::batch`].
+/// Every path is snapshotted before it is touched for the first time, so the working tree can be
+/// restored if a later step fails.
+pub struct Transaction<'s> {
+    store: &'s PasswordStore<'s>,
+    staging: TempDir,
+    snapshots: Vec<(PathBuf, Option<PathBuf>)>,
+    changes: Vec<CommitType>,
+    /// Set once the changes were added to the git index, a rollback unstages them again
+    staged: bool,
+}
+
+impl<'a> PasswordStore<'a> {
+    pub fn new<P: Into<PathBuf>>(root: P, extension: &str, config: &'a ParsConfig) -> Self {
+        Self { root: root.into(), extension: extension.to_string(), config }
+    }
+
+    /// Run `f` and commit everything it changed as a single commit.
+    /// If `f` or the commit fails, the working tree is rolled back and the error returned.
+    /// Returns the number of changes committed.
+    pub fn batch<F>(&'a self, f: F) -> Result<usize>
+    where
+        F: FnOnce(&mut Transaction<'a>) -> Result<()>,
+    {
+        let mut tx = Transaction {
+            store: self,
+            staging: TempDir::new()?,
+            snapshots: Vec::new(),
+            changes: Vec::new(),
+            staged: false,
+        };
+
+        let result = f(&mut tx).and_then(|_| tx.commit());
+        match result {
+            Ok(count) => Ok(count),
+            Err(e) => {
+                debug!("Batch failed, rolling back {} path(s): {e}", tx.snapshots.len());
+                if let Err(rollback_err) = tx.rollback() {
+                    return Err(anyhow!(format!("{e}\nRollback also failed: {rollback_err}")));
+                }
+                Err(e)
+            }
+        }
+    }
+
+    fn pass_path(&self, pass_name: &str) -> PathBuf {
+        self.root.join(format!("{}.{}", pass_name, self.extension))
+    }
+}
+
+impl Transaction<'_> {
+    /// Encrypt `content` as `pass_name`, an existing entry is only replaced with `force`
+    pub fn insert(&mut self, pass_name: &str, content: &SecretString, force: bool) -> Result<()> {
+        self.insert_bytes(pass_name, content.expose_secret().as_bytes(), force)
+    }
+
+    /// Like [`Transaction::insert`] for any content, a binary entry included
+    pub fn insert_bytes(&mut self, pass_name: &str, content: &[u8], force: bool) -> Result<()> {
+        let root = &self.store.root;
+        let pass_path = self.store.pass_path(pass_name);
+        path_attack_check(root, &pass_path)?;
+
+        let existed = pass_path.exists();
+        if existed && !force {
+            return Err(anyhow!(format!("An entry already exists for {pass_name}")));
+        }
+
+        let parent =
+            pass_path.parent().ok_or_else(|| IOErr::new(IOErrType::InvalidPath, &pass_path))?;
+        if let Some(dir) = topmost_missing(root, parent) {
+            self.snapshot(&dir)?;
+        }
+        self.snapshot(&pass_path)?;
+        fs::create_dir_all(parent)?;
+
+        let keys_fpr = get_dir_gpg_id_content(root, &pass_path, &self.store.config.groups)?;
+        let executable_config = &self.store.config.executable_config;
+        let client = PGPClient::new(&executable_config.pgp_executable, &keys_fpr)?
+            .with_options(&executable_config.pgp_options);
+        create_or_overwrite_bytes(&client, &pass_path, content)?;
+
+        self.changes.push(if existed {
+            CommitType::Update(pass_name.to_string())
+        } else {
+            CommitType::Insert(pass_name.to_string())
+        });
+        Ok(())
+    }
+
+    pub fn rename(&mut self, from: &str, to: &str, force: bool) -> Result<()> {
+        self.copy_or_rename(false, from, to, force)?;
+        self.changes.push(CommitType::Rename((from.to_string(), to.to_string())));
+        Ok(())
+    }
+
+    pub fn copy(&mut self, from: &str, to: &str, force: bool) -> Result<()> {
+        self.copy_or_rename(true, from, to, force)?;
+        self.changes.push(CommitType::Copy((from.to_string(), to.to_string())));
+        Ok(())
+    }
+
+    /// Remove an entry, or a directory when `recursive` is set
+    pub fn remove(&mut self, pass_name: &str, recursive: bool) -> Result<()> {
+        let root = &self.store.root;
+        let dir_path = root.join(pass_name);
+        path_attack_check(root, &dir_path)?;
+
+        if dir_path.is_dir() {
+            if !recursive {
+                return Err(IOErr::new(IOErrType::ExpectFile, &dir_path).into());
+            }
+            self.snapshot(&dir_path)?;
+            fs::remove_dir_all(&dir_path)?;
+        } else {
+            let pass_path = self.store.pass_path(pass_name);
+            if !pass_path.is_file() {
+                return Err(IOErr::new(IOErrType::PathNotExist, &pass_path).into());
+            }
+            self.snapshot(&pass_path)?;
+            fs::remove_file(&pass_path)?;
+        }
+
+        self.changes.push(CommitType::Delete(pass_name.to_string()));
+        Ok(())
+    }
+
+    /// Number of changes recorded so far
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    fn copy_or_rename(&mut self, copy: bool, from: &str, to: &str, force: bool) -> Result<()> {
+        let root = &self.store.root;
+        let extension = &self.store.extension;
+        let from_path = root.join(from);
+        let to_path = root.join(to);
+        path_attack_check(root, &from_path)?;
+        path_attack_check(root, &to_path)?;
+
+        // Every path `copy_rename_io` may write to or remove
+        let mut touched = vec![self.store.pass_path(from), self.store.pass_path(to)];
+        if from_path.is_dir() {
+            touched.push(from_path.clone());
+        }
+        if to_path.is_dir() {
+            touched.push(to_path.clone());
+        } else if let Some(dir) = topmost_missing(root, to_path.parent().unwrap_or(root)) {
+            touched.push(dir);
+        }
+        for path in touched {
+            self.snapshot(&path)?;
+        }
+
+        let copy_cfg = CopyRenameConfig {
+            copy,
+            force,
+            file_extension: extension.clone(),
+            groups: self.store.config.groups.clone(),
+        };
+        let mut in_s = BufReader::new(io::empty());
+        let io_streams =
+            IOStreams { in_s: &mut in_s, out_s: &mut io::sink(), err_s: &mut io::sink() };
+        copy_rename_io(copy_cfg, root, from, to, io_streams)
+    }
+
+    fn snapshot(&mut self, path: &Path) -> Result<()> {
+        if self.snapshots.iter().any(|(snapshot, _)| snapshot == path) {
+            return Ok(());
+        }
+        let backup = if path.is_dir() {
+            let backup = self.staging.path().join(self.snapshots.len().to_string());
+            copy_dir_recursive(path, &backup)?;
+            Some(backup)
+        } else if path.is_file() {
+            let backup = self.staging.path().join(self.snapshots.len().to_string());
+            fs::copy(path, &backup)?;
+            Some(backup)
+        } else {
+            None
+        };
+        self.snapshots.push((path.to_path_buf(), backup));
+        Ok(())
+    }
+
+    fn commit(&mut self) -> Result<usize> {
+        let store = self.store;
+        if self.changes.is_empty() {
+            return Ok(0);
+        }
+        if !store.root.join(".git").exists() {
+            warn!("{:?} is not a git repository, skip commit", store.root);
+            return Ok(self.changes.len());
+        }
+
+        let commit = GitCommit::new(
+            &store.root,
+            CommitType::Batch(self.changes.clone()),
+            &store.config.git_config,
+        );
+        debug!("batch: commit {commit}");
+        self.staged = true;
+        add_and_commit(store.config, &store.root, &commit.get_commit_msg())?;
+        Ok(self.changes.len())
+    }
+
+    fn rollback(&mut self) -> Result<()> {
+        let root = &self.store.root;
+        let staged: Vec<String> = self
+            .snapshots
+            .iter()
+            .filter_map(|(path, _)| path.strip_prefix(root).ok())
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        while let Some((path, backup)) = self.snapshots.pop() {
+            if path.is_dir() {
+                fs::remove_dir_all(&path)?;
+            } else if path.exists() || path.is_symlink() {
+                fs::remove_file(&path)?;
+            }
+            if let Some(backup) = backup {
+                better_rename(backup, &path)?;
+            }
+        }
+
+        if self.staged && !staged.is_empty() {
+            let mut args = vec!["reset", "-q", "--"];
+            args.extend(staged.iter().map(String::as_str));
+            git_io(&self.store.config.executable_config.git_executable, root, &args)?;
+        }
+        Ok(())
+    }
+}
+
+/// The outermost directory between `root` and `dir` that does not exist yet, so that a rollback
+/// also removes the directories created along the way.
+fn topmost_missing(root: &Path, dir: &Path) -> Option<PathBuf> {
+    let mut missing = None;
+    let mut cur = dir;
+    while cur != root && !cur.exists() {
+        missing = Some(cur.to_path_buf());
+        cur = cur.parent()?;
+    }
+    missing
+}
+
+#[cfg(test)]
+mod tests {
+    use std::process::Command;
+
+    use pretty_assertions::assert_eq;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::pgp::key_management::key_gen_batch;
+    use crate::util::defer::cleanup;
+    use crate::util::test_util::*;
+
+    #[test]
+    fn rollback_on_failure() {
+        // Structure:
+        // root
+        // ├── a.gpg
+        // ├── b.gpg
+        // └── dir
+        //     └── c.gpg
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] =
+            &[(None, &["a.gpg", "b.gpg"][..]), (Some("dir"), &["c.gpg"][..])];
+        create_dir_structure(&root, structure);
+        fs::write(root.join("a.gpg"), "a").unwrap();
+
+        let config = ParsConfig::default();
+        let store = PasswordStore::new(&root, "gpg", &config);
+        let res = store.batch(|tx| {
+            tx.rename("a", "new/nested/a", false)?;
+            tx.remove("dir", true)?;
+            tx.remove("b", false)?;
+            assert_eq!(tx.len(), 3);
+            tx.remove("not-exist", false)
+        });
+        assert!(res.is_err());
+
+        assert_eq!(fs::read_to_string(root.join("a.gpg")).unwrap(), "a");
+        assert!(root.join("b.gpg").exists());
+        assert!(root.join("dir").join("c.gpg").exists());
+        assert!(!root.join("new").exists());
+    }
+
+    #[test]
+    fn batch_single_commit() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] =
+            &[(None, &["a.gpg", "b.gpg", "c.gpg"][..]), (Some("dir"), &[][..])];
+        create_dir_structure(&root, structure);
+
+        let mut config = ParsConfig::default();
+        config.git_config.author_name = "pars-test".into();
+        config.git_config.author_email = "pars@test.local".into();
+        let git = &config.executable_config.git_executable;
+        crate::git::init_repo(git, &root).unwrap();
+
+        let store = PasswordStore::new(&root, "gpg", &config);
+        let count = store
+            .batch(|tx| {
+                tx.rename("a", "dir/a", false)?;
+                tx.copy("b", "dir/b", false)?;
+                tx.remove("c", false)
+            })
+            .unwrap();
+        assert_eq!(count, 3);
+        assert!(root.join("dir/a.gpg").exists() && root.join("dir/b.gpg").exists());
+        assert!(!root.join("c.gpg").exists());
+
+        let output =
+            Command::new(git).args(["log", "--format=%s"]).current_dir(&root).output().unwrap();
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            "Batch update: 1 renamed, 1 copied, 1 deleted\n"
+        );
+    }
+
+    #[test]
+    fn failed_commit_unstaged() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] =
+            &[(None, &["a.gpg", "b.gpg"][..]), (Some("dir"), &[][..])];
+        create_dir_structure(&root, structure);
+
+        let mut config = ParsConfig::default();
+        config.git_config.author_name = "pars-test".into();
+        config.git_config.author_email = "pars@test.local".into();
+        let git = config.executable_config.git_executable.clone();
+        crate::git::init_repo(&git, &root).unwrap();
+        let git_out = |args: &[&str]| {
+            let output = Command::new(&git).args(args).current_dir(&root).output().unwrap();
+            String::from_utf8(output.stdout).unwrap()
+        };
+        git_out(&["add", "a.gpg"]);
+        git_out(&["-c", "user.name=t", "-c", "user.email=t@t", "commit", "-qm", "init"]);
+
+        // Signing with a program that always fails makes the commit fail after `git add`
+        config.git_config.sign_commits = true;
+        config.git_config.signing_key = "none".into();
+        config.executable_config.pgp_executable = "false".into();
+        let store = PasswordStore::new(&root, "gpg", &config);
+        let res = store.batch(|tx| {
+            tx.rename("a", "dir/a", false)?;
+            tx.remove("b", false)
+        });
+        assert!(res.unwrap_err().to_string().contains("Failed to run git command"));
+        assert!(root.join("a.gpg").exists() && root.join("b.gpg").exists());
+        assert!(!root.join("dir/a.gpg").exists());
+        assert_eq!(git_out(&["diff", "--cached", "--name-only"]), "");
+        assert_eq!(git_out(&["status", "--porcelain"]), "?? b.gpg\n");
+    }
+
+    #[test]
+    #[serial]
+    fn insert_rollback() {
+        let executable = get_test_executable();
+        let email = get_test_email();
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+
+        cleanup!(
+            {
+                key_gen_batch(&executable, &gpg_key_gen_example_batch()).unwrap();
+                let test_client = PGPClient::new(&executable, &[&email]).unwrap();
+                write_gpg_id(&root, &test_client.get_keys_fpr());
+
+                let mut config = ParsConfig::default();
+                config.executable_config.pgp_executable = executable.clone();
+                let store = PasswordStore::new(&root, "gpg", &config);
+
+                store
+                    .batch(|tx| tx.insert("existing", &SecretString::from("secret"), false))
+                    .unwrap();
+                assert!(root.join("existing.gpg").exists());
+
+                let res = store.batch(|tx| {
+                    tx.insert("team/web/site", &SecretString::from("secret"), false)?;
+                    tx.insert("existing", &SecretString::from("secret"), false)
+                });
+                assert!(res.is_err());
+                assert!(!root.join("team").exists());
+                assert!(root.join("existing.gpg").exists());
+            },
+            {
+                clean_up_test_key(&executable, &[&email]).unwrap();
+            }
+        );
+    }
+}
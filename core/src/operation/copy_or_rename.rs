@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::io::{BufRead, Read, Write};
 use std::path::{Path, PathBuf};
 use std::{fs, path};
@@ -5,6 +6,7 @@ use std::{fs, path};
 use anyhow::Result;
 use log::debug;
 use secrecy::ExposeSecret;
+use walkdir::WalkDir;
 
 use crate::pgp::PGPClient;
 use crate::util::fs_util::{
@@ -16,11 +18,12 @@ pub struct CopyRenameConfig {
     pub copy: bool,
     pub force: bool,
     pub file_extension: String,
+    /// Recipient groups of the config
+    pub groups: BTreeMap<String, Vec<String>>,
 }
 
 use crate::operation::generate::IOStreams;
 
-// Cross repo rename/copy is not supported
 fn handle_overwrite_delete<I, O, E>(
     path_to_overwrite: &Path,
     force: bool,
@@ -208,8 +211,8 @@ where
 
     // Set up clients for decryption and re-encryption
     let from_dir = from_path.parent().unwrap_or(root);
-    let from_keys = get_dir_gpg_id_content(root, from_dir)?;
-    let to_keys = get_dir_gpg_id_content(root, target_dir)?;
+    let from_keys = get_dir_gpg_id_content(root, from_dir, &config.groups)?;
+    let to_keys = get_dir_gpg_id_content(root, target_dir, &config.groups)?;
 
     // Create client for decryption with source keys
     let source_client = match PGPClient::new("gpg", &from_keys) {
@@ -230,7 +233,7 @@ where
     };
 
     // Decrypt the file
-    let content = match source_client.decrypt_stdin(root, path_to_str(from_path)?) {
+    let content = match source_client.decrypt_bytes(root, path_to_str(from_path)?) {
         Ok(content) => content,
         Err(e) => {
             writeln!(io_streams.err_s, "Error decrypting file: {e}")?;
@@ -239,7 +242,7 @@ where
     };
 
     // Encrypt with the destination keys
-    match target_client.encrypt(content.expose_secret(), path_to_str(&target_file)?) {
+    match target_client.encrypt_bytes(content.expose_secret(), path_to_str(&target_file)?) {
         Ok(_) => {
             if !config.copy {
                 // If this was a move operation, delete the original file
@@ -310,7 +313,10 @@ where
         };
 
         // Compare GPG keys between source and destination directories
-        match (get_dir_gpg_id_content(root, from_dir), get_dir_gpg_id_content(root, to_dir)) {
+        match (
+            get_dir_gpg_id_content(root, from_dir, &config.groups),
+            get_dir_gpg_id_content(root, to_dir, &config.groups),
+        ) {
             (Ok(from_keys), Ok(to_keys)) => {
                 let mut from_keys_sorted = from_keys.clone();
                 let mut to_keys_sorted = to_keys.clone();
@@ -352,6 +358,172 @@ where
     }
 }
 
+/// Copy or rename a file or directory from one store into another. Every entry is decrypted
+/// and encrypted again for the `.gpg-id` of its destination directory, other files such as
+/// `.gpg-id` itself are not carried over.
+///
+/// All entries are encrypted into a staging folder first, the destination only changes once
+/// every one of them succeeded. A move then deletes the source entries, other files and the
+/// folders still holding them stay in the source store.
+/// Returns the number of entries written to the destination store.
+pub fn cross_copy_rename_io<I, O, E>(
+    config: CopyRenameConfig,
+    pgp_executable: &str,
+    pgp_options: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
+    (from_root, from): (&Path, &str),
+    (to_root, to): (&Path, &str),
+    mut io_streams: IOStreams<I, O, E>,
+) -> Result<usize>
+where
+    I: Read + BufRead,
+    O: Write,
+    E: Write,
+{
+    let mut from_path = from_root.join(from);
+    let to_path = to_root.join(to);
+    path_attack_check(from_root, &from_path)?;
+    path_attack_check(to_root, &to_path)?;
+
+    if !from_path.is_dir() {
+        from_path =
+            PathBuf::from(format!("{}.{}", path_to_str(&from_path)?, config.file_extension));
+        if !from_path.is_file() {
+            return Err(IOErr::new(IOErrType::PathNotExist, &from_root.join(from)).into());
+        }
+    }
+    debug!("cross_copy_rename_io: from_path: {from_path:?}, to_path: {to_path:?}");
+
+    let to_is_dir = to.is_empty() || to.ends_with(['/', '\\']);
+    if to_is_dir && !to_path.is_dir() {
+        return Err(IOErr::new(IOErrType::PathNotExist, &to_path).into());
+    }
+    // Like a local copy, an existing directory receives the source under its own name
+    let target = if to_path.is_dir() {
+        let file_name = from_path
+            .file_name()
+            .ok_or_else(|| IOErr::new(IOErrType::CannotGetFileName, &from_path))?;
+        to_path.join(file_name)
+    } else if from_path.is_dir() {
+        to_path
+    } else {
+        PathBuf::from(format!("{}.{}", path_to_str(&to_path)?, config.file_extension))
+    };
+
+    let entries: Vec<(PathBuf, PathBuf)> = if from_path.is_dir() {
+        let mut entries = Vec::new();
+        for entry in WalkDir::new(&from_path).sort_by_file_name() {
+            let entry = entry?;
+            let is_secret =
+                entry.path().extension().is_some_and(|ext| ext == config.file_extension.as_str());
+            if entry.file_type().is_file() && is_secret {
+                let relative = entry.path().strip_prefix(&from_path)?;
+                entries.push((entry.path().to_path_buf(), target.join(relative)));
+            }
+        }
+        entries
+    } else {
+        vec![(from_path.clone(), target)]
+    };
+
+    let mut accepted = Vec::with_capacity(entries.len());
+    for (source, dest) in entries {
+        if dest.exists() && !confirm_overwrite(&dest, config.force, &mut io_streams)? {
+            continue;
+        }
+        accepted.push((source, dest));
+    }
+    if accepted.is_empty() {
+        return Ok(0);
+    }
+
+    // Dropped along with whatever was staged when an entry fails
+    let staging = tempfile::Builder::new().prefix(".pars-staging").tempdir_in(to_root)?;
+    let mut staged = Vec::with_capacity(accepted.len());
+    for (index, (source, dest)) in accepted.iter().enumerate() {
+        let source_dir = source.parent().unwrap_or(from_root);
+        let dest_dir = dest.parent().unwrap_or(to_root);
+        let source_client = PGPClient::new(
+            pgp_executable,
+            &get_dir_gpg_id_content(from_root, source_dir, groups)?,
+        )?
+        .with_options(pgp_options);
+        let dest_client = PGPClient::new(
+            pgp_executable,
+            &get_dir_gpg_id_content(to_root, nearest_existing(to_root, dest_dir), groups)?,
+        )?
+        .with_options(pgp_options);
+        let content = source_client.decrypt_bytes(from_root, path_to_str(source)?)?;
+        let staged_path = staging.path().join(index.to_string());
+        dest_client.encrypt_bytes(content.expose_secret(), path_to_str(&staged_path)?)?;
+        staged.push(staged_path);
+    }
+
+    for (staged_path, (_, dest)) in staged.iter().zip(&accepted) {
+        if dest.is_dir() {
+            fs::remove_dir_all(dest)?;
+        }
+        fs::create_dir_all(dest.parent().unwrap_or(to_root))?;
+        fs::rename(staged_path, dest)?;
+    }
+
+    if !config.copy {
+        for (source, _) in &accepted {
+            fs::remove_file(source)?;
+        }
+        // Folders emptied by the move go as well, the ones keeping other files stay
+        if from_path.is_dir() {
+            for entry in WalkDir::new(&from_path).contents_first(true) {
+                let entry = entry?;
+                if entry.file_type().is_dir() && fs::read_dir(entry.path())?.next().is_none() {
+                    fs::remove_dir(entry.path())?;
+                }
+            }
+        }
+    }
+    Ok(accepted.len())
+}
+
+/// Ask before replacing `path`, which is only replaced once every entry is encrypted
+fn confirm_overwrite<I, O, E>(
+    path: &Path,
+    force: bool,
+    io_streams: &mut IOStreams<I, O, E>,
+) -> Result<bool>
+where
+    I: Read + BufRead,
+    O: Write,
+    E: Write,
+{
+    if force {
+        return Ok(true);
+    }
+    io_streams.out_s.write_fmt(format_args!(
+        "File {} already exists, overwrite? [y/N]: ",
+        path.to_string_lossy()
+    ))?;
+    io_streams.out_s.flush()?;
+    let mut input = String::new();
+    io_streams.in_s.read_line(&mut input)?;
+    if !input.trim().to_lowercase().starts_with('y') {
+        io_streams.out_s.write_all("Canceled\n".as_bytes())?;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// The closest directory of `dir` that exists, a destination folder is only created on success
+fn nearest_existing<'a>(root: &Path, dir: &'a Path) -> &'a Path {
+    let mut dir = dir;
+    while !dir.is_dir() && dir != root {
+        match dir.parent() {
+            Some(parent) => dir = parent,
+            None => break,
+        }
+    }
+    dir
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::{self, BufReader};
@@ -398,6 +570,7 @@ mod tests {
                     copy: false,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 // Rename a.gpg to c.gpg
@@ -420,6 +593,7 @@ mod tests {
                     copy: false,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 copy_rename_io(rename_config, &root, "b", "c", io_streams).unwrap();
@@ -435,6 +609,7 @@ mod tests {
                     copy: false,
                     force: true,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 copy_rename_io(rename_config, &root, "b", "c", io_streams).unwrap();
@@ -451,6 +626,7 @@ mod tests {
                     copy: false,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 copy_rename_io(
@@ -474,6 +650,7 @@ mod tests {
                     copy: false,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 copy_rename_io(rename_config, &root, "d_dir", "e_dir", io_streams).unwrap();
@@ -517,6 +694,7 @@ mod tests {
                     copy: true,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 copy_rename_io(copy_config, &root, "a", "c", io_streams).unwrap();
@@ -540,6 +718,7 @@ mod tests {
                     copy: true,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 copy_rename_io(copy_config, &root, "b", "c", io_streams).unwrap();
@@ -551,8 +730,12 @@ mod tests {
                 let io_streams =
                     IOStreams { in_s: &mut stdin, out_s: &mut stdout, err_s: &mut stderr };
 
-                let copy_config =
-                    CopyRenameConfig { copy: true, force: true, file_extension: "gpg".to_string() };
+                let copy_config = CopyRenameConfig {
+                    copy: true,
+                    force: true,
+                    file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
+                };
 
                 copy_rename_io(copy_config, &root, "b", "c", io_streams).unwrap();
                 assert_eq!("foo_b", fs::read_to_string(root.join("c.gpg")).unwrap());
@@ -569,6 +752,7 @@ mod tests {
                     copy: true,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 copy_rename_io(
@@ -592,6 +776,7 @@ mod tests {
                     copy: true,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 copy_rename_io(copy_config, &root, "d_dir", "e_dir", io_streams).unwrap();
@@ -628,6 +813,7 @@ mod tests {
                         copy: false,
                         force: true,
                         file_extension: "gpg".to_string(),
+                        groups: BTreeMap::new(),
                     },
                     &root,
                     "../../a",
@@ -646,7 +832,12 @@ mod tests {
                     IOStreams { in_s: &mut stdin, out_s: &mut stdout, err_s: &mut stderr };
 
                 if copy_rename_io(
-                    CopyRenameConfig { copy: true, force: true, file_extension: "gpg".to_string() },
+                    CopyRenameConfig {
+                        copy: true,
+                        force: true,
+                        file_extension: "gpg".to_string(),
+                        groups: BTreeMap::new(),
+                    },
                     &root,
                     "a",
                     "../../c",
@@ -689,6 +880,7 @@ mod tests {
                     copy: false,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 // Try to rename a.gpg to non_existent_dir/b.gpg, should fail
@@ -728,6 +920,7 @@ mod tests {
                     copy: true,
                     force: false,
                     file_extension: "gpg".to_string(),
+                    groups: BTreeMap::new(),
                 };
 
                 // Try to copy a.gpg to non_existent_dir/b.gpg, should fail
@@ -814,6 +1007,7 @@ Passphrase: password
                         copy: true,
                         force: false,
                         file_extension: "gpg".to_string(),
+                        groups: BTreeMap::new(),
                     },
                     &root,
                     "file1",
@@ -844,6 +1038,7 @@ Passphrase: password
                         copy: false, // false = move instead of copy
                         force: false,
                         file_extension: "gpg".to_string(),
+                        groups: BTreeMap::new(),
                     },
                     &root,
                     "file2",
@@ -867,4 +1062,118 @@ Passphrase: password
             }
         );
     }
+
+    #[test]
+    #[serial]
+    #[ignore = "need run interactively"]
+    fn cross_store_test() {
+        // personal                 work
+        // ├── .gpg-id              └── .gpg-id
+        // ├── a.gpg
+        // ├── broken
+        // │   └── a.gpg
+        // └── dir
+        //     ├── .gpg-id
+        //     ├── b.gpg
+        //     └── sub
+        //         └── c.gpg
+        let executable = get_test_executable();
+        let email = get_test_email();
+        let (_tmp_dir, base) = gen_unique_temp_dir();
+        let personal = base.join("personal");
+        let work = base.join("work");
+        let structure: &[(Option<&str>, &[&str])] =
+            &[(Some("dir/sub"), &[][..]), (Some("broken"), &[][..])];
+        create_dir_structure(&personal, structure);
+        fs::create_dir_all(&work).unwrap();
+
+        cleanup!(
+            {
+                key_gen_batch(&executable, &gpg_key_gen_example_batch()).unwrap();
+                let client = PGPClient::new(&executable, &[&email]).unwrap();
+                client.key_edit_batch(&gpg_key_edit_example_batch()).unwrap();
+                write_gpg_id(&personal, &client.get_keys_fpr());
+                write_gpg_id(&work, &client.get_keys_fpr());
+                write_gpg_id(&personal.join("dir"), &client.get_keys_fpr());
+                for (name, content) in [
+                    ("a", "secret a"),
+                    ("dir/b", "secret b"),
+                    ("dir/sub/c", "c"),
+                    ("broken/a", "a"),
+                ] {
+                    let path = personal.join(format!("{name}.gpg"));
+                    client.encrypt(content, path.to_str().unwrap()).unwrap();
+                }
+
+                let mut stdin = BufReader::new(io::empty());
+                let mut stdout = io::sink();
+                let mut stderr = io::sink();
+                let cfg = |copy| CopyRenameConfig {
+                    copy,
+                    force: false,
+                    file_extension: "gpg".into(),
+                    groups: BTreeMap::new(),
+                };
+
+                let io_streams =
+                    IOStreams { in_s: &mut stdin, out_s: &mut stdout, err_s: &mut stderr };
+                let written = cross_copy_rename_io(
+                    cfg(true),
+                    &executable,
+                    &[],
+                    &BTreeMap::new(),
+                    (&personal, "a"),
+                    (&work, "new/a"),
+                    io_streams,
+                )
+                .unwrap();
+                assert_eq!(written, 1);
+                assert!(personal.join("a.gpg").exists());
+                let decrypted =
+                    client.decrypt_stdin(&work, work.join("new/a.gpg").to_str().unwrap()).unwrap();
+                assert_eq!(decrypted.expose_secret(), "secret a");
+
+                let io_streams =
+                    IOStreams { in_s: &mut stdin, out_s: &mut stdout, err_s: &mut stderr };
+                let written = cross_copy_rename_io(
+                    cfg(false),
+                    &executable,
+                    &[],
+                    &BTreeMap::new(),
+                    (&personal, "dir"),
+                    (&work, "new/"),
+                    io_streams,
+                )
+                .unwrap();
+                assert_eq!(written, 2);
+                assert!(!personal.join("dir/b.gpg").exists());
+                assert!(!personal.join("dir/sub").exists());
+                assert!(personal.join("dir/.gpg-id").exists());
+                assert!(work.join("new/dir/b.gpg").exists());
+                assert!(work.join("new/dir/sub/c.gpg").exists());
+                assert!(!work.join("new/dir/.gpg-id").exists());
+
+                // Nothing changes on either side when one of the entries fails
+                fs::write(personal.join("broken/z.gpg"), "not encrypted").unwrap();
+                let io_streams =
+                    IOStreams { in_s: &mut stdin, out_s: &mut stdout, err_s: &mut stderr };
+                let res = cross_copy_rename_io(
+                    cfg(false),
+                    &executable,
+                    &[],
+                    &BTreeMap::new(),
+                    (&personal, "broken"),
+                    (&work, ""),
+                    io_streams,
+                );
+                assert!(res.is_err());
+                assert!(personal.join("broken/a.gpg").exists());
+                assert!(!work.join("broken").exists());
+                assert_eq!(fs::read_dir(&work).unwrap().count(), 2);
+            },
+            {
+                clean_up_test_key(&executable, &[&email]).unwrap();
+            }
+        );
+    }
 }
@@ -0,0 +1,439 @@
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::clipboard::clipboard_backend;
+use crate::config::cli::ParsConfig;
+use crate::config::keys::validate_file;
+use crate::constants::default_constants::{
+    BACKUP_EXTENSION, FPR_FILENAME, GROUPS_FILENAME, PUBLIC_KEYS_DIR, REPO_CONFIG_FILENAME,
+};
+use crate::operation::git::git_output;
+use crate::pgp::groups::expand_recipients;
+use crate::pgp::utils::{check_pgp_key, has_secret_key};
+use crate::util::fs_util::{
+    find_executable_in_path, get_dir_gpg_id_content, is_executable, read_gpg_id, walk_store,
+};
+
+/// Files of a store that are not entries but belong there
+const STORE_FILES: &[&str] =
+    &[FPR_FILENAME, REPO_CONFIG_FILENAME, GROUPS_FILENAME, ".gitattributes", ".gitignore"];
+/// Paths listed in a single check before the rest is only counted
+const MAX_LISTED: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub name: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    /// What to do about it, only set when something is wrong
+    pub fix: Option<String>,
+}
+
+pub struct DoctorConfig<'a> {
+    pub config: &'a ParsConfig,
+    pub root: &'a Path,
+    pub editor: &'a str,
+    /// Config files in use, each one is validated
+    pub config_files: &'a [PathBuf],
+    /// Why the config could not be loaded, the defaults are checked instead
+    pub config_error: Option<&'a str>,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Ok => write!(f, "ok"),
+            Severity::Warning => write!(f, "warn"),
+            Severity::Error => write!(f, "fail"),
+        }
+    }
+}
+
+impl Check {
+    fn ok(name: &'static str, message: impl Into<String>) -> Self {
+        Check { name, severity: Severity::Ok, message: message.into(), fix: None }
+    }
+
+    fn warning(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check { name, severity: Severity::Warning, message: message.into(), fix: Some(fix.into()) }
+    }
+
+    fn error(name: &'static str, message: impl Into<String>, fix: impl Into<String>) -> Self {
+        Check { name, severity: Severity::Error, message: message.into(), fix: Some(fix.into()) }
+    }
+}
+
+/// Check the environment pars runs in and the store at `root`
+pub fn diagnose(cfg: &DoctorConfig) -> Vec<Check> {
+    let mut checks = check_config(cfg.config_files, cfg.config_error);
+
+    let pgp_exe = &cfg.config.executable_config.pgp_executable;
+    let pgp_check = check_pgp(pgp_exe);
+    let pgp_found = pgp_check.severity != Severity::Error;
+    checks.push(pgp_check);
+
+    if !cfg.root.join(FPR_FILENAME).is_file() {
+        checks.push(Check::error(
+            "store",
+            format!("No store at '{}'", cfg.root.display()),
+            "Create one with `pars init <gpg-id>`, or select another with `-R`",
+        ));
+        checks.extend(check_environment(cfg));
+        return checks;
+    }
+    checks.push(Check::ok("store", cfg.root.display().to_string()));
+
+    if pgp_found {
+        checks.extend(check_keys(pgp_exe, cfg.root, &cfg.config.groups));
+    }
+    checks.extend(check_git(cfg.config, cfg.root));
+    checks.extend(check_environment(cfg));
+    #[cfg(unix)]
+    checks.push(check_permissions(cfg.root));
+    checks.push(check_backups(cfg.root));
+    checks.push(check_foreign_files(cfg.root));
+    checks
+}
+
+fn check_config(files: &[PathBuf], load_error: Option<&str>) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let mut reported = false;
+    for path in files {
+        match validate_file(path) {
+            Ok(problems) if problems.is_empty() => {}
+            Ok(problems) => {
+                for problem in problems {
+                    checks.push(Check::warning(
+                        "config",
+                        format!("{}: {}: {}", path.display(), problem.key, problem.message),
+                        format!("Fix it with `pars config edit`, or edit '{}'", path.display()),
+                    ));
+                }
+            }
+            Err(e) => {
+                reported = true;
+                checks.push(Check::error(
+                    "config",
+                    format!("{}: {e}", path.display()),
+                    format!("Fix it with `pars config edit`, or edit '{}'", path.display()),
+                ));
+            }
+        }
+    }
+    if let Some(e) = load_error.filter(|_| !reported) {
+        checks.push(Check::error("config", e, "Fix it with `pars config edit`"));
+    }
+    if checks.is_empty() {
+        checks.push(Check::ok("config", format!("{} file(s) loaded", files.len())));
+    }
+    checks
+}
+
+fn check_pgp(pgp_exe: &str) -> Check {
+    match Command::new(pgp_exe).arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let version = stdout.lines().next().unwrap_or_default().to_string();
+            if version.contains(" 1.") {
+                Check::warning(
+                    "gpg",
+                    format!("{version}, GnuPG 1 lacks features pars relies on"),
+                    "Install GnuPG 2 and set `executable_config.pgp_executable` to it",
+                )
+            } else {
+                Check::ok("gpg", version)
+            }
+        }
+        _ => Check::error(
+            "gpg",
+            format!("Cannot run '{pgp_exe}'"),
+            "Install GnuPG, or point `pars config set executable_config.pgp_executable` at it",
+        ),
+    }
+}
+
+/// Every key of every `.gpg-id` must be in the keyring and usable
+fn check_keys(pgp_exe: &str, root: &Path, groups: &BTreeMap<String, Vec<String>>) -> Vec<Check> {
+    let mut checks = Vec::new();
+    let mut key_count = 0;
+    for entry in walk_store(root).flatten() {
+        if entry.file_name() != FPR_FILENAME || !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = relative(root, entry.path());
+        let ids = read_gpg_id(entry.path()).unwrap_or_default();
+        if ids.is_empty() {
+            checks.push(Check::error(
+                "keys",
+                format!("{rel} lists no key"),
+                format!("Add a key to {rel}, or remove the file to use the parent's keys"),
+            ));
+        }
+        let ids = match expand_recipients(root, &ids, groups) {
+            Ok(ids) => ids,
+            Err(e) => {
+                checks.push(Check::error(
+                    "keys",
+                    format!("{rel}: {e}"),
+                    format!(
+                        "Define the group in {GROUPS_FILENAME} or under [groups] of the config"
+                    ),
+                ));
+                continue;
+            }
+        };
+        for id in &ids {
+            key_count += 1;
+            if let Err(e) = check_pgp_key(pgp_exe, id) {
+                let fix = match e.to_string().as_str() {
+                    "not in the keyring" if root.join(PUBLIC_KEYS_DIR).is_dir() => format!(
+                        "Import the key with `pars recipients import` or `gpg --import`, or \
+                         remove it from {rel}"
+                    ),
+                    "not in the keyring" => {
+                        format!("Import the key with `gpg --import`, or remove it from {rel}")
+                    }
+                    "expired" => {
+                        format!("Extend {id} with `gpg --quick-set-expire <fingerprint> 1y`")
+                    }
+                    _ => format!("Replace '{id}' in {rel} with a usable key"),
+                };
+                checks.push(Check::error("keys", format!("{rel}: '{id}' {e}"), fix));
+            }
+        }
+    }
+
+    let root_ids = get_dir_gpg_id_content(root, root, groups).unwrap_or_default();
+    if !root_ids.iter().any(|id| has_secret_key(pgp_exe, id)) {
+        checks.push(Check::warning(
+            "keys",
+            format!(
+                "No secret key of the recipients in {FPR_FILENAME}, entries cannot be decrypted"
+            ),
+            "Import your secret key with `gpg --import`",
+        ));
+    }
+    if checks.is_empty() {
+        checks.push(Check::ok("keys", format!("{key_count} recipient(s) usable")));
+    }
+    checks
+}
+
+fn check_git(config: &ParsConfig, root: &Path) -> Vec<Check> {
+    let git_exe = &config.executable_config.git_executable;
+    if Command::new(git_exe).arg("--version").output().is_err() {
+        return vec![Check::warning(
+            "git",
+            format!("Cannot run '{git_exe}', changes are not versioned"),
+            "Install git, or point `pars config set executable_config.git_executable` at it",
+        )];
+    }
+    if !root.join(".git").exists() {
+        return vec![Check::warning(
+            "git",
+            "The store is not a git repository",
+            "Track changes with `pars git init`",
+        )];
+    }
+
+    let mut checks = Vec::new();
+    let remotes = git_output(git_exe, root, &["remote"]).unwrap_or_default();
+    if remotes.trim().is_empty() {
+        checks.push(Check::warning(
+            "git",
+            "No remote, changes stay on this machine",
+            "Add one with `pars git remote add origin <url>`",
+        ));
+    }
+    let has_identity = !config.git_config.author_email.is_empty()
+        || git_output(git_exe, root, &["config", "user.email"])
+            .is_ok_and(|email| !email.trim().is_empty());
+    if !has_identity {
+        checks.push(Check::warning(
+            "git",
+            "No author email, commits will fail",
+            "Set one with `pars config set git_config.author_email <email>`",
+        ));
+    }
+    if checks.is_empty() {
+        let remotes: Vec<&str> = remotes.lines().collect();
+        checks.push(Check::ok("git", format!("remote {}", remotes.join(", "))));
+    }
+    checks
+}
+
+fn check_environment(cfg: &DoctorConfig) -> Vec<Check> {
+    let clipboard = match clipboard_backend() {
+        Ok(backend) => Check::ok("clipboard", backend),
+        Err(e) => Check::warning(
+            "clipboard",
+            e.to_string(),
+            "Install wl-clipboard on Wayland or xclip on X11 to use `--clip`",
+        ),
+    };
+
+    // The editor may come with arguments, like `code --wait`
+    let program = cfg.editor.split_whitespace().next().unwrap_or_default();
+    let editor_found = find_executable_in_path(program).is_some()
+        || is_executable(Path::new(program)).unwrap_or(false);
+    let editor = if editor_found {
+        Check::ok("editor", program)
+    } else {
+        Check::warning(
+            "editor",
+            format!("Cannot find '{program}'"),
+            "Set PARS_EDITOR or `pars config set executable_config.editor_executable <editor>`",
+        )
+    };
+    vec![clipboard, editor]
+}
+
+#[cfg(unix)]
+fn check_permissions(root: &Path) -> Check {
+    use std::os::unix::fs::PermissionsExt;
+
+    let exposed: Vec<String> = walk_store(root)
+        .flatten()
+        .filter(|entry| {
+            entry.metadata().is_ok_and(|metadata| metadata.permissions().mode() & 0o077 != 0)
+        })
+        .map(|entry| relative(root, entry.path()))
+        .collect();
+    if exposed.is_empty() {
+        Check::ok("permissions", "only readable by you")
+    } else {
+        Check::warning(
+            "permissions",
+            format!("Readable by others: {}", list_paths(&exposed)),
+            format!(
+                "Run `chmod -R go-rwx '{}'` and set `feature_config.umask` to 077",
+                root.display()
+            ),
+        )
+    }
+}
+
+/// Backups are left behind when pars is interrupted while rewriting an entry
+fn check_backups(root: &Path) -> Check {
+    let backups = store_files(root, |name| name.ends_with(&format!(".{BACKUP_EXTENSION}")));
+    if backups.is_empty() {
+        Check::ok("backups", format!("no stray .{BACKUP_EXTENSION} file"))
+    } else {
+        Check::warning(
+            "backups",
+            format!("Left over: {}", list_paths(&backups)),
+            "Rename a backup back to `.gpg` when its entry is missing or broken, delete it otherwise",
+        )
+    }
+}
+
+fn check_foreign_files(root: &Path) -> Check {
+    let foreign = store_files(root, |name| {
+        !name.ends_with(".gpg")
+            && !name.ends_with(&format!(".{BACKUP_EXTENSION}"))
+            && !STORE_FILES.contains(&name)
+    });
+    if foreign.is_empty() {
+        Check::ok("files", "only entries in the store")
+    } else {
+        Check::warning(
+            "files",
+            format!("Not entries, possibly unencrypted: {}", list_paths(&foreign)),
+            "Encrypt them with `pars insert`, or move them out of the store",
+        )
+    }
+}
+
+fn store_files(root: &Path, filter: impl Fn(&str) -> bool) -> Vec<String> {
+    walk_store(root)
+        .flatten()
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| filter(&entry.file_name().to_string_lossy()))
+        .map(|entry| relative(root, entry.path()))
+        .collect()
+}
+
+fn relative(root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(rel) if rel.as_os_str().is_empty() => ".".into(),
+        Ok(rel) => rel.display().to_string(),
+        Err(_) => path.display().to_string(),
+    }
+}
+
+fn list_paths(paths: &[String]) -> String {
+    let mut res = paths.iter().take(MAX_LISTED).cloned().collect::<Vec<_>>().join(", ");
+    if paths.len() > MAX_LISTED {
+        res.push_str(&format!(" and {} more", paths.len() - MAX_LISTED));
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::util::test_util::{create_dir_structure, gen_unique_temp_dir};
+
+    #[test]
+    fn store_files_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] = &[
+            (None, &[".gpg-id", ".gitattributes", "a.gpg", "notes.txt"]),
+            (Some("dir"), &[".gpg-id", "b.gpg", "b.gpg.parsbak"]),
+            (Some(".git"), &["config"]),
+        ];
+        create_dir_structure(&root, structure);
+
+        let backups = check_backups(&root);
+        assert_eq!(backups.severity, Severity::Warning);
+        assert!(backups.message.contains("dir/b.gpg.parsbak"), "{}", backups.message);
+
+        let foreign = check_foreign_files(&root);
+        assert_eq!(foreign.severity, Severity::Warning);
+        assert_eq!(foreign.message, "Not entries, possibly unencrypted: notes.txt");
+
+        fs::remove_file(root.join("notes.txt")).unwrap();
+        fs::remove_file(root.join("dir/b.gpg.parsbak")).unwrap();
+        assert_eq!(check_foreign_files(&root).severity, Severity::Ok);
+        assert_eq!(check_backups(&root).severity, Severity::Ok);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permissions_test() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        create_dir_structure(&root, &[(Some("dir"), &["a.gpg"])]);
+        for path in [root.clone(), root.join("dir")] {
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o700)).unwrap();
+        }
+        fs::set_permissions(root.join("dir/a.gpg"), fs::Permissions::from_mode(0o600)).unwrap();
+        assert_eq!(check_permissions(&root).severity, Severity::Ok);
+
+        fs::set_permissions(root.join("dir/a.gpg"), fs::Permissions::from_mode(0o644)).unwrap();
+        let check = check_permissions(&root);
+        assert_eq!(check.severity, Severity::Warning);
+        assert_eq!(check.message, "Readable by others: dir/a.gpg");
+    }
+
+    #[test]
+    fn list_paths_test() {
+        let paths: Vec<String> = (0..7).map(|i| i.to_string()).collect();
+        assert_eq!(list_paths(&paths[..2]), "0, 1");
+        assert_eq!(list_paths(&paths), "0, 1, 2, 3, 4 and 2 more");
+    }
+}
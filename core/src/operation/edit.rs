@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::{env, fs};
@@ -7,7 +8,7 @@ use secrecy::ExposeSecret;
 use tempfile::TempDir;
 use zeroize::Zeroize;
 
-use crate::pgp::PGPClient;
+use crate::pgp::{is_not_text, PGPClient};
 use crate::util::defer::Defer;
 use crate::util::fs_util::{
     backup_encrypted_file, get_dir_gpg_id_content, path_attack_check, path_to_str,
@@ -22,6 +23,8 @@ pub fn edit(
     extension: &str,
     editor: &str,
     pgp_executable: &str,
+    pgp_options: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
 ) -> Result<bool> {
     let target_path = root.join(format!("{target}.{extension}"));
     path_attack_check(root, &target_path)?;
@@ -33,11 +36,12 @@ pub fn edit(
     }
 
     // Get the appropriate key fingerprints for this path
-    let keys_fpr = get_dir_gpg_id_content(root, &target_path)?;
+    let keys_fpr = get_dir_gpg_id_content(root, &target_path, groups)?;
     let client = PGPClient::new(
         pgp_executable,
         &keys_fpr.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
-    )?;
+    )?
+    .with_options(pgp_options);
 
     let tmp_dir: PathBuf = {
         let temp_base = {
@@ -66,7 +70,13 @@ pub fn edit(
         format!(".{}-{}", rand_alphabet_string(10), temp_filename.to_string_lossy());
     let temp_filepath = tmp_dir.join(temp_filename);
 
-    let mut content = client.decrypt_stdin(root, path_to_str(&target_path)?)?;
+    let mut content = client.decrypt_stdin(root, path_to_str(&target_path)?).map_err(|e| {
+        if is_not_text(&e) {
+            anyhow!("'{target}' is a binary entry, replace it with `pars insert --file`")
+        } else {
+            e
+        }
+    })?;
     fs::write(&temp_filepath, content.expose_secret())?;
     let _cleaner = Defer::new(|| {
         let _ = fs::remove_file(&temp_filepath);
@@ -179,10 +189,19 @@ powershell -Command "(Get-Content %file% | Select-Object -Skip 1) | Set-Content
                 write_gpg_id(&root, &test_client.get_keys_fpr());
 
                 let fake_editor = create_fake_editor(&root);
-                let res1 =
-                    edit(&root, "file1", "gpg", path_to_str(&fake_editor).unwrap(), executable)
+                let res1 = edit(
+                    &root,
+                    "file1",
+                    "gpg",
+                    path_to_str(&fake_editor).unwrap(),
+                    executable,
+                    &[],
+                    &BTreeMap::new(),
+                )
+                .unwrap();
+                let res2 =
+                    edit(&root, "dir/file2", "gpg", "cat", executable, &[], &BTreeMap::new())
                         .unwrap();
-                let res2 = edit(&root, "dir/file2", "gpg", "cat", executable).unwrap();
                 assert!(res1);
                 assert!(!res2);
 
@@ -197,4 +216,44 @@ powershell -Command "(Get-Content %file% | Select-Object -Skip 1) | Set-Content
             }
         );
     }
+    #[test]
+    #[serial]
+    #[ignore = "need run interactively"]
+    fn binary_refused() {
+        let executable = &get_test_executable();
+        let email = &get_test_email();
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+
+        cleanup!(
+            {
+                key_gen_batch(executable, &gpg_key_gen_example_batch()).unwrap();
+                let test_client = PGPClient::new(executable, &[email]).unwrap();
+                test_client.key_edit_batch(&gpg_key_edit_example_batch()).unwrap();
+                write_gpg_id(&root, &test_client.get_keys_fpr());
+                let path = root.join("key.gpg");
+                test_client.encrypt_bytes(b"a\0b", path_to_str(&path).unwrap()).unwrap();
+                let before = fs::read(&path).unwrap();
+
+                let fake_editor = create_fake_editor(&root);
+                let err = edit(
+                    &root,
+                    "key",
+                    "gpg",
+                    path_to_str(&fake_editor).unwrap(),
+                    executable,
+                    &[],
+                    &BTreeMap::new(),
+                )
+                .unwrap_err();
+                assert_eq!(
+                    err.to_string(),
+                    "'key' is a binary entry, replace it with `pars insert --file`"
+                );
+                assert_eq!(fs::read(&path).unwrap(), before);
+            },
+            {
+                clean_up_test_key(executable, &[email]).unwrap();
+            }
+        );
+    }
 }
@@ -0,0 +1,373 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use rand::Rng;
+use secrecy::ExposeSecret;
+use serde::Serialize;
+
+use crate::config::cli::ParsConfig;
+use crate::pgp::{is_not_text, PGPClient};
+use crate::util::fs_util::{get_dir_gpg_id_content, path_attack_check, walk_store};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Every entry decrypted, as a JSON array
+    Json,
+    /// Every entry decrypted, with the columns of a KeePassXC CSV export
+    Csv,
+    /// The JSON array encrypted for a single recipient, ASCII armored
+    Bundle,
+    /// Every entry decrypted, as a KeePass 2 XML export
+    KeepassXml,
+}
+
+impl ExportFormat {
+    /// Whether the output holds the passwords in plain text
+    pub fn is_plaintext(&self) -> bool {
+        !matches!(self, ExportFormat::Bundle)
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "bundle" => Ok(ExportFormat::Bundle),
+            "keepass-xml" => Ok(ExportFormat::KeepassXml),
+            _ => Err(anyhow!(
+                "Unknown format '{s}', expected one of: json, csv, bundle, keepass-xml"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ExportedField {
+    pub name: String,
+    pub value: String,
+}
+
+/// A decrypted entry split along the structured layout
+#[derive(Debug, Default, Serialize, PartialEq, Eq)]
+pub struct ExportedEntry {
+    /// Path relative to the exported folder
+    pub name: String,
+    pub password: String,
+    pub login: String,
+    pub url: String,
+    /// `key: value` lines other than the login and the url
+    pub fields: Vec<ExportedField>,
+    /// Every other line
+    pub notes: String,
+}
+
+impl ExportedEntry {
+    /// Split `content`: the first line is the password, `key: value` lines are fields, the rest
+    /// are notes
+    pub fn from_content(name: &str, content: &str) -> Self {
+        let mut lines = content.lines();
+        let mut entry = ExportedEntry {
+            name: name.to_string(),
+            password: lines.next().unwrap_or_default().to_string(),
+            ..Default::default()
+        };
+        let mut notes = Vec::new();
+        for line in lines {
+            let field = line
+                .split_once(':')
+                .map(|(key, value)| (key.trim(), value.trim()))
+                .filter(|(key, _)| !key.is_empty() && !key.contains(char::is_whitespace));
+            match field {
+                Some((key, value)) if is_login(key) && entry.login.is_empty() => {
+                    entry.login = value.to_string()
+                }
+                Some((key, value)) if is_url(key) && entry.url.is_empty() => {
+                    entry.url = value.to_string()
+                }
+                // A URL on its own line is a note, not an `https` field
+                Some((key, value)) if !value.starts_with("//") => entry
+                    .fields
+                    .push(ExportedField { name: key.to_string(), value: value.to_string() }),
+                _ => notes.push(line),
+            }
+        }
+        entry.notes = notes.join("\n");
+        entry
+    }
+
+    fn folder_and_title(&self) -> (Vec<&str>, &str) {
+        let mut parts: Vec<&str> = self.name.split('/').collect();
+        let title = parts.pop().unwrap_or_default();
+        (parts, title)
+    }
+}
+
+/// Decrypt every entry below `sub_folder` of the store at `root`, sorted by name. Binary entries
+/// have no place in the formats, their names are returned apart.
+pub fn collect_entries(
+    config: &ParsConfig,
+    root: &Path,
+    sub_folder: Option<&str>,
+) -> Result<(Vec<ExportedEntry>, Vec<String>)> {
+    let folder = root.join(sub_folder.unwrap_or_default());
+    path_attack_check(root, &folder)?;
+    if !folder.is_dir() {
+        return Err(anyhow!(format!("{folder:?} is not a folder of the store")));
+    }
+
+    let mut entries = Vec::new();
+    let mut binary = Vec::new();
+    for entry in walk_store(&folder) {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().is_none_or(|ext| ext != "gpg") {
+            continue;
+        }
+        let name = path.strip_prefix(&folder).unwrap_or(path).with_extension("");
+        let name = name.to_string_lossy().replace('\\', "/");
+        let keys = get_dir_gpg_id_content(root, path.parent().unwrap_or(root), &config.groups)?;
+        let content = match PGPClient::new(&config.executable_config.pgp_executable, &keys)?
+            .with_options(&config.executable_config.pgp_options)
+            .decrypt_stdin(root, &path.to_string_lossy())
+        {
+            Ok(content) => content,
+            Err(e) if is_not_text(&e) => {
+                binary.push(name);
+                continue;
+            }
+            Err(e) => return Err(anyhow!(format!("Failed to decrypt '{name}': {e}"))),
+        };
+        entries.push(ExportedEntry::from_content(&name, content.expose_secret()));
+    }
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok((entries, binary))
+}
+
+pub fn to_json(entries: &[ExportedEntry]) -> Result<String> {
+    Ok(serde_json::to_string_pretty(entries)? + "\n")
+}
+
+/// CSV with the columns KeePassXC exports, which most password managers import. Fields are
+/// written as `key: value` lines before the notes.
+pub fn to_csv(entries: &[ExportedEntry]) -> Result<String> {
+    let mut writer =
+        csv::WriterBuilder::new().quote_style(csv::QuoteStyle::Always).from_writer(vec![]);
+    writer.write_record(["Group", "Title", "Username", "Password", "URL", "Notes"])?;
+    for entry in entries {
+        let (folder, title) = entry.folder_and_title();
+        let group = ["Root"].into_iter().chain(folder).collect::<Vec<_>>().join("/");
+        writer.write_record([
+            group.as_str(),
+            title,
+            &entry.login,
+            &entry.password,
+            &entry.url,
+            &notes_with_fields(entry),
+        ])?;
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// KeePass 2 XML, folders become groups below a root group named `root_name`
+pub fn to_keepass_xml(entries: &[ExportedEntry], root_name: &str) -> String {
+    #[derive(Default)]
+    struct Group<'a> {
+        groups: BTreeMap<&'a str, Group<'a>>,
+        entries: Vec<&'a ExportedEntry>,
+    }
+    let mut root = Group::default();
+    for entry in entries {
+        let (folder, _) = entry.folder_and_title();
+        let group =
+            folder.into_iter().fold(&mut root, |group, part| group.groups.entry(part).or_default());
+        group.entries.push(entry);
+    }
+
+    fn write_group(out: &mut String, name: &str, group: &Group, depth: usize) {
+        let indent = "\t".repeat(depth);
+        let _ = writeln!(out, "{indent}<Group>");
+        let _ = writeln!(out, "{indent}\t<UUID>{}</UUID>", new_uuid());
+        let _ = writeln!(out, "{indent}\t<Name>{}</Name>", escape_xml(name));
+        for entry in &group.entries {
+            let (_, title) = entry.folder_and_title();
+            let _ = writeln!(out, "{indent}\t<Entry>");
+            let _ = writeln!(out, "{indent}\t\t<UUID>{}</UUID>", new_uuid());
+            let mut strings = vec![
+                ("Title", title, false),
+                ("UserName", entry.login.as_str(), false),
+                ("Password", entry.password.as_str(), true),
+                ("URL", entry.url.as_str(), false),
+                ("Notes", entry.notes.as_str(), false),
+            ];
+            let mut names: Vec<String> = Vec::new();
+            for field in &entry.fields {
+                // KeePass keys are unique within an entry
+                let name = (1..)
+                    .map(
+                        |i| if i == 1 { field.name.clone() } else { format!("{} {i}", field.name) },
+                    )
+                    .find(|name| {
+                        !names.contains(name) && !strings.iter().any(|(key, _, _)| key == name)
+                    })
+                    .unwrap_or_default();
+                names.push(name);
+            }
+            for (field, name) in entry.fields.iter().zip(&names) {
+                strings.push((name.as_str(), field.value.as_str(), false));
+            }
+            for (key, value, protect) in strings {
+                let attribute = if protect { " ProtectInMemory=\"True\"" } else { "" };
+                let _ = writeln!(
+                    out,
+                    "{indent}\t\t<String><Key>{}</Key><Value{attribute}>{}</Value></String>",
+                    escape_xml(key),
+                    escape_xml(value)
+                );
+            }
+            let _ = writeln!(out, "{indent}\t</Entry>");
+        }
+        for (name, child) in &group.groups {
+            write_group(out, name, child, depth + 1);
+        }
+        let _ = writeln!(out, "{indent}</Group>");
+    }
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\" standalone=\"yes\"?>\n");
+    out.push_str("<KeePassFile>\n\t<Meta>\n\t\t<Generator>pars</Generator>\n\t</Meta>\n\t<Root>\n");
+    write_group(&mut out, root_name, &root, 2);
+    out.push_str("\t</Root>\n</KeePassFile>\n");
+    out
+}
+
+/// Encrypt the JSON export of `entries` for `recipient` to `output_path`, ASCII armored
+pub fn write_bundle(
+    config: &ParsConfig,
+    recipient: &str,
+    entries: &[ExportedEntry],
+    output_path: &str,
+) -> Result<()> {
+    let client = PGPClient::new(&config.executable_config.pgp_executable, &[recipient])?
+        .with_options(&config.executable_config.pgp_options);
+    client.encrypt_armored(&to_json(entries)?, output_path)
+}
+
+fn notes_with_fields(entry: &ExportedEntry) -> String {
+    let mut lines: Vec<String> =
+        entry.fields.iter().map(|field| format!("{}: {}", field.name, field.value)).collect();
+    if !entry.notes.is_empty() {
+        lines.push(entry.notes.clone());
+    }
+    lines.join("\n")
+}
+
+fn is_login(key: &str) -> bool {
+    ["login", "username", "user", "email"].iter().any(|name| key.eq_ignore_ascii_case(name))
+}
+
+fn is_url(key: &str) -> bool {
+    ["url", "website"].iter().any(|name| key.eq_ignore_ascii_case(name))
+}
+
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Random 16 bytes in base64, the form of KeePass UUIDs
+fn new_uuid() -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let bytes: [u8; 16] = rand::rng().random();
+    let mut encoded = String::with_capacity(24);
+    for chunk in bytes.chunks(3) {
+        let n =
+            chunk.iter().enumerate().fold(0u32, |n, (i, byte)| n | (*byte as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::operation::import::{parse, ImportFormat};
+
+    fn sample() -> Vec<ExportedEntry> {
+        vec![
+            ExportedEntry::from_content(
+                "web/github",
+                "gh-pw\nlogin: alice\nurl: https://github.com\notp: JBSWY3DP\nsecond <factor> & more\n",
+            ),
+            ExportedEntry::from_content("wifi", "wifi-pw"),
+        ]
+    }
+
+    #[test]
+    fn from_content_test() {
+        let entry = &sample()[0];
+        assert_eq!(
+            *entry,
+            ExportedEntry {
+                name: "web/github".into(),
+                password: "gh-pw".into(),
+                login: "alice".into(),
+                url: "https://github.com".into(),
+                fields: vec![ExportedField { name: "otp".into(), value: "JBSWY3DP".into() }],
+                notes: "second <factor> & more".into(),
+            }
+        );
+        let entry = ExportedEntry::from_content("a", "pw\nhttps://example.com\n");
+        assert_eq!((entry.url.as_str(), entry.notes.as_str()), ("", "https://example.com"));
+    }
+
+    #[test]
+    fn round_trip_test() {
+        let csv = to_csv(&sample()).unwrap();
+        let imported = parse(ImportFormat::KeepassxcCsv, &csv).unwrap();
+        assert_eq!(imported[0].pass_name(None), "web/github");
+        assert_eq!(imported[0].notes, "otp: JBSWY3DP\nsecond <factor> & more");
+
+        let xml = to_keepass_xml(&sample(), "pars");
+        let imported = parse(ImportFormat::KeepassXml, &xml).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].pass_name(None), "wifi");
+        assert_eq!(imported[1].pass_name(None), "web/github");
+        assert_eq!(imported[1].password, "gh-pw");
+        assert_eq!(imported[1].fields, vec![("otp".to_string(), "JBSWY3DP".to_string())]);
+        assert_eq!(imported[1].notes, "second <factor> & more");
+
+        let json: serde_json::Value = serde_json::from_str(&to_json(&sample()).unwrap()).unwrap();
+        assert_eq!(json[0]["fields"][0]["value"], "JBSWY3DP");
+    }
+
+    #[test]
+    fn uuid_test() {
+        let uuid = new_uuid();
+        assert_eq!(uuid.len(), 24);
+        assert!(uuid.ends_with("=="));
+        assert_ne!(uuid, new_uuid());
+    }
+}
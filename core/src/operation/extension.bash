@@ -0,0 +1,168 @@
+# SPDX-License-Identifier: GPL-2.0-or-later
+#
+# The variables and helper functions below are adapted from pass (src/password-store.sh),
+# Copyright (C) 2012 - 2018 Jason A. Donenfeld <Jason@zx2c4.com>. All Rights Reserved.
+#
+# Sourced by pars before a pass extension (`<name>.bash`), so the extension finds the
+# variables and helper functions pass itself would provide. Commands are run by pars.
+#
+# pars exports: PASSWORD_STORE_DIR, PARS_BIN, PARS_REPO, PARS_GPG, PARS_COMMAND, PARS_EXTENSION
+
+umask "${PASSWORD_STORE_UMASK:-077}"
+set -o pipefail
+
+GPG_OPTS=( $PASSWORD_STORE_GPG_OPTS "--quiet" "--yes" "--compress-algo=none" "--no-encrypt-to" )
+GPG="${PARS_GPG:-gpg}"
+export GPG_TTY="${GPG_TTY:-$(tty 2>/dev/null)}"
+[[ -n $GPG_AGENT_INFO || $GPG == "gpg2" ]] && GPG_OPTS+=( "--batch" "--use-agent" )
+
+PREFIX="${PASSWORD_STORE_DIR:-$HOME/.password-store}"
+EXTENSIONS="${PASSWORD_STORE_EXTENSIONS_DIR:-$PREFIX/.extensions}"
+X_SELECTION="${PASSWORD_STORE_X_SELECTION:-clipboard}"
+CLIP_TIME="${PASSWORD_STORE_CLIP_TIME:-45}"
+GENERATED_LENGTH="${PASSWORD_STORE_GENERATED_LENGTH:-25}"
+CHARACTER_SET="${PASSWORD_STORE_CHARACTER_SET:-[:punct:][:alnum:]}"
+CHARACTER_SET_NO_SYMBOLS="${PASSWORD_STORE_CHARACTER_SET_NO_SYMBOLS:-[:alnum:]}"
+PROGRAM="pars"
+COMMAND="$PARS_COMMAND"
+export GIT_CEILING_DIRECTORIES="$PREFIX/.."
+
+#
+# BEGIN helper functions, from password-store.sh
+#
+
+set_git() {
+	INNER_GIT_DIR="${1%/*}"
+	while [[ ! -d $INNER_GIT_DIR && ${INNER_GIT_DIR%/*}/ == "${PREFIX%/}/"* ]]; do
+		INNER_GIT_DIR="${INNER_GIT_DIR%/*}"
+	done
+	[[ $(git -C "$INNER_GIT_DIR" rev-parse --is-inside-work-tree 2>/dev/null) == true ]] || INNER_GIT_DIR=""
+}
+git_add_file() {
+	[[ -n $INNER_GIT_DIR ]] || return
+	git -C "$INNER_GIT_DIR" add "$1" || return
+	[[ -n $(git -C "$INNER_GIT_DIR" status --porcelain "$1") ]] || return
+	git_commit "$2"
+}
+git_commit() {
+	[[ -n $INNER_GIT_DIR ]] || return
+	local sign=""
+	[[ $(git -C "$INNER_GIT_DIR" config --bool --get pass.signcommits) == "true" ]] && sign="-S"
+	git -C "$INNER_GIT_DIR" commit $sign -m "$1"
+}
+yesno() {
+	[[ -t 0 ]] || return 0
+	local response
+	read -r -p "$1 [y/N] " response
+	[[ $response == [yY] ]] || exit 1
+}
+die() {
+	echo "$@" >&2
+	exit 1
+}
+verify_file() {
+	[[ -n $PASSWORD_STORE_SIGNING_KEY ]] || return 0
+	[[ -f $1.sig ]] || die "Signature for $1 does not exist."
+	local fingerprints="$($GPG $PASSWORD_STORE_GPG_OPTS --verify --status-fd=1 "$1.sig" "$1" 2>/dev/null | sed -n 's/^\[GNUPG:\] VALIDSIG \([A-F0-9]\{40\}\) .* \([A-F0-9]\{40\}\)$/\1\n\2/p')"
+	local fingerprint found=0
+	for fingerprint in $PASSWORD_STORE_SIGNING_KEY; do
+		[[ $fingerprint =~ ^[A-F0-9]{40}$ ]] || continue
+		[[ $fingerprints == *$fingerprint* ]] && { found=1; break; }
+	done
+	[[ $found -eq 1 ]] || die "Signature for $1 is invalid."
+}
+set_gpg_recipients() {
+	GPG_RECIPIENT_ARGS=( )
+	GPG_RECIPIENTS=( )
+
+	if [[ -n $PASSWORD_STORE_KEY ]]; then
+		for gpg_id in $PASSWORD_STORE_KEY; do
+			GPG_RECIPIENT_ARGS+=( "-r" "$gpg_id" )
+			GPG_RECIPIENTS+=( "$gpg_id" )
+		done
+		return
+	fi
+
+	local current="$PREFIX/$1"
+	while [[ $current != "$PREFIX" && ! -f $current/.gpg-id ]]; do
+		current="${current%/*}"
+	done
+	current="$current/.gpg-id"
+
+	if [[ ! -f $current ]]; then
+		die "Error: You must run: $PROGRAM init your-gpg-id before you may use the password store."
+	fi
+
+	verify_file "$current"
+
+	local gpg_id
+	while read -r gpg_id; do
+		gpg_id="${gpg_id%%#*}" # strip comment
+		[[ -n $gpg_id ]] || continue
+		GPG_RECIPIENT_ARGS+=( "-r" "$gpg_id" )
+		GPG_RECIPIENTS+=( "$gpg_id" )
+	done < "$current"
+}
+check_sneaky_paths() {
+	local path
+	for path in "$@"; do
+		[[ $path =~ /\.\.$ || $path =~ ^\.\./ || $path =~ /\.\./ || $path =~ ^\.\.$ ]] && die "Error: You have passed a sneaky path to $PROGRAM."
+	done
+}
+tmpdir() {
+	[[ -n $SECURE_TMPDIR ]] && return
+	local warn=1
+	[[ $1 == "nowarn" ]] && warn=0
+	local template="$PROGRAM.XXXXXXXXXXXXX"
+	if [[ -d /dev/shm && -w /dev/shm && -x /dev/shm ]]; then
+		SECURE_TMPDIR="$(mktemp -d "/dev/shm/$template")"
+	else
+		[[ $warn -eq 1 ]] && yesno "Your system does not have /dev/shm, which means that it may be difficult to entirely erase the temporary non-encrypted password file after editing.
+
+Are you sure you would like to continue?"
+		SECURE_TMPDIR="$(mktemp -d "${TMPDIR:-/tmp}/$template")"
+	fi
+	trap 'rm -rf "$SECURE_TMPDIR"' EXIT
+}
+clip() {
+	# pars copies and clears the clipboard after its clip_time, with its x_selection
+	printf '%s' "$1" | "$PARS_BIN" clip "$2" || die "Error: Could not copy data to the clipboard"
+}
+qrcode() {
+	qrencode -t utf8 <<<"$1" || die "Error: Could not encode as a QR code"
+}
+
+#
+# END helper functions
+#
+
+#
+# BEGIN commands, each one is a pars subcommand on this store
+#
+
+_pars() { "$PARS_BIN" -R "$PREFIX" "$@"; }
+cmd_version() { _pars --version; }
+cmd_usage() { _pars --help; }
+cmd_init() { _pars init "$@"; }
+cmd_show() { _pars show "$@"; }
+cmd_find() { _pars find "$@"; }
+cmd_grep() { _pars grep "$@"; }
+cmd_insert() { _pars insert "$@"; }
+cmd_edit() { _pars edit "$@"; }
+cmd_generate() { _pars generate "$@"; }
+cmd_delete() { _pars rm "$@"; }
+cmd_copy_move() {
+	local move=1
+	[[ $1 == "copy" ]] && move=0
+	shift
+	if [[ $move -eq 1 ]]; then
+		_pars mv "$@"
+	else
+		_pars cp "$@"
+	fi
+}
+cmd_git() { _pars git "$@"; }
+
+#
+# END commands
+#
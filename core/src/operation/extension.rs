@@ -0,0 +1,215 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::cli::ExtensionConfig;
+use crate::util::fs_util::{find_executable_in_path, is_executable};
+
+/// Variables and helpers of pass, sourced before a `.bash` extension
+const PASS_PRELUDE: &str = include_str!("extension.bash");
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Extension {
+    /// `pars-<name>` found in PATH
+    Executable(PathBuf),
+    /// A pass extension `<name>.bash`
+    Script(PathBuf),
+}
+
+/// What an extension gets to know about the store, see `extension.bash` for the variables
+pub struct ExtensionEnv<'a> {
+    pub store_dir: &'a Path,
+    pub repo: &'a str,
+    pub pars_bin: &'a Path,
+    pub pgp_executable: &'a str,
+}
+
+/// Find the extension run by `pars <name>`: a `pars-<name>` executable in PATH, then
+/// `<store>/.extensions/<name>.bash` when store extensions are enabled, then `<name>.bash`
+/// in the system directories
+pub fn find_extension(root: &Path, name: &str, config: &ExtensionConfig) -> Option<Extension> {
+    if name.is_empty() || name.starts_with(['.', '-']) || name.contains(['/', '\\']) {
+        return None;
+    }
+
+    if let Some(path) = find_executable_in_path(&format!("pars-{name}")) {
+        return Some(Extension::Executable(path));
+    }
+
+    let script = format!("{name}.bash");
+    if store_extensions_enabled(config) {
+        let dir = env::var("PASSWORD_STORE_EXTENSIONS_DIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| root.join(".extensions"));
+        // Like pass, a store extension has to be marked executable
+        let path = dir.join(&script);
+        if is_executable(&path).unwrap_or(false) {
+            return Some(Extension::Script(path));
+        }
+    }
+
+    config
+        .system_dirs
+        .iter()
+        .map(|dir| Path::new(dir).join(&script))
+        .find(|path| path.is_file())
+        .map(Extension::Script)
+}
+
+fn store_extensions_enabled(config: &ExtensionConfig) -> bool {
+    config.store_extensions
+        || env::var("PASSWORD_STORE_ENABLE_EXTENSIONS").is_ok_and(|value| value == "true")
+}
+
+/// Command running `extension` with `args`, in the environment documented in the README
+pub fn extension_command(
+    extension: &Extension,
+    name: &str,
+    ext_env: &ExtensionEnv,
+    args: &[String],
+) -> Command {
+    let mut cmd = match extension {
+        Extension::Executable(path) => Command::new(path),
+        Extension::Script(path) => {
+            let mut cmd = Command::new("bash");
+            cmd.arg("-c")
+                .arg(format!("{PASS_PRELUDE}\nsource \"$PARS_EXTENSION\" \"$@\""))
+                .arg("pars")
+                .env("PARS_EXTENSION", path);
+            cmd
+        }
+    };
+    cmd.args(args)
+        .env("PASSWORD_STORE_DIR", ext_env.store_dir)
+        .env("PARS_REPO", ext_env.repo)
+        .env("PARS_BIN", ext_env.pars_bin)
+        .env("PARS_GPG", ext_env.pgp_executable)
+        .env("PARS_COMMAND", name);
+    cmd
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use pretty_assertions::assert_eq;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::util::test_util::gen_unique_temp_dir;
+
+    fn write_script(path: &Path, content: &str, mode: u32) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn find_extension_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let store = root.join("store");
+        let system = root.join("system");
+        write_script(&store.join(".extensions/local.bash"), "", 0o755);
+        write_script(&store.join(".extensions/noexec.bash"), "", 0o644);
+        write_script(&system.join("update.bash"), "", 0o644);
+        write_script(&system.join("local.bash"), "", 0o644);
+
+        let mut config =
+            ExtensionConfig { store_extensions: false, system_dirs: vec![path_str(&system)] };
+        env::remove_var("PASSWORD_STORE_ENABLE_EXTENSIONS");
+        env::remove_var("PASSWORD_STORE_EXTENSIONS_DIR");
+
+        // Store extensions are ignored until enabled
+        assert_eq!(
+            find_extension(&store, "local", &config),
+            Some(Extension::Script(system.join("local.bash")))
+        );
+        config.store_extensions = true;
+        assert_eq!(
+            find_extension(&store, "local", &config),
+            Some(Extension::Script(store.join(".extensions/local.bash")))
+        );
+        assert_eq!(find_extension(&store, "noexec", &config), None);
+        assert_eq!(
+            find_extension(&store, "update", &config),
+            Some(Extension::Script(system.join("update.bash")))
+        );
+        assert_eq!(find_extension(&store, "missing", &config), None);
+        assert_eq!(find_extension(&store, "../system/update", &config), None);
+    }
+
+    #[test]
+    #[serial]
+    fn run_script_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let store = root.join("store");
+        fs::create_dir_all(store.join("team")).unwrap();
+        fs::write(store.join(".gpg-id"), "alice@example.com\n").unwrap();
+        fs::write(store.join("team/.gpg-id"), "# the team\nbob@example.com\ncarol@example.com\n")
+            .unwrap();
+        let script = root.join("hello.bash");
+        write_script(
+            &script,
+            r#"
+cmd_hello() {
+    check_sneaky_paths "$1"
+    set_gpg_recipients "$1"
+    echo "$PROGRAM $COMMAND $PREFIX ${GPG_RECIPIENTS[*]} $*"
+}
+cmd_hello "$@"
+"#,
+            0o644,
+        );
+
+        let ext_env = ExtensionEnv {
+            store_dir: &store,
+            repo: "default",
+            pars_bin: Path::new("/usr/bin/pars"),
+            pgp_executable: "gpg",
+        };
+        let extension = Extension::Script(script);
+        let args = vec!["team".to_string(), "--flag".to_string()];
+        let output = extension_command(&extension, "hello", &ext_env, &args).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap().trim(),
+            format!("pars hello {} bob@example.com carol@example.com team --flag", store.display())
+        );
+
+        let args = vec!["../escape".to_string()];
+        let output = extension_command(&extension, "hello", &ext_env, &args).output().unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr).contains("sneaky path"));
+    }
+
+    #[test]
+    fn clip_goes_through_pars_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let pars_bin = root.join("pars");
+        let copied = root.join("copied");
+        write_script(
+            &pars_bin,
+            &format!("#!/bin/sh\necho \"$@\" > {0:?}\ncat >> {0:?}\n", copied),
+            0o755,
+        );
+        let script = root.join("otp.bash");
+        write_script(&script, "clip \"123456\" \"OTP code\"\n", 0o644);
+
+        let ext_env = ExtensionEnv {
+            store_dir: &root,
+            repo: "default",
+            pars_bin: &pars_bin,
+            pgp_executable: "gpg",
+        };
+        let output =
+            extension_command(&Extension::Script(script), "otp", &ext_env, &[]).output().unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        assert_eq!(fs::read_to_string(&copied).unwrap(), "clip OTP code\n123456");
+    }
+
+    fn path_str(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+}
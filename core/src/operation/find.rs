@@ -12,12 +12,7 @@ pub fn find_term(
     tree_cfg: &TreeConfig,
     print_cfg: &TreePrintConfig,
 ) -> Result<String> {
-    let mut config = tree_cfg.clone();
-    config.filter_type = FilterType::Include;
-    config.filters = terms.iter().map(|term| Regex::new(term)).collect::<Result<Vec<_>, _>>()?;
-    let bump = Bump::new();
-    let tree = DirTree::new(&config, &bump)?;
-    let result = tree.print_tree(print_cfg)?;
+    let result = find_tree(terms, tree_cfg, print_cfg)?;
     let mut header = String::from_str("Search Terms: ")?;
     for term in terms {
         header.push_str(term);
@@ -26,7 +21,22 @@ pub fn find_term(
     header.pop();
     header.pop();
 
-    Ok(format!("{}\n{}", header, remove_lines_postfix(&result, ".gpg")))
+    Ok(format!("{}\n{}", header, result))
+}
+
+/// The matching entries as a tree, without the search terms header
+pub fn find_tree(
+    terms: &[&str],
+    tree_cfg: &TreeConfig,
+    print_cfg: &TreePrintConfig,
+) -> Result<String> {
+    let mut config = tree_cfg.clone();
+    config.filter_type = FilterType::Include;
+    config.filters = terms.iter().map(|term| Regex::new(term)).collect::<Result<Vec<_>, _>>()?;
+    let bump = Bump::new();
+    let tree = DirTree::new(&config, &bump)?;
+    let result = tree.print_tree(print_cfg)?;
+    Ok(remove_lines_postfix(&result, ".gpg"))
 }
 
 #[cfg(test)]
@@ -0,0 +1,354 @@
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::{debug, warn};
+use secrecy::ExposeSecret;
+
+use crate::config::cli::ParsConfig;
+use crate::constants::default_constants::BACKUP_EXTENSION;
+use crate::operation::batch::PasswordStore;
+use crate::pgp::recipients::{RecipientChecker, RecipientStatus};
+use crate::pgp::utils::decrypts_intact;
+use crate::pgp::PGPClient;
+use crate::util::fs_util::{get_dir_gpg_id_content, walk_store};
+use crate::util::tree::NodeType;
+
+pub struct FsckConfig {
+    /// Decrypt every entry instead of only reading who it is encrypted to
+    pub decrypt: bool,
+    /// Re-encrypt mismatched entries and restore or delete backups
+    pub fix: bool,
+}
+
+/// Something wrong in the store, paths are relative to its root
+#[derive(Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// The entry cannot be read, or cannot be decrypted when decryption is checked
+    Unreadable {
+        path: PathBuf,
+        error: String,
+    },
+    /// The recipients of the entry differ from its `.gpg-id`. `missing` lists the ids of the
+    /// `.gpg-id` that cannot read it, `extra` the key IDs that can but are no longer listed.
+    Mismatch {
+        path: PathBuf,
+        missing: Vec<String>,
+        extra: Vec<String>,
+    },
+    /// A `.gpg-id` lists a key missing from the keyring, its entries are not compared
+    UnknownRecipient {
+        id: String,
+        error: String,
+    },
+    /// A backup left behind by an interrupted rewrite
+    Backup(PathBuf),
+    BrokenSymlink(PathBuf),
+    RecursiveSymlink(PathBuf),
+    EmptyDir(PathBuf),
+}
+
+#[derive(Debug, Default)]
+pub struct FsckReport {
+    /// Number of entries checked
+    pub checked: usize,
+    pub problems: Vec<Problem>,
+    /// What `fix` changed, one line each
+    pub fixed: Vec<String>,
+    /// Fixable problems `fix` had to leave as they are, one line each
+    pub unfixed: Vec<String>,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Unreadable { path, error } => write!(f, "{}: {error}", path.display()),
+            Problem::Mismatch { path, missing, extra } => {
+                write!(f, "{}:", path.display())?;
+                if !missing.is_empty() {
+                    write!(f, " not readable by {}", missing.join(", "))?;
+                }
+                if !missing.is_empty() && !extra.is_empty() {
+                    write!(f, ",")?;
+                }
+                if !extra.is_empty() {
+                    write!(f, " still encrypted to unlisted key(s) {}", extra.join(", "))?;
+                }
+                Ok(())
+            }
+            Problem::UnknownRecipient { id, error } => {
+                write!(f, "recipient '{id}' cannot be checked: {error}")
+            }
+            Problem::Backup(path) => write!(f, "{}: leftover backup", path.display()),
+            Problem::BrokenSymlink(path) => write!(f, "{}: broken symlink", path.display()),
+            Problem::RecursiveSymlink(path) => write!(f, "{}: recursive symlink", path.display()),
+            Problem::EmptyDir(path) => write!(f, "{}: empty directory", path.display()),
+        }
+    }
+}
+
+impl Problem {
+    /// Whether `fix` repairs it, links and directories are left to the user
+    pub fn is_fixable(&self) -> bool {
+        matches!(self, Problem::Mismatch { .. } | Problem::Backup(_))
+    }
+}
+
+/// Check every entry of the store at `root` against its `.gpg-id`, and the store for leftovers
+pub fn fsck(config: &ParsConfig, root: &Path, fsck_cfg: &FsckConfig) -> Result<FsckReport> {
+    if !root.is_dir() {
+        return Err(anyhow!(format!("Store {root:?} does not exist")));
+    }
+    let pgp_exe = &config.executable_config.pgp_executable;
+    let mut report = FsckReport::default();
+    let mut checker = RecipientChecker::new(pgp_exe);
+
+    for entry in walk_store(root) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                let Some(path) = e.path().map(|path| relative(root, path)) else {
+                    warn!("Failed to walk the store: {e}");
+                    continue;
+                };
+                if e.loop_ancestor().is_some() {
+                    report.problems.push(Problem::RecursiveSymlink(path));
+                } else if matches!(NodeType::from(root.join(&path)), NodeType::Invalid) {
+                    report.problems.push(Problem::BrokenSymlink(path));
+                } else {
+                    warn!("Failed to read {path:?}: {e}");
+                }
+                continue;
+            }
+        };
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy();
+
+        if entry.file_type().is_dir() {
+            if entry.depth() > 0 && fs::read_dir(path)?.next().is_none() {
+                report.problems.push(Problem::EmptyDir(relative(root, path)));
+            }
+        } else if name.ends_with(&format!(".{BACKUP_EXTENSION}")) {
+            report.problems.push(Problem::Backup(relative(root, path)));
+        } else if name.ends_with(".gpg") {
+            report.checked += 1;
+            if let Some(problem) = check_entry(&mut checker, config, root, path, fsck_cfg)? {
+                report.problems.push(problem);
+            }
+        }
+    }
+
+    for (id, error) in checker.unknown_recipients() {
+        report.problems.push(Problem::UnknownRecipient { id, error });
+    }
+
+    if fsck_cfg.fix {
+        fix(config, root, &mut report)?;
+    }
+    Ok(report)
+}
+
+fn check_entry(
+    checker: &mut RecipientChecker,
+    config: &ParsConfig,
+    root: &Path,
+    path: &Path,
+    fsck_cfg: &FsckConfig,
+) -> Result<Option<Problem>> {
+    let rel = relative(root, path);
+    let recipients = get_dir_gpg_id_content(root, path.parent().unwrap_or(root), &config.groups)?;
+
+    if fsck_cfg.decrypt {
+        let decrypted = PGPClient::new(checker.executable(), &recipients)
+            .map(|client| client.with_options(&config.executable_config.pgp_options))
+            .and_then(|client| client.decrypt_bytes(root, &path.to_string_lossy()));
+        if let Err(e) = decrypted {
+            return Ok(Some(Problem::Unreadable { path: rel, error: e.to_string() }));
+        }
+    }
+
+    match checker.check(path, &recipients) {
+        Ok(RecipientStatus::Mismatch { missing, extra }) => {
+            Ok(Some(Problem::Mismatch { path: rel, missing, extra }))
+        }
+        Ok(RecipientStatus::Hidden) => {
+            debug!("{rel:?} hides its recipients, skip comparing them");
+            Ok(None)
+        }
+        // Unknown recipients are reported once for the whole store
+        Ok(RecipientStatus::Match | RecipientStatus::Unknown(_)) => Ok(None),
+        Err(e) => Ok(Some(Problem::Unreadable { path: rel, error: e.to_string() })),
+    }
+}
+
+/// Restore or delete the backups, then re-encrypt the mismatched entries as a single commit.
+///
+/// A backup is left behind by an interrupted write, so the entry next to it may be the
+/// truncated one. It is only deleted once the entry decrypts, and restored over an entry that
+/// does not when it decrypts itself. When neither does, both are kept and reported as unfixed.
+fn fix(config: &ParsConfig, root: &Path, report: &mut FsckReport) -> Result<()> {
+    let pgp_exe = &config.executable_config.pgp_executable;
+    let pgp_options = &config.executable_config.pgp_options;
+    let fixed = &mut report.fixed;
+    for problem in &report.problems {
+        if let Problem::Backup(path) = problem {
+            let backup = root.join(path);
+            let original = backup.with_extension("");
+            let original_name = relative(root, &original);
+            if !original.exists() {
+                fs::rename(&backup, &original)?;
+                fixed.push(format!("Restored {}", original_name.display()));
+            } else if decrypts_intact(pgp_exe, pgp_options, root, &original) {
+                fs::remove_file(&backup)?;
+                fixed.push(format!("Deleted backup {}", path.display()));
+            } else if decrypts_intact(pgp_exe, pgp_options, root, &backup) {
+                fs::rename(&backup, &original)?;
+                fixed.push(format!("Restored {} over a damaged copy", original_name.display()));
+            } else {
+                report.unfixed.push(format!(
+                    "Kept {}, neither it nor {} can be decrypted",
+                    path.display(),
+                    original_name.display()
+                ));
+            }
+        }
+    }
+
+    let mismatched: Vec<&PathBuf> = report
+        .problems
+        .iter()
+        .filter_map(|problem| match problem {
+            Problem::Mismatch { path, .. } => Some(path),
+            _ => None,
+        })
+        .collect();
+    if mismatched.is_empty() {
+        return Ok(());
+    }
+
+    let store = PasswordStore::new(root, "gpg", config);
+    store.batch(|tx| {
+        for path in &mismatched {
+            let full_path = root.join(path);
+            let recipients =
+                get_dir_gpg_id_content(root, full_path.parent().unwrap_or(root), &config.groups)?;
+            let content = PGPClient::new(pgp_exe, &recipients)?
+                .with_options(pgp_options)
+                .decrypt_bytes(root, &full_path.to_string_lossy())?;
+            let pass_name = path.with_extension("");
+            tx.insert_bytes(&pass_name.to_string_lossy(), content.expose_secret(), true)?;
+        }
+        Ok(())
+    })?;
+    fixed.extend(mismatched.iter().map(|path| format!("Re-encrypted {}", path.display())));
+    Ok(())
+}
+
+fn relative(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root).unwrap_or(path).to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::pgp::key_management::key_gen_batch;
+    use crate::util::defer::cleanup;
+    use crate::util::fs_util::create_symlink;
+    use crate::util::test_util::{
+        clean_up_test_key, create_dir_structure, gen_unique_temp_dir, get_test_email,
+        get_test_executable, gpg_key_edit_example_batch, gpg_key_gen_example_batch, write_gpg_id,
+    };
+
+    #[test]
+    fn fsck_structure_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] = &[
+            (Some("empty"), &[]),
+            (Some("dir"), &["a.gpg.parsbak", "b.gpg.parsbak"]),
+            (Some(".git/objects"), &[]),
+        ];
+        create_dir_structure(&root, structure);
+        write_gpg_id(&root, &["nobody@rs.pass"]);
+        fs::write(root.join("dir/b.gpg"), "entry").unwrap();
+        create_symlink(root.join("missing"), root.join("broken")).unwrap();
+        create_symlink(root.join("dir"), root.join("dir/loop")).unwrap();
+
+        let mut fsck_cfg = FsckConfig { decrypt: false, fix: false };
+        let config = ParsConfig::default();
+        let mut problems = fsck(&config, &root, &fsck_cfg).unwrap().problems;
+        problems.retain(|problem| !matches!(problem, Problem::Unreadable { .. }));
+        problems.sort_by_key(|problem| problem.to_string());
+        assert_eq!(
+            problems,
+            vec![
+                Problem::BrokenSymlink("broken".into()),
+                Problem::Backup("dir/a.gpg.parsbak".into()),
+                Problem::Backup("dir/b.gpg.parsbak".into()),
+                Problem::RecursiveSymlink("dir/loop".into()),
+                Problem::EmptyDir("empty".into()),
+            ]
+        );
+
+        // Neither `b.gpg` nor its backup decrypts, both stay
+        fsck_cfg.fix = true;
+        let report = fsck(&config, &root, &fsck_cfg).unwrap();
+        assert_eq!(report.fixed, vec!["Restored dir/a.gpg"]);
+        assert_eq!(
+            report.unfixed,
+            vec!["Kept dir/b.gpg.parsbak, neither it nor dir/b.gpg can be decrypted"]
+        );
+        assert!(root.join("dir/a.gpg").exists());
+        assert_eq!(fs::read_to_string(root.join("dir/b.gpg")).unwrap(), "entry");
+        assert!(root.join("dir/b.gpg.parsbak").exists());
+    }
+
+    #[test]
+    #[serial]
+    #[ignore = "need run interactively"]
+    fn fix_backup_test() {
+        let executable = get_test_executable();
+        let email = get_test_email();
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let mut config = ParsConfig::default();
+        config.executable_config.pgp_executable = executable.clone();
+
+        cleanup!(
+            {
+                key_gen_batch(&executable, &gpg_key_gen_example_batch()).unwrap();
+                let client = PGPClient::new(&executable, &[&email]).unwrap();
+                client.key_edit_batch(&gpg_key_edit_example_batch()).unwrap();
+                write_gpg_id(&root, &client.get_keys_fpr());
+                for name in ["good", "truncated"] {
+                    let path = root.join(format!("{name}.gpg"));
+                    client.encrypt(name, path.to_str().unwrap()).unwrap();
+                    fs::copy(&path, root.join(format!("{name}.gpg.parsbak"))).unwrap();
+                }
+                let truncated = fs::read(root.join("truncated.gpg")).unwrap();
+                fs::write(root.join("truncated.gpg"), &truncated[..truncated.len() / 2]).unwrap();
+
+                let fsck_cfg = FsckConfig { decrypt: false, fix: true };
+                let report = fsck(&config, &root, &fsck_cfg).unwrap();
+                assert!(report.unfixed.is_empty());
+                assert_eq!(
+                    report.fixed,
+                    vec![
+                        "Deleted backup good.gpg.parsbak",
+                        "Restored truncated.gpg over a damaged copy"
+                    ]
+                );
+                let decrypted = client
+                    .decrypt_stdin(&root, root.join("truncated.gpg").to_str().unwrap())
+                    .unwrap();
+                assert_eq!(decrypted.expose_secret(), "truncated");
+                assert!(!root.join("truncated.gpg.parsbak").exists());
+            },
+            {
+                clean_up_test_key(&executable, &[&email]).unwrap();
+            }
+        );
+    }
+}
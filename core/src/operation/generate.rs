@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::io::{BufRead, Read, Write};
 use std::path::Path;
@@ -12,6 +13,7 @@ use crate::util::fs_util::{
     backup_encrypted_file, create_or_overwrite, get_dir_gpg_id_content, path_attack_check,
     path_to_str, restore_backup_file,
 };
+use crate::util::rand::rand_string_from;
 
 pub struct IOStreams<'a, I, O, E>
 where
@@ -29,8 +31,14 @@ pub struct PasswdGenerateConfig {
     pub in_place: bool,
     pub force: bool,
     pub pass_length: usize,
+    /// `tr` style set of the characters to use, like `[:alnum:]_-`, empty for the default one
+    pub character_set: String,
     pub extension: String,
     pub pgp_executable: String,
+    /// Extra options of every encryption and decryption
+    pub pgp_options: Vec<String>,
+    /// Recipient groups of the config
+    pub groups: BTreeMap<String, Vec<String>>,
 }
 
 pub fn generate_io<I, O, E>(
@@ -63,21 +71,35 @@ where
         return Ok(SecretString::new("".to_string().into()));
     }
 
-    let pg = PasswordGenerator::new()
-        .length(gen_cfg.pass_length)
-        .numbers(true)
-        .lowercase_letters(true)
-        .uppercase_letters(true)
-        .symbols(!gen_cfg.no_symbols)
-        .spaces(false)
-        .exclude_similar_characters(true)
-        .strict(true);
-
-    let password = SecretString::new(pg.generate_one().map_err(|e| anyhow!(e))?.into());
+    let password = if gen_cfg.character_set.is_empty() {
+        let pg = PasswordGenerator::new()
+            .length(gen_cfg.pass_length)
+            .numbers(true)
+            .lowercase_letters(true)
+            .uppercase_letters(true)
+            .symbols(!gen_cfg.no_symbols)
+            .spaces(false)
+            .exclude_similar_characters(true)
+            .strict(true);
+        SecretString::new(pg.generate_one().map_err(|e| anyhow!(e))?.into())
+    } else {
+        let mut charset = expand_character_set(&gen_cfg.character_set)?;
+        if gen_cfg.no_symbols {
+            charset.retain(|c| !c.is_ascii_punctuation());
+        }
+        if charset.is_empty() {
+            return Err(anyhow!(
+                "Character set '{}' has no usable character",
+                gen_cfg.character_set
+            ));
+        }
+        SecretString::new(rand_string_from(&charset, gen_cfg.pass_length).into())
+    };
 
     // Get the appropriate key fingerprints for this path
-    let keys_fpr = get_dir_gpg_id_content(root, &pass_path)?;
-    let client = PGPClient::new(&gen_cfg.pgp_executable, &keys_fpr)?;
+    let keys_fpr = get_dir_gpg_id_content(root, &pass_path, &gen_cfg.groups)?;
+    let client =
+        PGPClient::new(&gen_cfg.pgp_executable, &keys_fpr)?.with_options(&gen_cfg.pgp_options);
 
     if gen_cfg.in_place && pass_path.exists() {
         let existing = client.decrypt_stdin(root, path_to_str(&pass_path)?)?;
@@ -110,6 +132,64 @@ where
     Ok(password)
 }
 
+/// Expand a `tr` style set: `[:alnum:]`, `[:alpha:]`, `[:digit:]`, `[:lower:]`,
+/// `[:upper:]`, `[:punct:]` and `[:xdigit:]` classes, ranges like `a-f` and literal characters,
+/// `\` escaping the next one
+pub fn expand_character_set(set: &str) -> Result<Vec<char>> {
+    let class = |name: &str| -> Option<fn(&char) -> bool> {
+        Some(match name {
+            "alnum" => char::is_ascii_alphanumeric,
+            "alpha" => char::is_ascii_alphabetic,
+            "digit" => char::is_ascii_digit,
+            "lower" => char::is_ascii_lowercase,
+            "upper" => char::is_ascii_uppercase,
+            "punct" => char::is_ascii_punctuation,
+            "xdigit" => char::is_ascii_hexdigit,
+            _ => return None,
+        })
+    };
+
+    let chars: Vec<char> = set.chars().collect();
+    let mut res = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '[' && chars.get(i + 1) == Some(&':') {
+            let rest: String = chars[i + 2..].iter().collect();
+            let name = rest
+                .split_once(":]")
+                .map(|(name, _)| name)
+                .ok_or_else(|| anyhow!("Unclosed class in character set '{set}'"))?;
+            let filter =
+                class(name).ok_or_else(|| anyhow!("Unknown class '[:{name}:]' in '{set}'"))?;
+            res.extend((0..=127u8).map(char::from).filter(filter));
+            i += name.chars().count() + 4;
+            continue;
+        }
+
+        let c = if chars[i] == '\\' && i + 1 < chars.len() {
+            i += 1;
+            chars[i]
+        } else {
+            chars[i]
+        };
+        if chars.get(i + 1) == Some(&'-') && i + 2 < chars.len() {
+            let end = chars[i + 2];
+            if end < c {
+                return Err(anyhow!("Invalid range '{c}-{end}' in character set '{set}'"));
+            }
+            res.extend(c..=end);
+            i += 3;
+        } else {
+            res.push(c);
+            i += 1;
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    res.retain(|c| seen.insert(*c));
+    Ok(res)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -154,8 +234,11 @@ mod tests {
                     in_place: false,
                     force: false,
                     pass_length: 16,
+                    character_set: String::new(),
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let mut io_streams =
@@ -227,8 +310,11 @@ mod tests {
                     in_place: true,
                     force: false,
                     pass_length: 12,
+                    character_set: String::new(),
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let mut io_streams =
@@ -274,8 +360,11 @@ mod tests {
                     in_place: false,
                     force: true,
                     pass_length: 8,
+                    character_set: String::new(),
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let mut io_streams =
@@ -314,8 +403,11 @@ mod tests {
                     in_place: false,
                     force: false,
                     pass_length: 10,
+                    character_set: String::new(),
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let mut io_streams =
@@ -353,8 +445,11 @@ mod tests {
                     in_place: false,
                     force: false,
                     pass_length: 16,
+                    character_set: String::new(),
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let mut io_streams =
@@ -390,8 +485,11 @@ mod tests {
                     in_place: true,
                     force: true,
                     pass_length: 16,
+                    character_set: String::new(),
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let mut io_streams =
@@ -406,4 +504,20 @@ mod tests {
             }
         );
     }
+
+    #[test]
+    fn expand_character_set_test() {
+        let digits: Vec<char> = "0123456789".chars().collect();
+        assert_eq!(expand_character_set("[:digit:]").unwrap(), digits);
+        assert_eq!(
+            expand_character_set("a-e0-3").unwrap(),
+            "abcde0123".chars().collect::<Vec<_>>()
+        );
+        assert_eq!(expand_character_set("\\-x-x").unwrap(), vec!['-', 'x']);
+        assert_eq!(expand_character_set("[:alnum:]").unwrap().len(), 62);
+        assert_eq!(expand_character_set("[:punct:]_").unwrap().len(), 32);
+        assert!(expand_character_set("[:nope:]").is_err());
+        assert!(expand_character_set("[:alnum").is_err());
+        assert!(expand_character_set("z-a").is_err());
+    }
 }
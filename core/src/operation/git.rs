@@ -18,3 +18,22 @@ pub fn git_io(executable: &str, work_dir: &Path, args: &[&str]) -> Result<()> {
         Err(anyhow!(format!("Failed to run git command, code {:?}", status)))
     }
 }
+
+pub fn git_output(executable: &str, work_dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new(executable)
+        .args(args)
+        .current_dir(work_dir)
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()?;
+
+    if output.status.success() {
+        Ok(String::from_utf8(output.stdout)?)
+    } else {
+        Err(anyhow!(format!(
+            "Failed to run git command, code {:?}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
@@ -1,23 +1,26 @@
 use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
 use std::hash::{Hash, Hasher};
 use std::io::Write;
 use std::path::Path;
 
 use anyhow::Result;
 use colored::{Color, Colorize};
+use log::debug;
 use regex::Regex;
 use secrecy::ExposeSecret;
-use walkdir::WalkDir;
 
 use crate::config::cli::PrintConfig;
-use crate::pgp::PGPClient;
-use crate::util::fs_util::{get_dir_gpg_id_content, path_to_str};
+use crate::pgp::{is_not_text, PGPClient};
+use crate::util::fs_util::{get_dir_gpg_id_content, path_to_str, walk_store};
 use crate::util::tree::string_to_color_opt;
 
 #[derive(Default)]
 pub struct GrepPrintConfig {
     pub grep_pass_color: Option<Color>,
     pub grep_match_color: Option<Color>,
+    /// Printed before every matching pass name, e.g. `work:` when searching several repos
+    pub pass_prefix: String,
 }
 
 impl<CFG: AsRef<PrintConfig>> From<CFG> for GrepPrintConfig {
@@ -25,12 +28,15 @@ impl<CFG: AsRef<PrintConfig>> From<CFG> for GrepPrintConfig {
         Self {
             grep_pass_color: string_to_color_opt(&config.as_ref().grep_pass_color),
             grep_match_color: string_to_color_opt(&config.as_ref().grep_match_color),
+            pass_prefix: String::new(),
         }
     }
 }
 
 pub fn grep_stream<O>(
     pgp_executable: &str,
+    pgp_options: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
     root: &Path,
     search_str: &str,
     print_cfg: &GrepPrintConfig,
@@ -42,13 +48,13 @@ where
     let mut cache: Vec<(u64, Vec<String>, PGPClient)> = Vec::new();
     let search_regex = Regex::new(&regex::escape(search_str))?;
 
-    for entry in WalkDir::new(root) {
+    for entry in walk_store(root) {
         let entry = entry?;
         if entry.file_type().is_file() && entry.path().extension().unwrap_or_default() == "gpg" {
             let relative_path = entry.path().strip_prefix(root)?;
             let relative_path_str = path_to_str(relative_path)?;
 
-            let mut keys_fpr = get_dir_gpg_id_content(root, entry.path())?;
+            let mut keys_fpr = get_dir_gpg_id_content(root, entry.path(), groups)?;
             keys_fpr.sort();
 
             let mut hasher = DefaultHasher::new();
@@ -64,29 +70,34 @@ where
                 let new_client = PGPClient::new(
                     pgp_executable,
                     &keys_fpr.iter().map(String::as_str).collect::<Vec<_>>(),
-                )?;
+                )?
+                .with_options(pgp_options);
                 cache.push((key_hash, keys_fpr.clone(), new_client));
                 &mut cache.last_mut().unwrap().2
             };
 
-            let decrypted = client.decrypt_stdin(root, path_to_str(entry.path())?)?;
+            let decrypted = match client.decrypt_stdin(root, path_to_str(entry.path())?) {
+                Ok(decrypted) => decrypted,
+                Err(e) if is_not_text(&e) => {
+                    debug!("Skipping binary entry {relative_path_str}");
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             let mut has_matches = false;
 
             for line in decrypted.expose_secret().lines() {
                 if line.contains(search_str) {
                     if !has_matches {
+                        let pass_name = format!(
+                            "{}{}",
+                            print_cfg.pass_prefix,
+                            &relative_path_str[..relative_path_str.len() - 4]
+                        );
                         if let Some(color) = print_cfg.grep_pass_color {
-                            writeln!(
-                                out_stream,
-                                "{}:",
-                                &relative_path_str[..relative_path_str.len() - 4].color(color)
-                            )?;
+                            writeln!(out_stream, "{}:", pass_name.color(color))?;
                         } else {
-                            writeln!(
-                                out_stream,
-                                "{}:",
-                                &relative_path_str[..relative_path_str.len() - 4]
-                            )?;
+                            writeln!(out_stream, "{pass_name}:")?;
                         }
                         has_matches = true;
                     }
@@ -111,6 +122,8 @@ where
 
 pub fn grep(
     pgp_executable: &str,
+    pgp_options: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
     root: &Path,
     search_str: &str,
     print_cfg: &GrepPrintConfig,
@@ -119,13 +132,13 @@ pub fn grep(
     let mut cache: Vec<(u64, Vec<String>, PGPClient)> = Vec::new();
     let search_regex = Regex::new(&regex::escape(search_str))?;
 
-    for entry in WalkDir::new(root) {
+    for entry in walk_store(root) {
         let entry = entry?;
         if entry.file_type().is_file() && entry.path().extension().unwrap_or_default() == "gpg" {
             let relative_path = entry.path().strip_prefix(root)?;
             let relative_path_str = path_to_str(relative_path)?;
 
-            let mut keys_fpr = get_dir_gpg_id_content(root, entry.path())?;
+            let mut keys_fpr = get_dir_gpg_id_content(root, entry.path(), groups)?;
             keys_fpr.sort();
 
             let mut hasher = DefaultHasher::new();
@@ -141,12 +154,20 @@ pub fn grep(
                 let new_client = PGPClient::new(
                     pgp_executable,
                     &keys_fpr.iter().map(String::as_str).collect::<Vec<_>>(),
-                )?;
+                )?
+                .with_options(pgp_options);
                 cache.push((key_hash, keys_fpr.clone(), new_client));
                 &mut cache.last_mut().unwrap().2
             };
 
-            let decrypted = client.decrypt_stdin(root, path_to_str(entry.path())?)?;
+            let decrypted = match client.decrypt_stdin(root, path_to_str(entry.path())?) {
+                Ok(decrypted) => decrypted,
+                Err(e) if is_not_text(&e) => {
+                    debug!("Skipping binary entry {relative_path_str}");
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             let matching_lines: Vec<String> = decrypted
                 .expose_secret()
                 .lines()
@@ -165,13 +186,15 @@ pub fn grep(
                 .collect();
 
             if !matching_lines.is_empty() {
+                let pass_name = format!(
+                    "{}{}",
+                    print_cfg.pass_prefix,
+                    &relative_path_str[..relative_path_str.len() - 4]
+                );
                 if let Some(color) = print_cfg.grep_pass_color {
-                    results.push(format!(
-                        "{}:",
-                        &relative_path_str[..relative_path_str.len() - 4].color(color)
-                    ));
+                    results.push(format!("{}:", pass_name.color(color)));
                 } else {
-                    results.push(format!("{}:", &relative_path_str[..relative_path_str.len() - 4]));
+                    results.push(format!("{pass_name}:"));
                 }
                 results.extend(matching_lines);
             }
@@ -224,7 +247,15 @@ mod tests {
 
         cleanup!(
             {
-                let results = grep(&executable, &root, "211", &GrepPrintConfig::default()).unwrap();
+                let results = grep(
+                    &executable,
+                    &[],
+                    &BTreeMap::new(),
+                    &root,
+                    "211",
+                    &GrepPrintConfig::default(),
+                )
+                .unwrap();
                 assert_eq!(results, vec![&format!("dir1{}01:", path::MAIN_SEPARATOR), "2112112"]);
             },
             {
@@ -241,11 +272,26 @@ mod tests {
 
         cleanup!(
             {
-                let results =
-                    grep(&executable, &root, "Overlord", &GrepPrintConfig::default()).unwrap();
+                let results = grep(
+                    &executable,
+                    &[],
+                    &BTreeMap::new(),
+                    &root,
+                    "Overlord",
+                    &GrepPrintConfig::default(),
+                )
+                .unwrap();
                 assert_eq!(results, vec![&format!("dir2{}10:", path::MAIN_SEPARATOR), "Overlord"]);
 
-                let results = grep(&executable, &root, "01", &GrepPrintConfig::default()).unwrap();
+                let results = grep(
+                    &executable,
+                    &[],
+                    &BTreeMap::new(),
+                    &root,
+                    "01",
+                    &GrepPrintConfig::default(),
+                )
+                .unwrap();
                 assert_eq!(results, Vec::<String>::new());
             },
             {
@@ -262,8 +308,15 @@ mod tests {
 
         cleanup!(
             {
-                let results =
-                    grep(&executable, &root, "nonexistent", &GrepPrintConfig::default()).unwrap();
+                let results = grep(
+                    &executable,
+                    &[],
+                    &BTreeMap::new(),
+                    &root,
+                    "nonexistent",
+                    &GrepPrintConfig::default(),
+                )
+                .unwrap();
                 assert!(results.is_empty());
             },
             {
@@ -271,4 +324,33 @@ mod tests {
             }
         );
     }
+    #[test]
+    #[serial]
+    #[ignore = "need run interactively"]
+    fn grep_skips_binary() {
+        let (executable, email, _tmp_dir, root) = setup_test_environment();
+
+        cleanup!(
+            {
+                let client = PGPClient::new(&executable, &[&email]).unwrap();
+                for (name, content) in [("bin", &b"2112112\xff"[..]), ("nul", b"2112112\0")] {
+                    let path = root.join(format!("dir2/{name}.gpg"));
+                    client.encrypt_bytes(content, path.to_str().unwrap()).unwrap();
+                }
+                let results = grep(
+                    &executable,
+                    &[],
+                    &BTreeMap::new(),
+                    &root,
+                    "211",
+                    &GrepPrintConfig::default(),
+                )
+                .unwrap();
+                assert_eq!(results, vec![&format!("dir1{}01:", path::MAIN_SEPARATOR), "2112112"]);
+            },
+            {
+                clean_up_test_key(&executable, &[&email]).unwrap();
+            }
+        );
+    }
 }
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+
+use super::ImportedEntry;
+
+/// Parse the unencrypted JSON export of Bitwarden. Logins and secure notes keep their layout,
+/// the details of cards and identities become fields.
+pub(super) fn parse(content: &str) -> Result<Vec<ImportedEntry>> {
+    let export: Value =
+        serde_json::from_str(content).map_err(|e| anyhow!("Invalid Bitwarden JSON: {e}"))?;
+    if export["encrypted"].as_bool() == Some(true) {
+        return Err(anyhow!("The Bitwarden export is encrypted, export it unencrypted instead"));
+    }
+    let items = export["items"]
+        .as_array()
+        .ok_or_else(|| anyhow!("Not a Bitwarden JSON export, there is no item list"))?;
+    let folders: HashMap<&str, &str> = export["folders"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|folder| Some((folder["id"].as_str()?, folder["name"].as_str()?)))
+        .collect();
+
+    let mut entries = Vec::with_capacity(items.len());
+    for item in items {
+        let folder = item["folderId"].as_str().and_then(|id| folders.get(id)).copied();
+        let mut entry = ImportedEntry {
+            folder: folder
+                .unwrap_or_default()
+                .split('/')
+                .filter(|part| !part.trim().is_empty())
+                .map(str::to_string)
+                .collect(),
+            title: text(&item["name"]),
+            notes: text(&item["notes"]),
+            ..Default::default()
+        };
+
+        let login = &item["login"];
+        entry.login = text(&login["username"]);
+        entry.password = text(&login["password"]);
+        let mut uris = login["uris"].as_array().into_iter().flatten().map(|uri| text(&uri["uri"]));
+        entry.url = uris.next().unwrap_or_default();
+        entry.fields.extend(uris.filter(|uri| !uri.is_empty()).map(|uri| ("url".into(), uri)));
+        if !text(&login["totp"]).is_empty() {
+            entry.fields.push(("otp".into(), text(&login["totp"])));
+        }
+        for details in [&item["card"], &item["identity"]] {
+            for (key, value) in details.as_object().into_iter().flatten() {
+                let value = text(value);
+                if !value.is_empty() {
+                    entry.fields.push((key.clone(), value));
+                }
+            }
+        }
+        for field in item["fields"].as_array().into_iter().flatten() {
+            let key = text(&field["name"]).replace(char::is_whitespace, "_");
+            let value = text(&field["value"]);
+            if !key.is_empty() && !value.is_empty() {
+                entry.fields.push((key, value));
+            }
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Number(number) => number.to_string(),
+        Value::Bool(flag) => flag.to_string(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn bitwarden_json_test() {
+        let content = r#"{
+  "encrypted": false,
+  "folders": [{ "id": "f1", "name": "Work/Infra" }],
+  "items": [
+    {
+      "id": "i1", "folderId": "f1", "type": 1, "name": "Grafana", "notes": null,
+      "fields": [{ "name": "API key", "value": "abc", "type": 1 }],
+      "login": {
+        "uris": [{ "match": null, "uri": "https://grafana.example" }, { "uri": "https://grafana2.example" }],
+        "username": "ops", "password": "pw", "totp": "JBSWY3DP"
+      }
+    },
+    {
+      "id": "i2", "folderId": null, "type": 3, "name": "Visa", "notes": "backup card",
+      "card": { "cardholderName": "Eve", "number": "4111", "code": "123", "brand": null }
+    }
+  ]
+}"#;
+        let entries = parse(content).unwrap();
+        assert_eq!(
+            entries[0],
+            ImportedEntry {
+                folder: vec!["Work".into(), "Infra".into()],
+                title: "Grafana".into(),
+                password: "pw".into(),
+                login: "ops".into(),
+                url: "https://grafana.example".into(),
+                fields: vec![
+                    ("url".into(), "https://grafana2.example".into()),
+                    ("otp".into(), "JBSWY3DP".into()),
+                    ("API_key".into(), "abc".into()),
+                ],
+                notes: String::new(),
+            }
+        );
+        assert_eq!(entries[1].pass_name(None), "Visa");
+        assert_eq!(entries[1].fields.len(), 3);
+        assert!(parse(r#"{"encrypted": true, "items": []}"#).is_err());
+    }
+}
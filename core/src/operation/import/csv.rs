@@ -0,0 +1,123 @@
+use anyhow::{anyhow, Result};
+
+use super::{ImportFormat, ImportedEntry};
+
+/// Header names of each column, compared case-insensitively. The exports differ mostly in
+/// naming, `name` is the title in Chrome and LastPass, `extra` the notes in LastPass.
+const TITLE: &[&str] = &["title", "name"];
+const FOLDER: &[&str] = &["group", "grouping", "folder"];
+const LOGIN: &[&str] = &["username", "login", "login_username"];
+const PASSWORD: &[&str] = &["password", "login_password"];
+const URL: &[&str] = &["url", "website", "login_uri"];
+const NOTES: &[&str] = &["notes", "note", "extra"];
+const OTP: &[&str] = &["totp", "otpauth", "otp", "login_totp"];
+
+/// URL LastPass gives its secure notes
+const LASTPASS_NOTE_URL: &str = "http://sn";
+
+pub(super) fn parse(format: ImportFormat, content: &str) -> Result<Vec<ImportedEntry>> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+    let headers: Vec<String> =
+        reader.headers()?.iter().map(|header| header.trim().to_lowercase()).collect();
+    let column =
+        |names: &[&str]| headers.iter().position(|header| names.contains(&header.as_str()));
+    let Some(password) = column(PASSWORD) else {
+        return Err(anyhow!("Not a {format} export, there is no password column"));
+    };
+    let (title, folder, login, url, notes, otp) =
+        (column(TITLE), column(FOLDER), column(LOGIN), column(URL), column(NOTES), column(OTP));
+
+    let mut entries = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let get = |index: Option<usize>| {
+            index.and_then(|index| record.get(index)).unwrap_or_default().to_string()
+        };
+        let mut entry = ImportedEntry {
+            folder: split_folder(format, &get(folder)),
+            title: get(title),
+            password: get(Some(password)),
+            login: get(login),
+            url: get(url),
+            notes: get(notes),
+            ..Default::default()
+        };
+        let otp = get(otp);
+        if !otp.is_empty() {
+            entry.fields.push(("otp".into(), otp));
+        }
+        if format == ImportFormat::LastpassCsv && entry.url == LASTPASS_NOTE_URL {
+            entry.url.clear();
+        }
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn split_folder(format: ImportFormat, folder: &str) -> Vec<String> {
+    let mut parts: Vec<String> = folder
+        .split(['/', '\\'])
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(str::to_string)
+        .collect();
+    // KeePassXC puts every group below the root group of the database
+    if format == ImportFormat::KeepassxcCsv && !parts.is_empty() {
+        parts.remove(0);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn keepassxc_csv_test() {
+        let content = "\"Group\",\"Title\",\"Username\",\"Password\",\"URL\",\"Notes\",\"TOTP\",\"Icon\",\"Last Modified\",\"Created\"\n\
+                       \"Root/Work\",\"GitHub\",\"alice\",\"p,w\",\"https://github.com\",\"line 1\nline 2\",\"\",\"0\",\"\",\"\"\n";
+        let entries = parse(ImportFormat::KeepassxcCsv, content).unwrap();
+        assert_eq!(
+            entries,
+            vec![ImportedEntry {
+                folder: vec!["Work".into()],
+                title: "GitHub".into(),
+                password: "p,w".into(),
+                login: "alice".into(),
+                url: "https://github.com".into(),
+                fields: vec![],
+                notes: "line 1\nline 2".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn browser_and_lastpass_csv_test() {
+        let chrome = "name,url,username,password,note\nexample.com,https://example.com/,bob,pw,\n";
+        let entry = &parse(ImportFormat::ChromeCsv, chrome).unwrap()[0];
+        assert_eq!((entry.title.as_str(), entry.login.as_str()), ("example.com", "bob"));
+
+        let firefox =
+            "\"url\",\"username\",\"password\",\"httpRealm\",\"formActionOrigin\",\"guid\"\n\
+                       \"https://mail.example.org\",\"carol\",\"pw\",,\"\",\"{1}\"\n";
+        let entry = &parse(ImportFormat::FirefoxCsv, firefox).unwrap()[0];
+        assert_eq!(entry.pass_name(None), "mail.example.org");
+
+        let lastpass = "url,username,password,totp,extra,name,grouping,fav\n\
+                        http://sn,,,,the note,Wifi,Home\\Network,0\n";
+        let entry = &parse(ImportFormat::LastpassCsv, lastpass).unwrap()[0];
+        assert_eq!(entry.pass_name(None), "Home/Network/Wifi");
+        assert_eq!((entry.url.as_str(), entry.notes.as_str()), ("", "the note"));
+
+        let onepassword = "Title,Url,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes\n\
+                           Bank,https://bank.example,dave,pw,otpauth://totp/x,false,false,,\n";
+        let entry = &parse(ImportFormat::OnePasswordCsv, onepassword).unwrap()[0];
+        assert_eq!(entry.fields, vec![("otp".to_string(), "otpauth://totp/x".to_string())]);
+
+        assert!(parse(ImportFormat::ChromeCsv, "a,b\n1,2\n").is_err());
+    }
+}
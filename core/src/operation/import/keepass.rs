@@ -0,0 +1,129 @@
+use anyhow::{anyhow, Result};
+use roxmltree::{Document, Node};
+
+use super::ImportedEntry;
+
+/// Parse a KeePass 2 XML export. The root group is left out of the folders, and so are the
+/// recycle bin and the history of each entry.
+pub(super) fn parse(content: &str) -> Result<Vec<ImportedEntry>> {
+    let doc = Document::parse(content).map_err(|e| anyhow!("Invalid KeePass XML: {e}"))?;
+    let file = doc.root_element();
+    if !file.has_tag_name("KeePassFile") {
+        return Err(anyhow!(
+            "Not a KeePass XML export, the root element is <{}>",
+            file.tag_name().name()
+        ));
+    }
+    let recycle_bin = child(file, "Meta").and_then(|meta| child_text(meta, "RecycleBinUUID"));
+    let root_group = child(file, "Root")
+        .and_then(|root| child(root, "Group"))
+        .ok_or_else(|| anyhow!("The KeePass XML export has no root group"))?;
+
+    let mut entries = Vec::new();
+    read_group(root_group, &mut Vec::new(), recycle_bin, &mut entries);
+    Ok(entries)
+}
+
+fn read_group(
+    group: Node,
+    folder: &mut Vec<String>,
+    recycle_bin: Option<&str>,
+    entries: &mut Vec<ImportedEntry>,
+) {
+    for node in group.children().filter(Node::is_element) {
+        match node.tag_name().name() {
+            "Entry" => entries.push(read_entry(node, folder)),
+            "Group" => {
+                if recycle_bin.is_some() && child_text(node, "UUID") == recycle_bin {
+                    continue;
+                }
+                folder.push(child_text(node, "Name").unwrap_or_default().to_string());
+                read_group(node, folder, recycle_bin, entries);
+                folder.pop();
+            }
+            _ => {}
+        }
+    }
+}
+
+fn read_entry(node: Node, folder: &[String]) -> ImportedEntry {
+    let mut entry = ImportedEntry { folder: folder.to_vec(), ..Default::default() };
+    for string in node.children().filter(|child| child.has_tag_name("String")) {
+        let key = child_text(string, "Key").unwrap_or_default();
+        let value = child_text(string, "Value").unwrap_or_default().to_string();
+        match key {
+            "Title" => entry.title = value,
+            "UserName" => entry.login = value,
+            "Password" => entry.password = value,
+            "URL" => entry.url = value,
+            "Notes" => entry.notes = value,
+            "otp" | "TOTP Seed" => entry.fields.push(("otp".into(), value)),
+            _ if !value.is_empty() => {
+                entry.fields.push((key.replace(char::is_whitespace, "_"), value))
+            }
+            _ => {}
+        }
+    }
+    entry
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|child| child.text())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn keepass_xml_test() {
+        let content = r#"<?xml version="1.0" encoding="utf-8" standalone="yes"?>
+<KeePassFile>
+  <Meta><RecycleBinUUID>BIN=</RecycleBinUUID></Meta>
+  <Root>
+    <Group>
+      <UUID>ROOT=</UUID><Name>Database</Name>
+      <Entry>
+        <String><Key>Title</Key><Value>Mail</Value></String>
+        <String><Key>UserName</Key><Value>alice</Value></String>
+        <String><Key>Password</Key><Value ProtectInMemory="True">s&amp;cret</Value></String>
+        <String><Key>URL</Key><Value>https://mail.example.com</Value></String>
+        <String><Key>Notes</Key><Value>a note</Value></String>
+        <String><Key>Recovery code</Key><Value>1234</Value></String>
+        <History><Entry><String><Key>Title</Key><Value>Old</Value></String></Entry></History>
+      </Entry>
+      <Group>
+        <UUID>WORK=</UUID><Name>Work</Name>
+        <Entry><String><Key>Title</Key><Value>VPN</Value></String><String><Key>Password</Key><Value>pw</Value></String></Entry>
+      </Group>
+      <Group>
+        <UUID>BIN=</UUID><Name>Recycle Bin</Name>
+        <Entry><String><Key>Title</Key><Value>Deleted</Value></String></Entry>
+      </Group>
+    </Group>
+  </Root>
+</KeePassFile>"#;
+        let entries = parse(content).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0],
+            ImportedEntry {
+                folder: vec![],
+                title: "Mail".into(),
+                password: "s&cret".into(),
+                login: "alice".into(),
+                url: "https://mail.example.com".into(),
+                fields: vec![("Recovery_code".into(), "1234".into())],
+                notes: "a note".into(),
+            }
+        );
+        assert_eq!(entries[1].pass_name(None), "Work/VPN");
+        assert!(parse("<KeePass/>").is_err());
+    }
+}
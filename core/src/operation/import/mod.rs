@@ -0,0 +1,388 @@
+mod bitwarden;
+mod csv;
+mod keepass;
+
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, Result};
+use secrecy::SecretString;
+
+use crate::config::cli::ParsConfig;
+use crate::operation::batch::PasswordStore;
+
+/// Export formats of other password managers `import` reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    KeepassXml,
+    KeepassxcCsv,
+    BitwardenJson,
+    OnePasswordCsv,
+    ChromeCsv,
+    FirefoxCsv,
+    LastpassCsv,
+}
+
+impl ImportFormat {
+    pub const ALL: &[ImportFormat] = &[
+        ImportFormat::KeepassXml,
+        ImportFormat::KeepassxcCsv,
+        ImportFormat::BitwardenJson,
+        ImportFormat::OnePasswordCsv,
+        ImportFormat::ChromeCsv,
+        ImportFormat::FirefoxCsv,
+        ImportFormat::LastpassCsv,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ImportFormat::KeepassXml => "keepass-xml",
+            ImportFormat::KeepassxcCsv => "keepassxc-csv",
+            ImportFormat::BitwardenJson => "bitwarden-json",
+            ImportFormat::OnePasswordCsv => "1password-csv",
+            ImportFormat::ChromeCsv => "chrome-csv",
+            ImportFormat::FirefoxCsv => "firefox-csv",
+            ImportFormat::LastpassCsv => "lastpass-csv",
+        }
+    }
+}
+
+impl Display for ImportFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        ImportFormat::ALL.iter().find(|format| format.name() == s).copied().ok_or_else(|| {
+            let names: Vec<&str> = ImportFormat::ALL.iter().map(ImportFormat::name).collect();
+            anyhow!("Unknown format '{s}', expected one of: {}", names.join(", "))
+        })
+    }
+}
+
+/// What to do when an imported name is already taken, in the store or earlier in the import
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Import nothing and report the conflicts
+    Abort,
+    /// Keep the existing entry
+    Skip,
+    Overwrite,
+    /// Import as `<name>-2`, `<name>-3`, ...
+    Rename,
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "abort" => Ok(ConflictPolicy::Abort),
+            "skip" => Ok(ConflictPolicy::Skip),
+            "overwrite" => Ok(ConflictPolicy::Overwrite),
+            "rename" => Ok(ConflictPolicy::Rename),
+            _ => Err(anyhow!(
+                "Unknown conflict policy '{s}', expected abort, skip, overwrite or rename"
+            )),
+        }
+    }
+}
+
+/// An entry read from an export. `folder` and `title` are sanitized into a store path later.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ImportedEntry {
+    pub folder: Vec<String>,
+    pub title: String,
+    pub password: String,
+    pub login: String,
+    pub url: String,
+    /// Other `key: value` fields, like custom fields or an OTP secret
+    pub fields: Vec<(String, String)>,
+    pub notes: String,
+}
+
+impl ImportedEntry {
+    /// The entry in the structured layout: password line, `login:`, `url:` and other fields,
+    /// then the notes
+    pub fn content(&self) -> SecretString {
+        let mut content = self.password.clone();
+        content.push('\n');
+        let fields = [("login", &self.login), ("url", &self.url)]
+            .into_iter()
+            .chain(self.fields.iter().map(|(key, value)| (key.as_str(), value)));
+        for (key, value) in fields {
+            if !value.is_empty() {
+                content.push_str(&format!("{key}: {}\n", value.replace('\n', " ")));
+            }
+        }
+        if !self.notes.trim().is_empty() {
+            content.push_str(self.notes.trim_end());
+            content.push('\n');
+        }
+        content.into()
+    }
+
+    /// Store path of the entry below `prefix`, each part made safe to be a file name
+    pub fn pass_name(&self, prefix: Option<&str>) -> String {
+        let title = if !self.title.trim().is_empty() {
+            self.title.clone()
+        } else if !self.url.is_empty() {
+            url_host(&self.url).to_string()
+        } else {
+            "untitled".into()
+        };
+        prefix
+            .into_iter()
+            .flat_map(|prefix| prefix.split('/'))
+            .map(str::to_string)
+            .chain(self.folder.iter().cloned())
+            .map(|part| sanitize(&part))
+            .filter(|part| !part.is_empty())
+            .chain([sanitize(&title)].into_iter().map(|part| {
+                if part.is_empty() {
+                    "untitled".into()
+                } else {
+                    part
+                }
+            }))
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ImportAction {
+    Create,
+    Overwrite,
+    /// The name is taken and the entry is left out
+    Skip,
+    /// The name is taken, imported under the given name instead
+    Rename(String),
+    /// The name is taken and the policy is to abort
+    Conflict,
+}
+
+/// One entry of the import with what will happen to it
+#[derive(Debug)]
+pub struct PlannedEntry {
+    pub name: String,
+    pub action: ImportAction,
+    pub entry: ImportedEntry,
+}
+
+impl PlannedEntry {
+    /// Name the entry is written to, `None` when it is not written
+    pub fn target(&self) -> Option<&str> {
+        match &self.action {
+            ImportAction::Create | ImportAction::Overwrite => Some(&self.name),
+            ImportAction::Rename(name) => Some(name),
+            ImportAction::Skip | ImportAction::Conflict => None,
+        }
+    }
+}
+
+/// Read the entries of an export in `format`
+pub fn parse(format: ImportFormat, content: &str) -> Result<Vec<ImportedEntry>> {
+    match format {
+        ImportFormat::KeepassXml => keepass::parse(content),
+        ImportFormat::BitwardenJson => bitwarden::parse(content),
+        ImportFormat::KeepassxcCsv
+        | ImportFormat::OnePasswordCsv
+        | ImportFormat::ChromeCsv
+        | ImportFormat::FirefoxCsv
+        | ImportFormat::LastpassCsv => csv::parse(format, content),
+    }
+}
+
+/// Decide where each entry goes in the store at `root`, applying `policy` to names that exist
+/// or come up twice. Nothing is written.
+pub fn plan(
+    root: &Path,
+    prefix: Option<&str>,
+    entries: Vec<ImportedEntry>,
+    policy: ConflictPolicy,
+) -> Vec<PlannedEntry> {
+    let mut taken: Vec<String> = Vec::new();
+    let mut planned: Vec<PlannedEntry> = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let name = entry.pass_name(prefix);
+        let exists = |name: &str, taken: &[String]| {
+            taken.iter().any(|known| known == name) || root.join(format!("{name}.gpg")).exists()
+        };
+        let action = if !exists(&name, &taken) {
+            ImportAction::Create
+        } else {
+            match policy {
+                ConflictPolicy::Abort => ImportAction::Conflict,
+                ConflictPolicy::Skip => ImportAction::Skip,
+                ConflictPolicy::Overwrite => {
+                    // A later entry of the same name wins
+                    for earlier in planned.iter_mut().filter(|earlier| earlier.name == name) {
+                        if earlier.target() == Some(name.as_str()) {
+                            earlier.action = ImportAction::Skip;
+                        }
+                    }
+                    ImportAction::Overwrite
+                }
+                ConflictPolicy::Rename => {
+                    let renamed = (2..)
+                        .map(|i| format!("{name}-{i}"))
+                        .find(|candidate| !exists(candidate, &taken))
+                        .unwrap_or_default();
+                    ImportAction::Rename(renamed)
+                }
+            }
+        };
+        if let ImportAction::Rename(renamed) = &action {
+            taken.push(renamed.clone());
+        }
+        taken.push(name.clone());
+        planned.push(PlannedEntry { name, action, entry });
+    }
+    planned
+}
+
+/// Write the planned entries to the store at `root` as a single commit, returns how many were
+/// written. Fails without writing anything when an entry conflicts.
+pub fn import(config: &ParsConfig, root: &Path, planned: &[PlannedEntry]) -> Result<usize> {
+    let conflicts: Vec<&str> = planned
+        .iter()
+        .filter(|entry| entry.action == ImportAction::Conflict)
+        .map(|entry| entry.name.as_str())
+        .collect();
+    if !conflicts.is_empty() {
+        return Err(anyhow!(format!(
+            "{} name(s) already taken: {}",
+            conflicts.len(),
+            conflicts.join(", ")
+        )));
+    }
+
+    let store = PasswordStore::new(root, "gpg", config);
+    store.batch(|tx| {
+        for entry in planned {
+            if let Some(name) = entry.target() {
+                let force = entry.action == ImportAction::Overwrite;
+                tx.insert(name, &entry.entry.content(), force)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Host part of `url`, or `url` itself when it has none
+fn url_host(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or(rest);
+    let host = host.rsplit_once('@').map_or(host, |(_, host)| host);
+    host.split(':').next().filter(|host| !host.is_empty()).unwrap_or(url)
+}
+
+/// `part` usable as a single path component: no separators, no leading dots, trimmed
+fn sanitize(part: &str) -> String {
+    part.trim()
+        .replace(['/', '\\'], "-")
+        .chars()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .trim_start_matches('.')
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use pretty_assertions::assert_eq;
+    use secrecy::ExposeSecret;
+
+    use super::*;
+    use crate::util::test_util::gen_unique_temp_dir;
+
+    fn entry(folder: &[&str], title: &str) -> ImportedEntry {
+        ImportedEntry {
+            folder: folder.iter().map(|part| part.to_string()).collect(),
+            title: title.into(),
+            password: "secret".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn entry_layout_test() {
+        let mut imported = entry(&["Work", "../ops"], "db/main");
+        imported.login = "alice".into();
+        imported.fields.push(("otp".into(), "JBSWY3DP".into()));
+        imported.notes = "first line\nsecond line\n".into();
+        assert_eq!(
+            imported.content().expose_secret(),
+            "secret\nlogin: alice\notp: JBSWY3DP\nfirst line\nsecond line\n"
+        );
+        assert_eq!(imported.pass_name(Some("team/imported")), "team/imported/Work/-ops/db-main");
+
+        let mut untitled = entry(&[], " ");
+        untitled.url = "https://user@accounts.example.com:443/login".into();
+        assert_eq!(untitled.pass_name(None), "accounts.example.com");
+        untitled.url.clear();
+        assert_eq!(untitled.pass_name(None), "untitled");
+    }
+
+    #[test]
+    fn plan_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        fs::create_dir_all(root.join("web")).unwrap();
+        fs::write(root.join("web/github.gpg"), "").unwrap();
+        let entries = || {
+            vec![entry(&["web"], "github"), entry(&["web"], "gitlab"), entry(&["web"], "gitlab")]
+        };
+        let actions = |policy| {
+            plan(&root, None, entries(), policy)
+                .into_iter()
+                .map(|planned| planned.action)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            actions(ConflictPolicy::Abort),
+            vec![ImportAction::Conflict, ImportAction::Create, ImportAction::Conflict]
+        );
+        assert_eq!(
+            actions(ConflictPolicy::Skip),
+            vec![ImportAction::Skip, ImportAction::Create, ImportAction::Skip]
+        );
+        assert_eq!(
+            actions(ConflictPolicy::Overwrite),
+            vec![ImportAction::Overwrite, ImportAction::Skip, ImportAction::Overwrite]
+        );
+        assert_eq!(
+            actions(ConflictPolicy::Rename),
+            vec![
+                ImportAction::Rename("web/github-2".into()),
+                ImportAction::Create,
+                ImportAction::Rename("web/gitlab-2".into())
+            ]
+        );
+        assert!(import(
+            &ParsConfig::default(),
+            &root,
+            &plan(&root, None, entries(), ConflictPolicy::Abort)
+        )
+        .unwrap_err()
+        .to_string()
+        .contains("web/github, web/gitlab"));
+    }
+
+    #[test]
+    fn format_names_test() {
+        for format in ImportFormat::ALL {
+            assert_eq!(format.name().parse::<ImportFormat>().unwrap(), *format);
+        }
+        assert!("keepass".parse::<ImportFormat>().is_err());
+    }
+}
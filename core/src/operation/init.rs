@@ -1,22 +1,45 @@
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::fs::OpenOptions;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::{anyhow, Result};
 use log::debug;
 use secrecy::ExposeSecret;
-use walkdir::WalkDir;
 
+use crate::config::cli::ParsConfig;
 use crate::constants::default_constants::FPR_FILENAME;
+use crate::pgp::groups::expand_recipients;
+use crate::pgp::recipients::{RecipientChecker, RecipientStatus};
 use crate::pgp::PGPClient;
 use crate::util::fs_util::{
-    backup_encrypted_file, get_dir_gpg_id_content, path_attack_check, path_to_str,
+    create_or_overwrite_bytes, get_dir_gpg_id_content, get_dir_gpg_id_lines, get_dir_gpg_id_path,
+    path_attack_check, path_to_str, walk_store,
 };
 
 pub struct InitConfig {
     pub pgp_executable: String,
+    /// Extra options of every encryption and decryption
+    pub pgp_options: Vec<String>,
     pub keys_fpr: Vec<String>,
+    /// Recipient groups of the config
+    pub groups: BTreeMap<String, Vec<String>>,
+    /// Also re-encrypt entries hiding their recipients, which cannot be compared with the keys
+    pub force: bool,
+}
+
+impl InitConfig {
+    /// Set `keys_fpr` with the executable, options and groups of `config`, not forced
+    pub fn new(config: &ParsConfig, keys_fpr: Vec<String>) -> Self {
+        Self {
+            pgp_executable: config.executable_config.pgp_executable.clone(),
+            pgp_options: config.executable_config.pgp_options.clone(),
+            keys_fpr,
+            groups: config.groups.clone(),
+            force: false,
+        }
+    }
 }
 
 fn write_new_fpr_file(path: &Path, fprs: &[impl AsRef<str>]) -> Result<()> {
@@ -34,7 +57,26 @@ fn write_new_fpr_file(path: &Path, fprs: &[impl AsRef<str>]) -> Result<()> {
     Ok(())
 }
 
-pub fn init(config: &InitConfig, root: &Path, target_path: Option<&str>) -> Result<()> {
+/// What [`init`] changed under the target directory
+#[derive(Debug, Default)]
+pub struct InitReport {
+    /// Whether a `.gpg-id` was written with other keys than before
+    pub keys_changed: bool,
+    /// Entries re-encrypted for their new recipients
+    pub reencrypted: Vec<String>,
+    /// Entries that could not be re-encrypted with the reason, running `init` again retries them
+    pub failed: Vec<(String, String)>,
+    /// Entries hiding their recipients that were left as they are, `force` re-encrypts them
+    pub hidden: Vec<String>,
+}
+
+impl InitReport {
+    pub fn is_empty(&self) -> bool {
+        !self.keys_changed && self.reencrypted.is_empty()
+    }
+}
+
+pub fn init(config: &InitConfig, root: &Path, target_path: Option<&str>) -> Result<InitReport> {
     let target = root.join(target_path.unwrap_or_default());
     path_attack_check(root, &target)?;
 
@@ -54,7 +96,7 @@ pub fn init(config: &InitConfig, root: &Path, target_path: Option<&str>) -> Resu
     if first_init {
         let gpg_id_path = root.join(FPR_FILENAME);
         write_new_fpr_file(&gpg_id_path, &config.keys_fpr)?;
-        return Ok(());
+        return Ok(InitReport { keys_changed: true, ..Default::default() });
     }
 
     if !target.exists() {
@@ -62,55 +104,255 @@ pub fn init(config: &InitConfig, root: &Path, target_path: Option<&str>) -> Resu
         fs::create_dir_all(&target)?;
     }
 
-    // Try to read old fingerprints from the directory
-    let mut old_fprs = get_dir_gpg_id_content(root, &target)?;
+    // Try to read old fingerprints from the directory, groups are compared by name
+    let mut old_fprs = get_dir_gpg_id_lines(root, &target)?;
     old_fprs.sort();
     let mut new_fprs = config.keys_fpr.clone();
     new_fprs.sort();
 
-    if old_fprs == new_fprs {
-        println!("New fingerprints are the same as the old ones, no need to update.");
-        return Ok(());
-    }
+    let mut report = InitReport::default();
+    let new_keys = if old_fprs != new_fprs {
+        debug!("Old fpr <{old_fprs:?}>, replace with <{new_fprs:?}>");
+        // Fail before anything changes if a new key is unusable
+        let recipients = expand_recipients(root, &new_fprs, &config.groups)?;
+        PGPClient::new(&config.pgp_executable, &recipients)?;
+        Some(recipients)
+    } else {
+        None
+    };
 
-    debug!("Old fpr <{old_fprs:?}>, replace with <{new_fprs:?}>");
+    // Entries are compared with their `.gpg-id` rather than with the old keys, so an interrupted
+    // run is finished by the next one and subdirectories keep their own keys. This also checks
+    // every key is known before the new `.gpg-id` is written.
+    let (stale, hidden) = stale_entries(config, root, &target, new_keys.as_deref())?;
+    if new_keys.is_some() {
+        write_new_fpr_file(&target.join(FPR_FILENAME), &config.keys_fpr)?;
+        report.keys_changed = true;
+    }
+    report.hidden = relative_names(root, &hidden);
+    if stale.is_empty() {
+        println!("Every entry is encrypted for its keys, nothing to re-encrypt.");
+        return Ok(report);
+    }
 
-    // Create old client using old fingerprints
-    let old_client = PGPClient::new(&config.pgp_executable, &old_fprs)?;
+    let mut clients: HashMap<Vec<String>, PGPClient> = HashMap::new();
+    for (i, filepath) in stale.iter().enumerate() {
+        let rel = filepath.strip_prefix(root).unwrap_or(filepath).display().to_string();
+        println!("[{}/{}] Re-encrypting '{rel}'", i + 1, stale.len());
+        match reencrypt(config, root, filepath, &mut clients) {
+            Ok(()) => report.reencrypted.push(rel),
+            Err(e) => report.failed.push((rel, e.to_string())),
+        }
+    }
 
-    // Create new client using new fingerprints
-    let new_client = PGPClient::new(&config.pgp_executable, &new_fprs)?;
+    Ok(report)
+}
 
-    for entry in WalkDir::new(&target) {
+/// Entries below `target` not encrypted for exactly the keys of their `.gpg-id`, and the ones
+/// hiding their recipients. `new_keys` replaces the keys of `target` when they change.
+///
+/// Hidden recipients cannot be compared, those entries are only counted as stale when the keys
+/// they are encrypted for change or when forced.
+fn stale_entries(
+    config: &InitConfig,
+    root: &Path,
+    target: &Path,
+    new_keys: Option<&[String]>,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    let mut checker = RecipientChecker::new(&config.pgp_executable);
+    let mut stale = Vec::new();
+    let mut hidden = Vec::new();
+    for entry in walk_store(target) {
         let entry = entry?;
-        if !entry.file_type().is_file() {
+        let filepath = entry.path();
+        if !entry.file_type().is_file() || filepath.extension().is_none_or(|ext| ext != "gpg") {
             continue;
         }
-
-        let filepath = entry.path();
-        let filename = filepath.file_name();
-
-        if let Some(filename) = filename {
-            if filename != FPR_FILENAME {
-                let content: secrecy::SecretBox<str> =
-                    old_client.decrypt_stdin(root, path_to_str(filepath)?)?;
-                let backup_path = backup_encrypted_file(filepath)?;
-                new_client.encrypt(content.expose_secret(), path_to_str(filepath)?)?;
-                fs::remove_file(backup_path)?;
+        let dir = filepath.parent().unwrap_or(root);
+        // The `.gpg-id` of `target` applies unless a subdirectory has its own
+        let gpg_id_path = get_dir_gpg_id_path(root, dir)?;
+        let gpg_id_dir = gpg_id_path.parent().unwrap_or(root);
+        let by_target = gpg_id_dir == target || !gpg_id_dir.starts_with(target);
+        let recipients = match new_keys {
+            Some(keys) if by_target => keys.to_vec(),
+            _ => get_dir_gpg_id_content(root, dir, &config.groups)?,
+        };
+        match checker.check(filepath, &recipients)? {
+            RecipientStatus::Match => {}
+            RecipientStatus::Unknown(id) => {
+                return Err(anyhow!("Cannot find key '{id}' to encrypt {filepath:?} for"));
+            }
+            RecipientStatus::Mismatch { .. } => stale.push(filepath.to_path_buf()),
+            RecipientStatus::Hidden if config.force || (by_target && new_keys.is_some()) => {
+                stale.push(filepath.to_path_buf())
             }
+            RecipientStatus::Hidden => hidden.push(filepath.to_path_buf()),
         }
     }
+    Ok((stale, hidden))
+}
 
-    write_new_fpr_file(&target.join(FPR_FILENAME), &config.keys_fpr)?;
+fn relative_names(root: &Path, paths: &[PathBuf]) -> Vec<String> {
+    paths.iter().map(|path| path.strip_prefix(root).unwrap_or(path).display().to_string()).collect()
+}
 
-    Ok(())
+fn reencrypt(
+    config: &InitConfig,
+    root: &Path,
+    filepath: &Path,
+    clients: &mut HashMap<Vec<String>, PGPClient>,
+) -> Result<()> {
+    let recipients =
+        get_dir_gpg_id_content(root, filepath.parent().unwrap_or(root), &config.groups)?;
+    if !clients.contains_key(&recipients) {
+        let client =
+            PGPClient::new(&config.pgp_executable, &recipients)?.with_options(&config.pgp_options);
+        clients.insert(recipients.clone(), client);
+    }
+    let client = &clients[&recipients];
+
+    let content = client.decrypt_bytes(root, path_to_str(filepath)?)?;
+    create_or_overwrite_bytes(client, filepath, content.expose_secret())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    use pretty_assertions::assert_eq;
+    use serial_test::serial;
+
+    use super::*;
+    use crate::pgp::key_management::key_gen_batch;
+    use crate::util::defer::cleanup;
+    use crate::util::test_util::{
+        clean_up_test_key, create_dir_structure, gen_unique_temp_dir, get_test_email,
+        get_test_executable, gpg_key_edit_example_batch, gpg_key_gen_example_batch, write_gpg_id,
+    };
+
+    const SECOND_EMAIL: &str = "bob@rs.pass";
+
+    fn second_key_batch() -> String {
+        format!(
+            r#"Key-Type: RSA
+Key-Length: 2048
+Subkey-Type: RSA
+Subkey-Length: 2048
+Name-Real: bob
+Name-Email: {SECOND_EMAIL}
+Expire-Date: 0
+%no-protection
+%commit
+"#
+        )
+    }
+
+    fn encrypt_hidden(executable: &str, recipient: &str, path: &Path) {
+        let _ = fs::remove_file(path);
+        let mut child = Command::new(executable)
+            .args(["--batch", "--yes", "--trust-model", "always", "--throw-keyids", "-e", "-r"])
+            .arg(recipient)
+            .arg("-o")
+            .arg(path)
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child.stdin.take().unwrap().write_all(b"hidden").unwrap();
+        assert!(child.wait().unwrap().success());
+    }
+
+    fn sorted(mut names: Vec<String>) -> Vec<String> {
+        names.sort();
+        names
+    }
 
     #[test]
-    fn init_empty_repo() {
-        // unimplemented!("fuck me")
+    #[serial]
+    #[ignore = "need run interactively"]
+    fn init_reencrypt_test() {
+        // root
+        // ├── .gpg-id
+        // ├── a.gpg
+        // ├── h.gpg        hides its recipients
+        // ├── dir
+        // │   └── b.gpg
+        // └── own
+        //     ├── .gpg-id
+        //     └── c.gpg
+        let executable = get_test_executable();
+        let email = get_test_email();
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        create_dir_structure(&root, &[(Some("dir"), &[][..]), (Some("own"), &[][..])]);
+
+        cleanup!(
+            {
+                key_gen_batch(&executable, &gpg_key_gen_example_batch()).unwrap();
+                key_gen_batch(&executable, &second_key_batch()).unwrap();
+                let client = PGPClient::new(&executable, &[&email]).unwrap();
+                client.key_edit_batch(&gpg_key_edit_example_batch()).unwrap();
+                let second = PGPClient::new(&executable, &[SECOND_EMAIL]).unwrap();
+                second.key_edit_batch(&gpg_key_edit_example_batch()).unwrap();
+                let fpr = client.get_keys_fpr()[0].to_string();
+                let second_fpr = second.get_keys_fpr()[0].to_string();
+
+                write_gpg_id(&root, &[&fpr]);
+                write_gpg_id(&root.join("own"), &[&fpr]);
+                for name in ["a", "dir/b", "own/c"] {
+                    let path = root.join(format!("{name}.gpg"));
+                    client.encrypt(name, path.to_str().unwrap()).unwrap();
+                }
+                encrypt_hidden(&executable, &fpr, &root.join("h.gpg"));
+                let config = |keys: &[&String], force| InitConfig {
+                    pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    keys_fpr: keys.iter().map(|key| key.to_string()).collect(),
+                    groups: BTreeMap::new(),
+                    force,
+                };
+
+                // Same keys, the hidden entry is left alone
+                let report = init(&config(&[&fpr], false), &root, None).unwrap();
+                assert!(report.is_empty());
+                assert_eq!(report.hidden, vec!["h.gpg"]);
+
+                // New keys, subdirectories with their own `.gpg-id` are kept
+                let report = init(&config(&[&fpr, &second_fpr], false), &root, None).unwrap();
+                assert!(report.keys_changed);
+                assert_eq!(sorted(report.reencrypted), vec!["a.gpg", "dir/b.gpg", "h.gpg"]);
+                assert!(report.failed.is_empty());
+                assert_eq!(fs::read_to_string(root.join("own/.gpg-id")).unwrap().trim(), fpr);
+                let report = init(&config(&[&fpr, &second_fpr], false), &root, None).unwrap();
+                assert!(report.is_empty());
+                assert!(report.hidden.is_empty());
+
+                // An entry left for the old keys by an interrupted run is picked up
+                fs::remove_file(root.join("a.gpg")).unwrap();
+                client.encrypt("a", root.join("a.gpg").to_str().unwrap()).unwrap();
+                let report = init(&config(&[&second_fpr, &fpr], false), &root, None).unwrap();
+                assert!(!report.keys_changed);
+                assert_eq!(report.reencrypted, vec!["a.gpg"]);
+                let decrypted = second.decrypt_stdin(&root, root.join("a.gpg").to_str().unwrap());
+                assert_eq!(decrypted.unwrap().expose_secret(), "a");
+
+                // Hidden entries are only re-encrypted when forced
+                encrypt_hidden(&executable, &fpr, &root.join("h.gpg"));
+                let report = init(&config(&[&fpr, &second_fpr], false), &root, None).unwrap();
+                assert!(report.reencrypted.is_empty());
+                assert_eq!(report.hidden, vec!["h.gpg"]);
+                let report = init(&config(&[&fpr, &second_fpr], true), &root, None).unwrap();
+                assert_eq!(report.reencrypted, vec!["h.gpg"]);
+                assert!(report.hidden.is_empty());
+
+                // An unknown key fails before the new `.gpg-id` is written
+                let gpg_id = fs::read_to_string(root.join(FPR_FILENAME)).unwrap();
+                write_gpg_id(&root.join("own"), &["nobody@rs.pass"]);
+                assert!(init(&config(&[&fpr], false), &root, None).is_err());
+                assert_eq!(fs::read_to_string(root.join(FPR_FILENAME)).unwrap(), gpg_id);
+            },
+            {
+                clean_up_test_key(&executable, &[email.as_str(), SECOND_EMAIL]).unwrap();
+            }
+        );
     }
 }
@@ -1,6 +1,7 @@
+use std::collections::BTreeMap;
 use std::fs::create_dir_all;
 use std::io::{BufRead, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use secrecy::{ExposeSecret, SecretString};
@@ -8,7 +9,8 @@ use zeroize::Zeroize;
 
 use crate::pgp::PGPClient;
 use crate::util::fs_util::{
-    create_or_overwrite, get_dir_gpg_id_content, path_attack_check, prompt_overwrite,
+    create_or_overwrite, create_or_overwrite_bytes, get_dir_gpg_id_content, path_attack_check,
+    prompt_overwrite,
 };
 use crate::{IOErr, IOErrType};
 
@@ -18,6 +20,10 @@ pub struct PasswdInsertConfig {
     pub force: bool,
     pub extension: String,
     pub pgp_executable: String,
+    /// Extra options of every encryption and decryption
+    pub pgp_options: Vec<String>,
+    /// Recipient groups of the config
+    pub groups: BTreeMap<String, Vec<String>>,
 }
 
 pub fn insert_io<I, O, E>(
@@ -33,27 +39,9 @@ where
     O: Write,
     E: Write,
 {
-    let pass_path = root.join(format!("{}.{}", pass_name, insert_cfg.extension));
-    path_attack_check(root, &pass_path)?;
-
-    // Handle the case parent folder not exist
-    if let Some(parent) = pass_path.parent() {
-        if !parent.exists() {
-            create_dir_all(parent)?;
-        }
-    } else {
-        return Err(IOErr::new(IOErrType::InvalidPath, &pass_path).into());
-    }
-
-    if pass_path.exists() && !insert_cfg.force && !prompt_overwrite(in_s, err_s, pass_name)? {
+    let Some(pass_path) = prepare_pass_path(root, pass_name, insert_cfg, in_s, err_s)? else {
         return Ok(false);
-    }
-
-    if let Some(parent) = pass_path.parent() {
-        if !parent.exists() {
-            create_dir_all(parent)?;
-        }
-    }
+    };
 
     write!(out_s, "Enter password for '{pass_name}': ")?;
     out_s.flush()?;
@@ -82,17 +70,76 @@ where
     }
 
     // Get the appropriate key fingerprints for this path
-    let keys_fpr = get_dir_gpg_id_content(root, &pass_path)?;
-    let client = PGPClient::new(&insert_cfg.pgp_executable, &keys_fpr)?;
+    let keys_fpr = get_dir_gpg_id_content(root, &pass_path, &insert_cfg.groups)?;
+    let client = PGPClient::new(&insert_cfg.pgp_executable, &keys_fpr)?
+        .with_options(&insert_cfg.pgp_options);
 
     create_or_overwrite(&client, &pass_path, &password)?;
     writeln!(out_s, "Password encrypted and saved.")?;
     Ok(true)
 }
 
+/// Encrypt `content`, e.g. a key file, as `pass_name`. Unlike [`insert_io`] it may be binary,
+/// `in_s` is only read to confirm overwriting.
+pub fn insert_bytes_io<I, O, E>(
+    root: &Path,
+    pass_name: &str,
+    content: &[u8],
+    insert_cfg: &PasswdInsertConfig,
+    in_s: &mut I,
+    out_s: &mut O,
+    err_s: &mut E,
+) -> Result<bool>
+where
+    I: Read + BufRead,
+    O: Write,
+    E: Write,
+{
+    let Some(pass_path) = prepare_pass_path(root, pass_name, insert_cfg, in_s, err_s)? else {
+        return Ok(false);
+    };
+
+    let keys_fpr = get_dir_gpg_id_content(root, &pass_path, &insert_cfg.groups)?;
+    let client = PGPClient::new(&insert_cfg.pgp_executable, &keys_fpr)?
+        .with_options(&insert_cfg.pgp_options);
+    create_or_overwrite_bytes(&client, &pass_path, content)?;
+    writeln!(out_s, "Encrypted {} bytes as '{pass_name}'.", content.len())?;
+    Ok(true)
+}
+
+/// The path of `pass_name` with its folder created, `None` if overwriting it was declined
+fn prepare_pass_path<I, E>(
+    root: &Path,
+    pass_name: &str,
+    insert_cfg: &PasswdInsertConfig,
+    in_s: &mut I,
+    err_s: &mut E,
+) -> Result<Option<PathBuf>>
+where
+    I: Read + BufRead,
+    E: Write,
+{
+    let pass_path = root.join(format!("{}.{}", pass_name, insert_cfg.extension));
+    path_attack_check(root, &pass_path)?;
+
+    // Handle the case parent folder not exist
+    if let Some(parent) = pass_path.parent() {
+        if !parent.exists() {
+            create_dir_all(parent)?;
+        }
+    } else {
+        return Err(IOErr::new(IOErrType::InvalidPath, &pass_path).into());
+    }
+
+    if pass_path.exists() && !insert_cfg.force && !prompt_overwrite(in_s, err_s, pass_name)? {
+        return Ok(None);
+    }
+    Ok(Some(pass_path))
+}
+
 #[cfg(test)]
 mod tests {
-    use std::io::BufReader;
+    use std::io::{self, BufReader};
     use std::path::PathBuf;
     use std::thread::{self, sleep};
     use std::time::Duration;
@@ -103,6 +150,7 @@ mod tests {
     use tempfile::TempDir;
 
     use super::*;
+    use crate::pgp::is_not_text;
     use crate::pgp::key_management::key_gen_batch;
     use crate::util::defer::cleanup;
     use crate::util::test_util::*;
@@ -145,6 +193,8 @@ mod tests {
                     force: false,
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let res = insert_io(
@@ -191,6 +241,8 @@ mod tests {
                     force: false,
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let res = insert_io(
@@ -234,6 +286,8 @@ mod tests {
                     force: false,
                     extension: "gpg".into(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let res = insert_io(
@@ -284,6 +338,8 @@ mod tests {
                     force: false,
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let res = insert_io(
@@ -367,6 +423,8 @@ mod tests {
                     force: false,
                     extension: "gpg".to_string(),
                     pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
                 };
 
                 let result = insert_io(
@@ -385,4 +443,50 @@ mod tests {
             }
         );
     }
+    #[test]
+    #[serial]
+    #[ignore = "need run interactively"]
+    fn binary_insert() {
+        let (executable, email, test_client, _tmp_dir, root) = setup_test_environment();
+
+        cleanup!(
+            {
+                let config = PasswdInsertConfig {
+                    echo: false,
+                    multiline: false,
+                    force: false,
+                    extension: "gpg".to_string(),
+                    pgp_executable: executable.clone(),
+                    pgp_options: Vec::new(),
+                    groups: BTreeMap::new(),
+                };
+                // Invalid UTF-8, and valid UTF-8 holding a NUL byte
+                for (name, content) in [("key", &b"\x89PNG\r\n\x1a\n\xff"[..]), ("nul", b"a\0b")] {
+                    let mut stdout = Vec::new();
+                    let res = insert_bytes_io(
+                        &root,
+                        name,
+                        content,
+                        &config,
+                        &mut BufReader::new(io::empty()),
+                        &mut stdout,
+                        &mut Vec::new(),
+                    )
+                    .unwrap();
+                    assert!(res);
+                    let expected = format!("Encrypted {} bytes as '{name}'.\n", content.len());
+                    assert_eq!(String::from_utf8(stdout).unwrap(), expected);
+
+                    let path = format!("{name}.gpg");
+                    let decrypted = test_client.decrypt_bytes(&root, &path).unwrap();
+                    assert_eq!(decrypted.expose_secret(), content);
+                    let err = test_client.decrypt_stdin(&root, &path).unwrap_err();
+                    assert!(is_not_text(&err));
+                }
+            },
+            {
+                clean_up_test_key(&executable, &[&email]).unwrap();
+            }
+        );
+    }
 }
@@ -1,10 +1,13 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
 use anyhow::Result;
 use bumpalo::Bump;
 use log::debug;
-use secrecy::SecretString;
+use secrecy::{SecretSlice, SecretString};
 
 use crate::pgp::PGPClient;
-use crate::util::fs_util::{get_dir_gpg_id_content, path_to_str};
+use crate::util::fs_util::{get_dir_gpg_id_content, path_attack_check, path_to_str};
 use crate::util::str;
 use crate::util::str::remove_lines_postfix;
 use crate::util::tree::{DirTree, TreeConfig, TreePrintConfig};
@@ -17,6 +20,8 @@ pub enum LsOrShow {
 
 pub fn ls_io(
     pgp_executable: &str,
+    pgp_options: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
     tree_cfg: &TreeConfig,
     print_cfg: &TreePrintConfig,
 ) -> Result<LsOrShow> {
@@ -49,11 +54,12 @@ pub fn ls_io(
     if full_path.is_file() {
         debug!("ls_io: '{}' is file", tree_cfg.target);
         // Get the appropriate key fingerprints for this file's path
-        let keys_fpr = get_dir_gpg_id_content(tree_cfg.root, &full_path)?;
+        let keys_fpr = get_dir_gpg_id_content(tree_cfg.root, &full_path, groups)?;
         let client = PGPClient::new(
             pgp_executable,
             &keys_fpr.iter().map(|s| s.as_str()).collect::<Vec<&str>>(),
-        )?;
+        )?
+        .with_options(pgp_options);
 
         let data = client.decrypt_stdin(tree_cfg.root, path_to_str(&full_path)?)?;
         Ok(LsOrShow::Password(data))
@@ -65,6 +71,25 @@ pub fn ls_io(
     }
 }
 
+/// Decrypt the entry `pass_name` as it is, text or binary, e.g. to write it to a file
+pub fn decrypt_entry(
+    pgp_executable: &str,
+    pgp_options: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
+    root: &Path,
+    pass_name: &str,
+) -> Result<SecretSlice<u8>> {
+    let pass_path = root.join(format!("{pass_name}.gpg"));
+    path_attack_check(root, &pass_path)?;
+    if !pass_path.is_file() {
+        return Err(IOErr::new(IOErrType::PathNotExist, &pass_path).into());
+    }
+    let keys_fpr = get_dir_gpg_id_content(root, &pass_path, groups)?;
+    PGPClient::new(pgp_executable, &keys_fpr)?
+        .with_options(pgp_options)
+        .decrypt_bytes(root, path_to_str(&pass_path)?)
+}
+
 pub fn ls_dir(tree_cfg: &TreeConfig, print_cfg: &TreePrintConfig) -> Result<String> {
     let mut full_path = tree_cfg.root.join(tree_cfg.target);
 
@@ -89,11 +114,21 @@ pub fn ls_dir(tree_cfg: &TreeConfig, print_cfg: &TreePrintConfig) -> Result<Stri
 
 #[cfg(test)]
 mod tests {
+    use std::fs;
+
     use pretty_assertions::assert_eq;
+    use secrecy::ExposeSecret;
+    use serial_test::serial;
 
     use super::*;
+    use crate::pgp::is_not_text;
+    use crate::pgp::key_management::key_gen_batch;
     use crate::util::defer::cleanup;
-    use crate::util::test_util::{create_dir_structure, gen_unique_temp_dir};
+    use crate::util::fs_util::write_private_file;
+    use crate::util::test_util::{
+        clean_up_test_key, create_dir_structure, gen_unique_temp_dir, get_test_email,
+        get_test_executable, gpg_key_edit_example_batch, gpg_key_gen_example_batch, write_gpg_id,
+    };
     use crate::util::tree::FilterType;
 
     //TODO: check interactive mode
@@ -167,4 +202,57 @@ mod tests {
             {}
         )
     }
+    #[test]
+    #[serial]
+    #[ignore = "need run interactively"]
+    fn show_binary() {
+        let executable = get_test_executable();
+        let email = get_test_email();
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+
+        cleanup!(
+            {
+                key_gen_batch(&executable, &gpg_key_gen_example_batch()).unwrap();
+                let client = PGPClient::new(&executable, &[&email]).unwrap();
+                client.key_edit_batch(&gpg_key_edit_example_batch()).unwrap();
+                write_gpg_id(&root, &client.get_keys_fpr());
+                let content = b"\x89PNG\r\n\x1a\n\0\xff";
+                client.encrypt_bytes(content, root.join("key.gpg").to_str().unwrap()).unwrap();
+
+                let tree_cfg = TreeConfig {
+                    root: &root,
+                    target: "key",
+                    filter_type: FilterType::Disable,
+                    filters: Vec::new(),
+                };
+                let print_cfg = TreePrintConfig {
+                    dir_color: None,
+                    file_color: None,
+                    symbol_color: None,
+                    tree_color: None,
+                };
+                let err =
+                    ls_io(&executable, &[], &BTreeMap::new(), &tree_cfg, &print_cfg).err().unwrap();
+                assert!(is_not_text(&err));
+
+                let decrypted =
+                    decrypt_entry(&executable, &[], &BTreeMap::new(), &root, "key").unwrap();
+                assert_eq!(decrypted.expose_secret(), content);
+                let output = root.join("key.png");
+                write_private_file(&output, decrypted.expose_secret(), false).unwrap();
+                assert_eq!(fs::read(&output).unwrap(), content);
+                #[cfg(unix)]
+                {
+                    use std::os::unix::fs::PermissionsExt;
+                    let mode = fs::metadata(&output).unwrap().permissions().mode();
+                    assert_eq!(mode & 0o777, 0o600);
+                }
+                assert!(write_private_file(&output, b"other", false).is_err());
+                assert_eq!(fs::read(&output).unwrap(), content);
+            },
+            {
+                clean_up_test_key(&executable, &[&email]).unwrap();
+            }
+        );
+    }
 }
@@ -0,0 +1,141 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use anyhow::{anyhow, Result};
+
+use crate::util::fs_util::find_executable_in_path;
+
+/// Pickers tried in order when none is configured, the first one found in PATH is used
+#[cfg(all(unix, not(target_os = "macos")))]
+const WAYLAND_PICKERS: &[&str] = &["fuzzel", "wofi", "rofi"];
+#[cfg(all(unix, not(target_os = "macos")))]
+const X11_PICKERS: &[&str] = &["rofi", "dmenu"];
+const TERMINAL_PICKERS: &[&str] = &["fzf"];
+
+/// Command line of a picker reading the choices from stdin and printing the selected one.
+/// Known pickers are switched to their dmenu mode, anything else is run as given.
+pub fn picker_command(picker: &str, prompt: &str) -> Vec<String> {
+    let mut words: Vec<String> = picker.split_whitespace().map(String::from).collect();
+    if words.len() == 1 {
+        let extra: &[&str] = match words[0].as_str() {
+            "dmenu" => &["-i", "-p", prompt],
+            "rofi" => &["-dmenu", "-i", "-p", prompt],
+            "fuzzel" => &["--dmenu", "--prompt", prompt],
+            "wofi" => &["--dmenu", "--insensitive", "--prompt", prompt],
+            "fzf" => &["--prompt", prompt],
+            _ => &[],
+        };
+        words.extend(extra.iter().map(|arg| arg.to_string()));
+    }
+    words
+}
+
+/// The configured picker, or one matching the display server. `fzf` is used without one.
+pub fn detect_picker(configured: &str) -> Result<String> {
+    if !configured.trim().is_empty() {
+        return Ok(configured.to_string());
+    }
+
+    #[allow(unused_mut)]
+    let mut candidates: Vec<&str> = Vec::new();
+    #[cfg(all(unix, not(target_os = "macos")))]
+    {
+        use std::env;
+        candidates.extend(display_pickers(
+            env::var_os("WAYLAND_DISPLAY").is_some(),
+            env::var_os("DISPLAY").is_some(),
+            env::var("XDG_SESSION_TYPE").ok().as_deref(),
+        ));
+    }
+    candidates.extend(TERMINAL_PICKERS);
+
+    candidates
+        .iter()
+        .find(|picker| find_executable_in_path(picker).is_some())
+        .map(|picker| picker.to_string())
+        .ok_or_else(|| anyhow!("No picker found in PATH, tried: {}", candidates.join(", ")))
+}
+
+/// Pickers of the display server, none on a text console. `XDG_SESSION_TYPE` is also set
+/// there, to `tty`, so only `x11` counts as X11.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn display_pickers(
+    wayland_display: bool,
+    display: bool,
+    session_type: Option<&str>,
+) -> &'static [&'static str] {
+    if wayland_display {
+        WAYLAND_PICKERS
+    } else if display || session_type == Some("x11") {
+        X11_PICKERS
+    } else {
+        &[]
+    }
+}
+
+/// Let the user pick one of `choices` with the `picker` command line, `None` when the
+/// picker was canceled or printed nothing
+pub fn pick(picker: &[String], choices: &[&str]) -> Result<Option<String>> {
+    let (executable, args) =
+        picker.split_first().ok_or_else(|| anyhow!("The picker command is empty"))?;
+    let mut child = Command::new(executable)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to run picker '{executable}': {e}"))?;
+
+    let mut child_stdin =
+        child.stdin.take().ok_or(anyhow!("Cannot get stdin for '{executable}'"))?;
+    // A picker may exit before reading everything, that is a cancel rather than an error
+    let _ = child_stdin.write_all(choices.join("\n").as_bytes());
+    drop(child_stdin);
+
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Ok(None);
+    }
+    let selected = String::from_utf8(output.stdout)?;
+    let selected = selected.trim_end_matches(['\r', '\n']);
+    Ok(if selected.is_empty() { None } else { Some(selected.to_string()) })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn picker_command_test() {
+        assert_eq!(picker_command("rofi", "pars"), vec!["rofi", "-dmenu", "-i", "-p", "pars"]);
+        assert_eq!(picker_command("fzf", "> "), vec!["fzf", "--prompt", "> "]);
+        assert_eq!(
+            picker_command("rofi -dmenu -theme x", "pars"),
+            vec!["rofi", "-dmenu", "-theme", "x"]
+        );
+        assert_eq!(detect_picker("my-picker --flag").unwrap(), "my-picker --flag");
+    }
+
+    #[test]
+    #[cfg(not(target_os = "macos"))]
+    fn display_pickers_test() {
+        assert_eq!(display_pickers(true, true, Some("wayland")), WAYLAND_PICKERS);
+        assert_eq!(display_pickers(false, true, None), X11_PICKERS);
+        assert_eq!(display_pickers(false, false, Some("x11")), X11_PICKERS);
+        assert!(display_pickers(false, false, Some("tty")).is_empty());
+        assert!(display_pickers(false, false, None).is_empty());
+    }
+
+    #[test]
+    fn pick_test() {
+        let choices = ["web/github", "mail/work", "bank"];
+        let second = picker_command("sed -n 2p", "");
+        assert_eq!(pick(&second, &choices).unwrap(), Some("mail/work".to_string()));
+
+        // Canceled or nothing chosen
+        assert_eq!(pick(&picker_command("false", ""), &choices).unwrap(), None);
+        assert_eq!(pick(&picker_command("grep nothing", ""), &choices).unwrap(), None);
+        assert!(pick(&picker_command("pars-no-such-picker", ""), &choices).is_err());
+    }
+}
@@ -1,10 +1,21 @@
+pub mod backup;
+pub mod batch;
 pub mod copy_or_rename;
+pub mod doctor;
 pub mod edit;
+pub mod export;
+pub mod extension;
 pub mod find;
+pub mod fsck;
 pub mod generate;
 pub mod git;
 pub mod grep;
+pub mod import;
 pub mod init;
 pub mod insert;
 pub mod ls_or_show;
+pub mod menu;
+pub mod public_keys;
+pub mod recipients;
 pub mod remove;
+pub mod trash;
@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use log::{debug, warn};
+
+use crate::constants::default_constants::{FPR_FILENAME, PUBLIC_KEYS_DIR};
+use crate::pgp::groups::expand_recipients;
+use crate::pgp::key_management::{
+    export_key, find_keys, import_key_file, lsign_key, read_key_file,
+};
+use crate::util::fs_util::{read_gpg_id, walk_store};
+
+const KEY_EXTENSION: &str = "asc";
+
+/// A key of `.public-keys/` that the keyring lacks
+#[derive(Debug, PartialEq, Eq)]
+pub struct BundledKey {
+    /// The `.gpg-id` line asking for it
+    pub id: String,
+    pub fpr: String,
+    pub uids: Vec<String>,
+    pub path: PathBuf,
+    /// The `.gpg-id` line is the key's full fingerprint. Otherwise the key is only matched by a
+    /// user id or short id, which anyone able to push can put on a key of their own.
+    pub pinned: bool,
+}
+
+/// What `export_public_keys` changed, fingerprints of the written and removed key files
+#[derive(Debug, Default)]
+pub struct ExportReport {
+    pub written: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl ExportReport {
+    pub fn is_empty(&self) -> bool {
+        self.written.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Write the public key of every recipient of the store to `.public-keys/<fingerprint>.asc`, so
+/// a fresh clone can encrypt for all of them, and drop the keys of former recipients. Recipients
+/// missing from the keyring keep the file they already have.
+pub fn export_public_keys(
+    pgp_executable: &str,
+    root: &Path,
+    groups: &BTreeMap<String, Vec<String>>,
+) -> Result<ExportReport> {
+    let dir = root.join(PUBLIC_KEYS_DIR);
+    let mut report = ExportReport::default();
+    let mut keep = Vec::new();
+    let bundle = bundled_keys(pgp_executable, root);
+
+    for id in store_recipients(root, groups)? {
+        let fprs: Vec<String> =
+            find_keys(pgp_executable, &id)?.into_iter().map(|(fpr, _)| fpr).collect();
+        if fprs.is_empty() {
+            warn!("'{id}' is not in the keyring, its public key is not exported");
+            keep.extend(
+                bundle
+                    .iter()
+                    .filter(|(fpr, uids, _)| matches_id(&id, fpr, uids))
+                    .map(|k| k.2.clone()),
+            );
+            continue;
+        }
+        for fpr in fprs {
+            let path = dir.join(format!("{fpr}.{KEY_EXTENSION}"));
+            let armored = export_key(pgp_executable, &fpr)?;
+            if fs::read_to_string(&path).ok().as_deref() != Some(armored.as_str()) {
+                fs::create_dir_all(&dir)?;
+                fs::write(&path, armored)?;
+                report.written.push(fpr);
+            }
+            keep.push(path);
+        }
+    }
+
+    for (fpr, _, path) in bundle {
+        if !keep.contains(&path) {
+            fs::remove_file(&path)?;
+            report.removed.push(fpr);
+        }
+    }
+    report.written.sort();
+    report.written.dedup();
+    Ok(report)
+}
+
+/// Recipients of the store missing from the keyring whose key is in `.public-keys/`. Returns
+/// nothing, without calling gpg, when the store has no such directory.
+pub fn missing_bundled_keys(
+    pgp_executable: &str,
+    root: &Path,
+    groups: &BTreeMap<String, Vec<String>>,
+) -> Result<Vec<BundledKey>> {
+    if !root.join(PUBLIC_KEYS_DIR).is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut missing = Vec::new();
+    for id in store_recipients(root, groups)? {
+        if find_keys(pgp_executable, &id)?.is_empty() {
+            missing.push(id);
+        }
+    }
+    if missing.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut found = Vec::new();
+    for (fpr, uids, path) in bundled_keys(pgp_executable, root) {
+        if let Some(id) = missing.iter().find(|id| matches_id(id, &fpr, &uids)) {
+            let pinned = is_fingerprint_of(id, &fpr);
+            found.push(BundledKey { id: id.clone(), fpr, uids, path, pinned });
+        }
+    }
+    Ok(found)
+}
+
+/// Import `keys` into the keyring and certify them locally, so gpg encrypts for them. Callers
+/// only pass keys that are pinned or whose fingerprint was confirmed by their owner.
+pub fn import_bundled_keys(pgp_executable: &str, keys: &[BundledKey]) -> Result<()> {
+    for key in keys {
+        import_key_file(pgp_executable, &key.path)?;
+        lsign_key(pgp_executable, &key.fpr)?;
+    }
+    Ok(())
+}
+
+/// Every id listed by a `.gpg-id` of the store with groups expanded, without duplicates
+fn store_recipients(root: &Path, groups: &BTreeMap<String, Vec<String>>) -> Result<Vec<String>> {
+    let mut ids: Vec<String> = Vec::new();
+    for entry in walk_store(root) {
+        let entry = entry?;
+        if entry.file_name() != FPR_FILENAME || !entry.file_type().is_file() {
+            continue;
+        }
+        for id in expand_recipients(root, &read_gpg_id(entry.path())?, groups)? {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+    Ok(ids)
+}
+
+/// Keys of the `.asc` files of `.public-keys/`, unreadable files are skipped
+fn bundled_keys(pgp_executable: &str, root: &Path) -> Vec<(String, Vec<String>, PathBuf)> {
+    let Ok(dir) = fs::read_dir(root.join(PUBLIC_KEYS_DIR)) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = dir
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == KEY_EXTENSION))
+        .collect();
+    files.sort();
+
+    let mut keys = BTreeMap::new();
+    for path in files {
+        match read_key_file(pgp_executable, &path) {
+            Ok(found) => {
+                for (fpr, uids) in found {
+                    keys.insert(fpr, (uids, path.clone()));
+                }
+            }
+            Err(e) => debug!("Skip {path:?}: {e}"),
+        }
+    }
+    keys.into_iter().map(|(fpr, (uids, path))| (fpr, uids, path)).collect()
+}
+
+/// Whether a `.gpg-id` line is the full fingerprint `fpr`
+pub fn is_fingerprint_of(id: &str, fpr: &str) -> bool {
+    let hex = id.strip_prefix("0x").unwrap_or(id).to_ascii_uppercase();
+    hex.len() >= 40 && hex == fpr
+}
+
+/// Whether a `.gpg-id` line names the key: its fingerprint, key ID, email or full user id
+fn matches_id(id: &str, fpr: &str, uids: &[String]) -> bool {
+    let hex = id.strip_prefix("0x").unwrap_or(id).to_ascii_uppercase();
+    if hex.len() >= 8 && fpr.ends_with(&hex) {
+        return true;
+    }
+    let email = id.trim_start_matches('<').trim_end_matches('>');
+    uids.iter().any(|uid| uid == id || uid.contains(&format!("<{email}>")))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::util::test_util::{create_dir_structure, gen_unique_temp_dir, write_gpg_id};
+
+    #[test]
+    fn matches_id_test() {
+        let fpr = "ED073EFEFDB997D6A00FF29FA13C6C9470745EFE";
+        let uids = vec!["E2E <e2e@test>".to_string()];
+        assert!(matches_id(fpr, fpr, &uids));
+        assert!(matches_id("0xa13c6c9470745efe", fpr, &uids));
+        assert!(matches_id("e2e@test", fpr, &uids));
+        assert!(matches_id("<e2e@test>", fpr, &uids));
+        assert!(matches_id("E2E <e2e@test>", fpr, &uids));
+        assert!(!matches_id("2e@test", fpr, &uids));
+        assert!(!matches_id("EFE", fpr, &uids));
+
+        assert!(is_fingerprint_of(fpr, fpr));
+        assert!(is_fingerprint_of(&format!("0x{}", fpr.to_lowercase()), fpr));
+        assert!(!is_fingerprint_of("0xa13c6c9470745efe", fpr));
+        assert!(!is_fingerprint_of("e2e@test", fpr));
+    }
+
+    #[test]
+    fn store_recipients_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] = &[(Some("team"), &[]), (Some(".git"), &[])];
+        create_dir_structure(&root, structure);
+        write_gpg_id(&root, &["alice@rs.pass"]);
+        write_gpg_id(&root.join("team"), &["alice@rs.pass", "bob@rs.pass"]);
+        write_gpg_id(&root.join(".git"), &["eve@rs.pass"]);
+
+        assert_eq!(
+            store_recipients(&root, &BTreeMap::new()).unwrap(),
+            vec!["alice@rs.pass", "bob@rs.pass"]
+        );
+        assert!(missing_bundled_keys("gpg", &root, &BTreeMap::new()).unwrap().is_empty());
+    }
+}
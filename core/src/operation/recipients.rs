@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+
+use crate::config::cli::ParsConfig;
+use crate::constants::default_constants::FPR_FILENAME;
+use crate::operation::init::{init, InitConfig, InitReport};
+use crate::pgp::groups::GROUP_PREFIX;
+use crate::pgp::utils::get_pgp_key_info;
+use crate::util::fs_util::{
+    get_dir_gpg_id_content, get_dir_gpg_id_lines, get_dir_gpg_id_path, read_gpg_id, walk_store,
+};
+
+/// Recipients of `target` as written, groups unexpanded, and the `.gpg-id` they are read from
+pub fn list_recipients(root: &Path, target: &Path) -> Result<(PathBuf, Vec<String>)> {
+    let gpg_id = get_dir_gpg_id_path(root, target)?;
+    let recipients = read_gpg_id(&gpg_id)?;
+    Ok((gpg_id, recipients))
+}
+
+/// Add `ids` to the recipients of `target_path` and re-encrypt the entries using them. A folder
+/// without its own `.gpg-id` gets one, starting from the recipients it inherits.
+pub fn add_recipients(
+    config: &ParsConfig,
+    root: &Path,
+    target_path: Option<&str>,
+    ids: &[String],
+) -> Result<InitReport> {
+    let pgp_executable = &config.executable_config.pgp_executable;
+    let target = root.join(target_path.unwrap_or_default());
+    let mut recipients = get_dir_gpg_id_lines(root, &target)?;
+    for id in ids {
+        if recipients.iter().any(|recipient| same_key(pgp_executable, recipient, id)) {
+            return Err(anyhow!(format!(
+                "'{id}' is already a recipient of {}",
+                label(target_path)
+            )));
+        }
+        recipients.push(id.clone());
+    }
+
+    let init_cfg = InitConfig::new(config, recipients);
+    init(&init_cfg, root, target_path)
+}
+
+/// Remove `id` from the recipients of `target_path` and re-encrypt the entries using them.
+/// Also returns the entries `id` could read until now, their passwords should be changed.
+pub fn remove_recipient(
+    config: &ParsConfig,
+    root: &Path,
+    target_path: Option<&str>,
+    id: &str,
+) -> Result<(InitReport, Vec<String>)> {
+    let pgp_executable = &config.executable_config.pgp_executable;
+    let target = root.join(target_path.unwrap_or_default());
+    let (kept, removed): (Vec<String>, Vec<String>) = get_dir_gpg_id_lines(root, &target)?
+        .into_iter()
+        .partition(|recipient| !same_key(pgp_executable, recipient, id));
+    if removed.is_empty() {
+        let through_group = get_dir_gpg_id_content(root, &target, &config.groups)?
+            .iter()
+            .any(|recipient| same_key(pgp_executable, recipient, id));
+        return Err(anyhow!(if through_group {
+            format!(
+                "'{id}' is a recipient of {} through a group, remove it from the group instead",
+                label(target_path)
+            )
+        } else {
+            format!("'{id}' is not a recipient of {}", label(target_path))
+        }));
+    }
+    if kept.is_empty() {
+        return Err(anyhow!(format!("Cannot remove the last recipient of {}", label(target_path))));
+    }
+
+    let init_cfg = InitConfig::new(config, kept);
+    let report = init(&init_cfg, root, target_path)?;
+    let exposed = governed_entries(root, &target)?;
+    Ok((report, exposed))
+}
+
+/// Entries below `target` encrypted for the recipients of its own `.gpg-id`, subfolders with
+/// their own `.gpg-id` are left out
+fn governed_entries(root: &Path, target: &Path) -> Result<Vec<String>> {
+    let gpg_id = target.join(FPR_FILENAME);
+    let mut entries = Vec::new();
+    for entry in walk_store(target) {
+        let entry = entry?;
+        let path = entry.path();
+        if !entry.file_type().is_file() || path.extension().is_none_or(|ext| ext != "gpg") {
+            continue;
+        }
+        if get_dir_gpg_id_path(root, path.parent().unwrap_or(root))? == gpg_id {
+            let name = path.strip_prefix(root).unwrap_or(path).with_extension("");
+            entries.push(name.to_string_lossy().into_owned());
+        }
+    }
+    Ok(entries)
+}
+
+fn label(target_path: Option<&str>) -> String {
+    match target_path.filter(|path| !path.is_empty()) {
+        Some(path) => format!("'{path}'"),
+        None => "the store".into(),
+    }
+}
+
+/// Whether two ids, like an email and a fingerprint, name the same key
+fn same_key(pgp_executable: &str, a: &str, b: &str) -> bool {
+    if a.eq_ignore_ascii_case(b) {
+        return true;
+    }
+    if a.starts_with(GROUP_PREFIX) || b.starts_with(GROUP_PREFIX) {
+        return false;
+    }
+    let fpr = |id: &str| get_pgp_key_info(pgp_executable, id).map(|(fpr, _, _)| fpr).ok();
+    matches!((fpr(a), fpr(b)), (Some(a), Some(b)) if a == b)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::util::test_util::{create_dir_structure, gen_unique_temp_dir, write_gpg_id};
+
+    #[test]
+    fn governed_entries_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] = &[
+            (Some("team"), &["a.gpg", "notes.txt"]),
+            (Some("team/sub"), &["b.gpg"]),
+            (Some("team/own"), &["c.gpg"]),
+        ];
+        create_dir_structure(&root, structure);
+        write_gpg_id(&root, &["alice@rs.pass"]);
+        write_gpg_id(&root.join("team"), &["alice@rs.pass", "bob@rs.pass"]);
+        write_gpg_id(&root.join("team/own"), &["alice@rs.pass"]);
+
+        let entries = governed_entries(&root, &root.join("team")).unwrap();
+        assert_eq!(entries, vec!["team/a", "team/sub/b"]);
+
+        let (gpg_id, recipients) = list_recipients(&root, &root.join("team/sub")).unwrap();
+        assert_eq!(gpg_id, root.join("team").join(FPR_FILENAME));
+        assert_eq!(recipients, vec!["alice@rs.pass", "bob@rs.pass"]);
+    }
+}
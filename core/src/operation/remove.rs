@@ -1,10 +1,11 @@
 use std::fs;
 use std::io::{self, BufRead, Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use anyhow::Result;
 use log::debug;
 
+use super::trash::move_to_trash;
 use crate::util::fs_util::path_attack_check;
 use crate::{IOErr, IOErrType};
 
@@ -38,6 +39,61 @@ pub fn remove_io<I, O, E>(
     stdout: &mut O,
     stderr: &mut E,
 ) -> Result<()>
+where
+    I: Read + BufRead,
+    O: Write,
+    E: Write,
+{
+    let Some(dist_path) = confirm_target(root, dist, recursive, force, stdin, stdout, stderr)?
+    else {
+        return Ok(());
+    };
+
+    if dist_path.is_file() {
+        fs::remove_file(&dist_path)?;
+        writeln!(stderr, "Removed '{dist}'")?;
+    } else {
+        remove_dir_recursive(&dist_path, stdout)?;
+    }
+    Ok(())
+}
+
+/// Like [`remove_io`], moving the entry or folder into `.trash/` instead of deleting it
+pub fn trash_io<I, O, E>(
+    root: &Path,
+    dist: &str,
+    recursive: bool,
+    force: bool,
+    stdin: &mut I,
+    stdout: &mut O,
+    stderr: &mut E,
+) -> Result<()>
+where
+    I: Read + BufRead,
+    O: Write,
+    E: Write,
+{
+    let Some(dist_path) = confirm_target(root, dist, recursive, force, stdin, stdout, stderr)?
+    else {
+        return Ok(());
+    };
+
+    let item = move_to_trash(root, &dist_path)?;
+    writeln!(stderr, "Moved '{dist}' to the trash, `pars trash restore {}` undoes it", item.id)?;
+    Ok(())
+}
+
+/// Resolve `dist` to the entry or folder to remove and ask for confirmation unless `force`,
+/// `None` when there is nothing to do
+fn confirm_target<I, O, E>(
+    root: &Path,
+    dist: &str,
+    recursive: bool,
+    force: bool,
+    stdin: &mut I,
+    stdout: &mut O,
+    stderr: &mut E,
+) -> Result<Option<PathBuf>>
 where
     I: Read + BufRead,
     O: Write,
@@ -52,7 +108,7 @@ where
         if !dist_path.exists() || !dist_path.is_file() {
             if force {
                 writeln!(stdout, "Noting to remove")?;
-                return Ok(());
+                return Ok(None);
             }
             debug!("Try to delete file {dist_path:?}, which not exist");
             writeln!(stderr, "Cannot remove '{dist}': No such file or directory")?;
@@ -71,28 +127,25 @@ where
         let mut input = String::new();
         stdin.read_line(&mut input)?;
         if !input.trim().to_lowercase().starts_with('y') {
-            return Ok(());
+            return Ok(None);
         }
     }
 
     if dist_path.is_file() {
-        fs::remove_file(&dist_path)?;
-        writeln!(stderr, "Removed '{dist}'")?;
+        Ok(Some(dist_path))
     } else if dist_path.is_dir() {
         if recursive {
-            remove_dir_recursive(&dist_path, stdout)?;
+            Ok(Some(dist_path))
         } else {
             let err_msg = format!("Cannot remove '{dist}': Is a directory.");
             writeln!(stderr, "{err_msg}")?;
-            return Err(IOErr::new(IOErrType::ExpectFile, &dist_path).into());
+            Err(IOErr::new(IOErrType::ExpectFile, &dist_path).into())
         }
     } else {
         let err_msg = format!("Cannot remove '{dist}': Not a file or directory.");
         writeln!(stderr, "{err_msg}")?;
-        return Err(IOErr::new(IOErrType::InvalidFileType, &dist_path).into());
+        Err(IOErr::new(IOErrType::InvalidFileType, &dist_path).into())
     }
-
-    Ok(())
 }
 
 #[cfg(test)]
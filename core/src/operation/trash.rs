@@ -0,0 +1,247 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveDateTime, SubsecRound, TimeDelta, Utc};
+use log::{debug, warn};
+
+use crate::constants::default_constants::TRASH_DIR;
+use crate::util::fs_util::{better_rename, path_attack_check, path_to_str};
+
+/// Written into every slot of the trash, holds the path of the removed entry or folder
+const NAME_FILE: &str = ".pars-trash";
+/// Slots are named after the time of the removal, in UTC
+const ID_FORMAT: &str = "%Y%m%d-%H%M%S";
+const ENTRY_EXTENSION: &str = ".gpg";
+
+/// An entry or folder removed by `pars rm`, kept in `.trash/<id>/` under its former path
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashedItem {
+    pub id: String,
+    pub deleted_at: DateTime<Utc>,
+    /// Relative to the store root, `team/db.gpg` for an entry and `team` for a folder
+    pub path: String,
+}
+
+impl TrashedItem {
+    pub fn is_dir(&self) -> bool {
+        !self.path.ends_with(ENTRY_EXTENSION)
+    }
+
+    /// The name `pars show` knew it by
+    pub fn pass_name(&self) -> &str {
+        self.path.strip_suffix(ENTRY_EXTENSION).unwrap_or(&self.path)
+    }
+}
+
+/// Move `path`, an entry or folder of the store, into a new slot of the trash. The trash is kept
+/// out of git, the removal is committed like a deletion.
+pub fn move_to_trash(root: &Path, path: &Path) -> Result<TrashedItem> {
+    let relative = path.strip_prefix(root)?;
+    if relative.starts_with(TRASH_DIR) {
+        return Err(anyhow!("{relative:?} is in the trash already, `pars trash empty` deletes it"));
+    }
+    let trash = trash_dir(root)?;
+
+    let now = Utc::now().trunc_subsecs(0);
+    let base_id = now.format(ID_FORMAT).to_string();
+    let mut id = base_id.clone();
+    let mut counter = 2;
+    while trash.join(&id).exists() {
+        id = format!("{base_id}-{counter}");
+        counter += 1;
+    }
+
+    let slot = trash.join(&id);
+    let target = slot.join(relative);
+    fs::create_dir_all(target.parent().unwrap_or(&slot))?;
+    let relative = path_to_str(relative)?.replace('\\', "/");
+    fs::write(slot.join(NAME_FILE), &relative)?;
+    better_rename(path, &target)?;
+    debug!("Moved {path:?} to {target:?}");
+    Ok(TrashedItem { id, deleted_at: now, path: relative })
+}
+
+/// Everything in the trash, oldest first
+pub fn list_trash(root: &Path) -> Result<Vec<TrashedItem>> {
+    let trash = root.join(TRASH_DIR);
+    if !trash.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut items = Vec::new();
+    for entry in fs::read_dir(&trash)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let id = entry.file_name().to_string_lossy().to_string();
+        let path = match fs::read_to_string(entry.path().join(NAME_FILE)) {
+            Ok(path) => path.trim().to_string(),
+            Err(e) => {
+                warn!("Skipping {:?} in the trash: {e}", entry.path());
+                continue;
+            }
+        };
+        match parse_id(&id) {
+            Some(deleted_at) => items.push(TrashedItem { id, deleted_at, path }),
+            None => warn!("Skipping {:?} in the trash, not named after a time", entry.path()),
+        }
+    }
+    items.sort_by(|a, b| a.deleted_at.cmp(&b.deleted_at).then_with(|| a.id.cmp(&b.id)));
+    Ok(items)
+}
+
+/// Put an item back where it was removed from. `target` is the id of a slot or the pass name,
+/// the most recent removal of that name is restored.
+pub fn restore_from_trash(root: &Path, target: &str) -> Result<TrashedItem> {
+    let items = list_trash(root)?;
+    let target = target.trim_end_matches('/');
+    let item = items
+        .iter()
+        .find(|item| item.id == target)
+        .or_else(|| items.iter().rev().find(|item| item.pass_name() == target))
+        .ok_or_else(|| anyhow!("'{target}' is not in the trash"))?;
+
+    // The name file is read from the trash, it must not move anything out of the store
+    let slot = root.join(TRASH_DIR).join(&item.id);
+    let destination = root.join(&item.path);
+    path_attack_check(root, &destination)?;
+    path_attack_check(&slot, &slot.join(&item.path))?;
+    if destination.exists() {
+        return Err(anyhow!(
+            "'{}' exists again, move it away before restoring it",
+            item.pass_name()
+        ));
+    }
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    better_rename(slot.join(&item.path), &destination)?;
+    fs::remove_dir_all(&slot)?;
+    Ok(item.clone())
+}
+
+/// Delete items removed more than `older_than_days` ago for good, all of them without it
+pub fn empty_trash(root: &Path, older_than_days: Option<u32>) -> Result<Vec<TrashedItem>> {
+    let now = Utc::now();
+    let mut purged = Vec::new();
+    for item in list_trash(root)? {
+        let keep = older_than_days
+            .is_some_and(|days| now - item.deleted_at <= TimeDelta::days(days.into()));
+        if keep {
+            continue;
+        }
+        fs::remove_dir_all(root.join(TRASH_DIR).join(&item.id))?;
+        purged.push(item);
+    }
+    Ok(purged)
+}
+
+/// Create `.trash/` with a `.gitignore` keeping it out of the repository
+fn trash_dir(root: &Path) -> Result<PathBuf> {
+    let trash = root.join(TRASH_DIR);
+    fs::create_dir_all(&trash)?;
+    let ignore = trash.join(".gitignore");
+    if !ignore.exists() {
+        fs::write(ignore, "*\n")?;
+    }
+    Ok(trash)
+}
+
+fn parse_id(id: &str) -> Option<DateTime<Utc>> {
+    let time = id.get(..15)?;
+    NaiveDateTime::parse_from_str(time, ID_FORMAT).ok().map(|time| time.and_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::util::test_util::{create_dir_structure, gen_unique_temp_dir};
+
+    #[test]
+    fn trash_round_trip_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] =
+            &[(Some("team"), &["db.gpg", "web.gpg"]), (Some("solo"), &["a.gpg"])];
+        create_dir_structure(&root, structure);
+        fs::write(root.join("team/db.gpg"), "first").unwrap();
+
+        let entry = move_to_trash(&root, &root.join("team/db.gpg")).unwrap();
+        assert_eq!(entry.pass_name(), "team/db");
+        assert!(!entry.is_dir());
+        let folder = move_to_trash(&root, &root.join("solo")).unwrap();
+        assert!(folder.is_dir());
+        assert!(!root.join("solo").exists());
+        assert_eq!(fs::read_to_string(root.join(TRASH_DIR).join(".gitignore")).unwrap(), "*\n");
+
+        fs::write(root.join("team/db.gpg"), "second").unwrap();
+        let again = move_to_trash(&root, &root.join("team/db.gpg")).unwrap();
+        assert_ne!(again.id, entry.id);
+        assert_eq!(list_trash(&root).unwrap(), vec![entry.clone(), folder.clone(), again]);
+
+        assert_eq!(restore_from_trash(&root, "team/db").unwrap().path, "team/db.gpg");
+        assert_eq!(fs::read_to_string(root.join("team/db.gpg")).unwrap(), "second");
+        let err = restore_from_trash(&root, &entry.id).unwrap_err();
+        assert_eq!(err.to_string(), "'team/db' exists again, move it away before restoring it");
+        restore_from_trash(&root, "solo/").unwrap();
+        assert!(root.join("solo/a.gpg").exists());
+        assert!(restore_from_trash(&root, "solo").is_err());
+
+        let err = move_to_trash(&root, &root.join(TRASH_DIR)).unwrap_err();
+        assert!(err.to_string().contains("in the trash already"));
+    }
+
+    #[test]
+    fn restore_outside_test() {
+        let (_tmp_dir, base) = gen_unique_temp_dir();
+        let root = base.join("store");
+        let structure: &[(Option<&str>, &[&str])] = &[(Some("store"), &["a.gpg"])];
+        create_dir_structure(&base, structure);
+        let item = move_to_trash(&root, &root.join("a.gpg")).unwrap();
+        let slot = root.join(TRASH_DIR).join(&item.id);
+        fs::write(base.join("victim.gpg"), "outside").unwrap();
+
+        for path in ["../victim.gpg", "../../../victim.gpg"] {
+            fs::write(slot.join(NAME_FILE), path).unwrap();
+            assert!(restore_from_trash(&root, &item.id).is_err());
+            assert!(slot.join("a.gpg").exists());
+        }
+        fs::write(slot.join(NAME_FILE), base.join("elsewhere.gpg").to_str().unwrap()).unwrap();
+        assert!(restore_from_trash(&root, &item.id).is_err());
+        assert!(!base.join("elsewhere.gpg").exists());
+        assert_eq!(fs::read_to_string(base.join("victim.gpg")).unwrap(), "outside");
+    }
+
+    #[test]
+    fn empty_trash_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] = &[(None, &["old.gpg", "new.gpg"])];
+        create_dir_structure(&root, structure);
+        let old = move_to_trash(&root, &root.join("old.gpg")).unwrap();
+        let new = move_to_trash(&root, &root.join("new.gpg")).unwrap();
+        fs::rename(
+            root.join(TRASH_DIR).join(&old.id),
+            root.join(TRASH_DIR).join("20200101-000000"),
+        )
+        .unwrap();
+
+        let purged = empty_trash(&root, Some(30)).unwrap();
+        assert_eq!(purged.len(), 1);
+        assert_eq!(purged[0].path, "old.gpg");
+        assert_eq!(list_trash(&root).unwrap(), vec![new]);
+
+        assert_eq!(empty_trash(&root, None).unwrap().len(), 1);
+        assert!(list_trash(&root).unwrap().is_empty());
+        assert!(root.join(TRASH_DIR).join(".gitignore").exists());
+    }
+
+    #[test]
+    fn parse_id_test() {
+        let time = parse_id("20261018-093000-2").unwrap();
+        assert_eq!(time.format("%Y-%m-%d %H:%M:%S").to_string(), "2026-10-18 09:30:00");
+        assert!(parse_id("backup").is_none());
+    }
+}
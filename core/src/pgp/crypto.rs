@@ -1,17 +1,41 @@
-use std::io::{Read, Write};
+use std::fs::File;
+use std::io::{self, Read, Write};
 use std::path::Path;
-use std::process::{Command, Stdio};
+use std::process::Stdio;
+use std::str;
 
 use anyhow::{anyhow, Result};
 use log::debug;
-use secrecy::{ExposeSecret, SecretString};
+use secrecy::{ExposeSecret, SecretSlice, SecretString};
 use zeroize::Zeroize;
 
 use super::{PGPClient, PGPErr};
 impl PGPClient {
     pub fn encrypt(&self, plaintext: &str, output_path: &str) -> Result<()> {
+        self.encrypt_to(&mut plaintext.as_bytes(), output_path, false)
+    }
+
+    /// Like [`PGPClient::encrypt`], writing ASCII armor, `-` as `output_path` prints it
+    pub fn encrypt_armored(&self, plaintext: &str, output_path: &str) -> Result<()> {
+        self.encrypt_to(&mut plaintext.as_bytes(), output_path, true)
+    }
+
+    /// Like [`PGPClient::encrypt`] for content that is not text
+    pub fn encrypt_bytes(&self, mut plaintext: &[u8], output_path: &str) -> Result<()> {
+        self.encrypt_to(&mut plaintext, output_path, false)
+    }
+
+    /// Like [`PGPClient::encrypt_bytes`], streaming `plaintext` to gpg rather than holding it
+    pub fn encrypt_reader(&self, mut plaintext: impl Read, output_path: &str) -> Result<()> {
+        self.encrypt_to(&mut plaintext, output_path, false)
+    }
+
+    fn encrypt_to(&self, plaintext: &mut dyn Read, output_path: &str, armor: bool) -> Result<()> {
         let fprs = self.get_keys_fpr();
-        let prefix = vec!["--batch", "--encrypt"];
+        let mut prefix = vec!["--batch", "--encrypt"];
+        if armor {
+            prefix.push("--armor");
+        }
         let mut args = Vec::with_capacity(prefix.len() + fprs.len() * 2 + 2);
         args.extend(prefix);
         fprs.into_iter().for_each(|fpr| {
@@ -20,15 +44,17 @@ impl PGPClient {
         });
         args.push("--output");
         args.push(output_path);
-        let mut child = Command::new(&self.executable)
+        let stdout = if output_path == "-" { Stdio::inherit() } else { Stdio::piped() };
+        let mut child = self
+            .command()
             .args(&args)
             .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
+            .stdout(stdout)
             .stderr(Stdio::piped())
             .spawn()?;
 
         if let Some(mut stdin) = child.stdin.take() {
-            stdin.write_all(plaintext.as_bytes())?;
+            io::copy(plaintext, &mut stdin)?;
         }
 
         let status = child.wait()?;
@@ -48,7 +74,18 @@ impl PGPClient {
         }
     }
 
+    /// Fails with [`PGPErr::NotText`] for binary entries, content that is not UTF-8 or holds a
+    /// NUL byte, [`PGPClient::decrypt_bytes`] reads them
     pub fn decrypt_stdin(&self, work_dir: &Path, file_path: &str) -> Result<SecretString> {
+        let content = self.decrypt_bytes(work_dir, file_path)?;
+        match str::from_utf8(content.expose_secret()) {
+            Ok(text) if !text.contains('\0') => Ok(text.into()),
+            _ => Err(PGPErr::NotText.into()),
+        }
+    }
+
+    /// Like [`PGPClient::decrypt_stdin`] without requiring the content to be text
+    pub fn decrypt_bytes(&self, work_dir: &Path, file_path: &str) -> Result<SecretSlice<u8>> {
         let mut args = Vec::with_capacity(1 + 2 * self.keys.len() + 1);
         args.push("--decrypt");
         for key in &self.keys {
@@ -56,10 +93,28 @@ impl PGPClient {
             args.push(&key.key_fpr);
         }
         args.push(file_path);
-        let output = Command::new(&self.executable).current_dir(work_dir).args(&args).output()?;
+        let output = self.command().current_dir(work_dir).args(&args).output()?;
 
         if output.status.success() {
-            Ok(String::from_utf8(output.stdout)?.into())
+            Ok(output.stdout.into())
+        } else {
+            let error_message = String::from_utf8_lossy(&output.stderr);
+            Err(anyhow!(format!("PGP decryption failed: {}", error_message)))
+        }
+    }
+
+    /// Like [`PGPClient::decrypt_bytes`], writing the content to `output` as gpg decrypts it
+    pub fn decrypt_into(&self, work_dir: &Path, file_path: &str, output: File) -> Result<()> {
+        let output = self
+            .command()
+            .current_dir(work_dir)
+            .args(["--decrypt", file_path])
+            .stdout(output)
+            .stderr(Stdio::piped())
+            .output()?;
+
+        if output.status.success() {
+            Ok(())
         } else {
             let error_message = String::from_utf8_lossy(&output.stderr);
             Err(anyhow!(format!("PGP decryption failed: {}", error_message)))
@@ -87,7 +142,8 @@ impl PGPClient {
             args.push(&key.key_fpr);
         }
         args.push(file_path);
-        let mut cmd = Command::new(&self.executable)
+        let mut cmd = self
+            .command()
             .args(&args)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
@@ -124,8 +180,8 @@ mod tests {
     use crate::pgp::key_management::key_gen_batch;
     use crate::util::defer::cleanup;
     use crate::util::test_util::{
-        clean_up_test_key, get_test_email, get_test_executable, get_test_password,
-        gpg_key_edit_example_batch, gpg_key_gen_example_batch,
+        clean_up_test_key, gen_unique_temp_dir, get_test_email, get_test_executable,
+        get_test_password, gpg_key_edit_example_batch, gpg_key_gen_example_batch,
     };
 
     #[test]
@@ -152,6 +208,30 @@ mod tests {
         );
     }
 
+    #[test]
+    #[serial]
+    fn encrypt_with_options() {
+        let executable = &get_test_executable();
+        let email = &get_test_email();
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let output_dest = root.join("options.gpg");
+        cleanup!(
+            {
+                key_gen_batch(executable, &gpg_key_gen_example_batch()).unwrap();
+                let test_client = PGPClient::new(executable, &[email])
+                    .unwrap()
+                    .with_options(&["--armor".to_string()]);
+                test_client.encrypt("secret", output_dest.to_str().unwrap()).unwrap();
+
+                let content = fs::read_to_string(&output_dest).unwrap();
+                assert!(content.starts_with("-----BEGIN PGP MESSAGE-----"), "{content}");
+            },
+            {
+                clean_up_test_key(executable, &[email]).unwrap();
+            }
+        );
+    }
+
     // #[test]
     // #[serial]
     // #[ignore = "need run interactively"]
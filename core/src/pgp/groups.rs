@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+
+use crate::constants::default_constants::GROUPS_FILENAME;
+
+/// `.gpg-id` lines starting with it name a group rather than a key
+pub const GROUP_PREFIX: char = '@';
+
+/// Groups known in the store at `root`, its `.pars-groups` wins over `config_groups`, the
+/// `groups` of the config
+pub fn load_groups(
+    root: &Path,
+    config_groups: &BTreeMap<String, Vec<String>>,
+) -> Result<BTreeMap<String, Vec<String>>> {
+    let mut groups = config_groups.clone();
+    let path = root.join(GROUPS_FILENAME);
+    if path.is_file() {
+        let store_groups: BTreeMap<String, Vec<String>> =
+            toml::from_str(&fs::read_to_string(&path)?)
+                .map_err(|e| anyhow!(format!("Failed to read {path:?}: {e}")))?;
+        groups.extend(store_groups);
+    }
+    Ok(groups)
+}
+
+/// `ids` with every `@group` replaced by its members, groups may contain groups. Duplicates are
+/// dropped, the first occurrence keeps its place.
+pub fn expand_recipients(
+    root: &Path,
+    ids: &[String],
+    config_groups: &BTreeMap<String, Vec<String>>,
+) -> Result<Vec<String>> {
+    if !ids.iter().any(|id| id.starts_with(GROUP_PREFIX)) {
+        return Ok(ids.to_vec());
+    }
+    let groups = load_groups(root, config_groups)?;
+    let mut expanded = Vec::with_capacity(ids.len());
+    for id in ids {
+        expand_into(&groups, id, &mut Vec::new(), &mut expanded)?;
+    }
+    Ok(expanded)
+}
+
+fn expand_into(
+    groups: &BTreeMap<String, Vec<String>>,
+    id: &str,
+    parents: &mut Vec<String>,
+    expanded: &mut Vec<String>,
+) -> Result<()> {
+    let Some(name) = id.strip_prefix(GROUP_PREFIX) else {
+        if !expanded.iter().any(|known| known == id) {
+            expanded.push(id.to_string());
+        }
+        return Ok(());
+    };
+    if parents.iter().any(|parent| parent == name) {
+        return Err(anyhow!(format!("Recipient group '{id}' contains itself")));
+    }
+    let members = groups.get(name).ok_or_else(|| {
+        anyhow!(format!("Unknown recipient group '{id}', define it in {GROUPS_FILENAME}"))
+    })?;
+    parents.push(name.to_string());
+    for member in members.iter().map(|member| member.trim()).filter(|member| !member.is_empty()) {
+        expand_into(groups, member, parents, expanded)?;
+    }
+    parents.pop();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+    use crate::util::test_util::gen_unique_temp_dir;
+
+    #[test]
+    fn expand_recipients_test() {
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        let config_groups = BTreeMap::from([
+            ("ops".into(), ids(&["alice@rs.pass"])),
+            ("admins".into(), ids(&["root@rs.pass"])),
+        ]);
+        fs::write(
+            root.join(GROUPS_FILENAME),
+            "ops = [\"alice@rs.pass\", \"bob@rs.pass\"]\nall = [\"@ops\", \"@admins\", \"carol@rs.pass\"]\nloop = [\"@loop\"]\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            expand_recipients(&root, &ids(&["bob@rs.pass", "@all"]), &config_groups).unwrap(),
+            vec!["bob@rs.pass", "alice@rs.pass", "root@rs.pass", "carol@rs.pass"]
+        );
+        assert_eq!(
+            expand_recipients(&root, &ids(&["@admins"]), &config_groups).unwrap(),
+            vec!["root@rs.pass"]
+        );
+        assert!(expand_recipients(&root, &ids(&["@loop"]), &config_groups).is_err());
+        assert!(expand_recipients(&root, &ids(&["@nobody"]), &config_groups).is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use std::io::Write;
+use std::path::Path;
 use std::process::{Command, Stdio};
 
 use anyhow::{anyhow, Result};
@@ -50,6 +51,96 @@ pub fn key_gen_batch(pgp_exe: &str, batch_input: &str) -> Result<()> {
     run_gpg_batched_child(pgp_exe, &gpg_args, batch_input)
 }
 
+/// Primary fingerprint and user ids of every public key in `file`, without importing them
+pub fn read_key_file(pgp_exe: &str, file: &Path) -> Result<Vec<(String, Vec<String>)>> {
+    let output = Command::new(pgp_exe)
+        .args(["--batch", "--with-colons", "--import-options", "show-only", "--import"])
+        .arg(file)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(format!(
+            "Failed to read keys from {file:?}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(parse_key_listing(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Import the public keys of `file` into the keyring, returning their fingerprints
+pub fn import_key_file(pgp_exe: &str, file: &Path) -> Result<Vec<String>> {
+    let fprs: Vec<String> = read_key_file(pgp_exe, file)?.into_iter().map(|(fpr, _)| fpr).collect();
+    if fprs.is_empty() {
+        return Err(anyhow!(format!("No public key in {file:?}")));
+    }
+
+    let output = Command::new(pgp_exe).args(["--batch", "--import"]).arg(file).output()?;
+    if !output.status.success() {
+        return Err(anyhow!(format!(
+            "Failed to import {file:?}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(fprs)
+}
+
+/// Primary fingerprint and user ids of the keys of the keyring matching `id`, empty when none
+pub fn find_keys(pgp_exe: &str, id: &str) -> Result<Vec<(String, Vec<String>)>> {
+    let output = Command::new(pgp_exe).args(["--list-keys", "--with-colons", id]).output()?;
+    if !output.status.success() {
+        return Ok(Vec::new());
+    }
+    Ok(parse_key_listing(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Armored public key of `fpr`
+pub fn export_key(pgp_exe: &str, fpr: &str) -> Result<String> {
+    let output = Command::new(pgp_exe).args(["--batch", "--armor", "--export", fpr]).output()?;
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(anyhow!(format!("Failed to export key {fpr}")));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// Certify `fpr` with a local, non exportable signature, so gpg accepts to encrypt for it
+pub fn lsign_key(pgp_exe: &str, fpr: &str) -> Result<()> {
+    let status = Command::new(pgp_exe)
+        .args(["--batch", "--yes", "--quick-lsign-key", fpr])
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::null())
+        .stderr(Stdio::inherit())
+        .status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(anyhow!(format!("Failed to sign key {fpr}, code {:?}", status)))
+    }
+}
+
+/// Fingerprint and user ids of the primary keys in `--with-colons` output, subkeys are skipped
+fn parse_key_listing(colons: &str) -> Vec<(String, Vec<String>)> {
+    let mut keys: Vec<(String, Vec<String>)> = Vec::new();
+    let mut after_pub = false;
+    for line in colons.lines() {
+        let fields: Vec<&str> = line.split(':').collect();
+        match fields[0] {
+            "pub" | "sec" => after_pub = true,
+            "fpr" if after_pub => {
+                if let Some(fpr) = fields.get(9) {
+                    keys.push((fpr.to_string(), Vec::new()));
+                }
+                after_pub = false;
+            }
+            "uid" => {
+                if let (Some((_, uids)), Some(uid)) = (keys.last_mut(), fields.get(9)) {
+                    uids.push(uid.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    keys
+}
+
 impl PGPClient {
     pub fn key_edit_batch(&self, batch_input: &str) -> Result<()> {
         for key in &self.keys {
@@ -107,4 +198,20 @@ mod tests {
         pgp_client.key_edit_batch(&gpg_key_edit_example_batch()).unwrap();
         clean_up_test_key(pgp_client.get_executable(), &[&email]).unwrap();
     }
+
+    #[test]
+    fn key_listing_test() {
+        let colons = "pub:-:3072:1:A13C6C9470745EFE:1700000000:::-:::scESC::::::23::0:\n\
+                      fpr:::::::::ED073EFEFDB997D6A00FF29FA13C6C9470745EFE:\n\
+                      uid:-::::1700000000::AAAA::E2E <e2e@test>::::::::::0:\n\
+                      sub:-:3072:1:166014C5622F9F04:1700000000:::::e::::::23:\n\
+                      fpr:::::::::BBBB0000BBBB0000BBBB0000166014C5622F9F04:\n";
+        assert_eq!(
+            parse_key_listing(colons),
+            vec![(
+                "ED073EFEFDB997D6A00FF29FA13C6C9470745EFE".to_string(),
+                vec!["E2E <e2e@test>".to_string()]
+            )]
+        );
+    }
 }
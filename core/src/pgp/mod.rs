@@ -1,10 +1,13 @@
 #[allow(dead_code)]
 pub mod crypto;
+pub mod groups;
 pub mod key_management;
+pub mod recipients;
 pub mod utils;
 
 use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::process::Command;
 
 #[derive(Debug)]
 pub struct PGPKey {
@@ -15,9 +18,19 @@ pub struct PGPKey {
 
 pub struct PGPClient {
     executable: String,
+    /// Extra options of every encryption and decryption, like `PASSWORD_STORE_GPG_OPTS`
+    options: Vec<String>,
     keys: Vec<PGPKey>,
 }
 
+impl PGPClient {
+    fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.executable);
+        cmd.args(&self.options);
+        cmd
+    }
+}
+
 #[derive(Debug)]
 pub enum PGPErr {
     NoneFingerprint,
@@ -26,6 +39,8 @@ pub enum PGPErr {
     CannotTakeStdin,
     CannotTakeStdout,
     CannotTakeStderr,
+    /// The decrypted content is binary, like a key file
+    NotText,
 }
 
 impl Display for PGPErr {
@@ -39,8 +54,14 @@ impl Display for PGPErr {
             CannotTakeStdin => write!(f, "Cannot take child's stdin"),
             CannotTakeStdout => write!(f, "Cannot take child's stdout"),
             CannotTakeStderr => write!(f, "Cannot take child's stderr"),
+            NotText => write!(f, "The entry is binary, not text"),
         }
     }
 }
 
 impl Error for PGPErr {}
+
+/// Whether `err` is [`PGPErr::NotText`], returned when decrypting a binary entry as text
+pub fn is_not_text(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref::<PGPErr>(), Some(PGPErr::NotText))
+}
@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::Result;
+
+use super::utils::{get_encryption_key_ids, get_recipient_key_ids};
+
+/// Key ID gpg writes in place of a recipient hidden with `--throw-keyids`
+const HIDDEN_KEY_ID: &str = "0000000000000000";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RecipientStatus {
+    /// Encrypted to exactly the expected keys
+    Match,
+    /// `missing` lists the expected recipients that cannot read the file, `extra` the key IDs
+    /// that can but are not expected
+    Mismatch { missing: Vec<String>, extra: Vec<String> },
+    /// The file hides its recipients, nothing can be told
+    Hidden,
+    /// An expected recipient is not in the keyring
+    Unknown(String),
+}
+
+/// Compares who files are encrypted to with the recipients they should be encrypted to, reading
+/// only the packet headers. The keys of each recipient are looked up once.
+pub struct RecipientChecker<'a> {
+    executable: &'a str,
+    key_ids: HashMap<String, Result<Vec<String>, String>>,
+}
+
+impl<'a> RecipientChecker<'a> {
+    pub fn new(executable: &'a str) -> Self {
+        Self { executable, key_ids: HashMap::new() }
+    }
+
+    pub fn executable(&self) -> &str {
+        self.executable
+    }
+
+    /// Errors only when the headers of `file` cannot be read
+    pub fn check(&mut self, file: &Path, recipients: &[String]) -> Result<RecipientStatus> {
+        let actual = get_recipient_key_ids(self.executable, file)?;
+        if actual.iter().any(|id| id == HIDDEN_KEY_ID) {
+            return Ok(RecipientStatus::Hidden);
+        }
+
+        let mut expected = Vec::with_capacity(recipients.len());
+        for id in recipients {
+            let executable = self.executable;
+            let ids = self.key_ids.entry(id.clone()).or_insert_with(|| {
+                get_encryption_key_ids(executable, id).map_err(|e| e.to_string())
+            });
+            match ids {
+                Ok(ids) => expected.push((id, ids.clone())),
+                Err(_) => return Ok(RecipientStatus::Unknown(id.clone())),
+            }
+        }
+
+        let missing: Vec<String> = expected
+            .iter()
+            .filter(|(_, ids)| !ids.iter().any(|key_id| actual.contains(key_id)))
+            .map(|(id, _)| id.to_string())
+            .collect();
+        let extra: Vec<String> = actual
+            .into_iter()
+            .filter(|key_id| !expected.iter().any(|(_, ids)| ids.contains(key_id)))
+            .collect();
+        if missing.is_empty() && extra.is_empty() {
+            Ok(RecipientStatus::Match)
+        } else {
+            Ok(RecipientStatus::Mismatch { missing, extra })
+        }
+    }
+
+    /// Recipients met so far that are not in the keyring, with the reason
+    pub fn unknown_recipients(&self) -> Vec<(String, String)> {
+        let mut unknown: Vec<(String, String)> = self
+            .key_ids
+            .iter()
+            .filter_map(|(id, ids)| ids.as_ref().err().map(|e| (id.clone(), e.clone())))
+            .collect();
+        unknown.sort();
+        unknown
+    }
+}
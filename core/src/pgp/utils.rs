@@ -1,5 +1,6 @@
 use std::io::Read;
-use std::process::{Child, Command};
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
 
 use anyhow::{anyhow, Result};
 use log::debug;
@@ -56,6 +57,120 @@ pub(crate) fn get_pgp_key_info<S: AsRef<str>, T: AsRef<str>>(
     Err(anyhow!(format!("No userinfo found for {}", identifier.as_ref())))
 }
 
+/// Check that `identifier` names a key of the keyring that can be encrypted to
+pub fn check_pgp_key<S: AsRef<str>, T: AsRef<str>>(executable: S, identifier: T) -> Result<()> {
+    let output = Command::new(executable.as_ref())
+        .args(["--list-keys", "--with-colons", identifier.as_ref()])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!("not in the keyring"));
+    }
+    parse_key_usability(&String::from_utf8(output.stdout)?)
+}
+
+/// Whether the secret part of `identifier` is available, needed to decrypt
+pub fn has_secret_key<S: AsRef<str>, T: AsRef<str>>(executable: S, identifier: T) -> bool {
+    Command::new(executable.as_ref())
+        .args(["--list-secret-keys", "--with-colons", identifier.as_ref()])
+        .output()
+        .is_ok_and(|output| output.status.success() && !output.stdout.is_empty())
+}
+
+/// Key IDs of the keys and subkeys `identifier` can be encrypted to, expired ones included
+pub fn get_encryption_key_ids<S: AsRef<str>, T: AsRef<str>>(
+    executable: S,
+    identifier: T,
+) -> Result<Vec<String>> {
+    let output = Command::new(executable.as_ref())
+        .args(["--list-keys", "--with-colons", identifier.as_ref()])
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(format!("Cannot find key '{}'", identifier.as_ref())));
+    }
+    Ok(parse_encryption_key_ids(&String::from_utf8(output.stdout)?))
+}
+
+/// Whether `file` decrypts to its end, with the extra `options` of decryptions. A truncated file
+/// can leave gpg nothing to decrypt, which it does not report as a failure, so its status has
+/// to confirm the decryption.
+pub fn decrypts_intact<S: AsRef<str>>(
+    executable: S,
+    options: &[String],
+    work_dir: &Path,
+    file: &Path,
+) -> bool {
+    Command::new(executable.as_ref())
+        .args(options)
+        .current_dir(work_dir)
+        .args(["--status-fd", "2", "--decrypt"])
+        .arg(file)
+        .stdout(Stdio::null())
+        .output()
+        .is_ok_and(|output| {
+            output.status.success()
+                && String::from_utf8_lossy(&output.stderr)
+                    .lines()
+                    .any(|line| line == "[GNUPG:] DECRYPTION_OKAY")
+        })
+}
+
+/// Key IDs a file is encrypted to, read from its packet headers without decrypting it.
+/// Recipients hidden with `--throw-keyids` show up as `0000000000000000`.
+pub fn get_recipient_key_ids<S: AsRef<str>>(executable: S, file: &Path) -> Result<Vec<String>> {
+    let output = Command::new(executable.as_ref())
+        .args(["--batch", "--list-only", "--list-packets"])
+        .arg(file)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow!(format!(
+            "Failed to read packets of {file:?}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(parse_recipient_key_ids(&String::from_utf8_lossy(&output.stdout)))
+}
+
+fn parse_encryption_key_ids(colons: &str) -> Vec<String> {
+    colons
+        .lines()
+        .filter(|line| line.starts_with("pub:") || line.starts_with("sub:"))
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        // Lower case letters are the capabilities of this very key
+        .filter(|fields| fields.get(11).is_some_and(|caps| caps.contains('e')))
+        .filter_map(|fields| fields.get(4).map(|id| id.to_uppercase()))
+        .collect()
+}
+
+fn parse_recipient_key_ids(packets: &str) -> Vec<String> {
+    packets
+        .lines()
+        .filter(|line| line.starts_with(":pubkey enc packet:"))
+        .filter_map(|line| line.split_once("keyid "))
+        .filter_map(|(_, rest)| rest.split_whitespace().next())
+        .map(str::to_uppercase)
+        .collect()
+}
+
+/// An identifier like an email may match several keys, one usable key is enough
+fn parse_key_usability(colons: &str) -> Result<()> {
+    let mut problem = anyhow!("not in the keyring");
+    for line in colons.lines().filter(|line| line.starts_with("pub:")) {
+        let fields: Vec<&str> = line.split(':').collect();
+        let validity = fields.get(1).copied().unwrap_or_default();
+        let capabilities = fields.get(11).copied().unwrap_or_default();
+        problem = match validity {
+            "e" => anyhow!("expired"),
+            "r" => anyhow!("revoked"),
+            "i" => anyhow!("invalid"),
+            _ if capabilities.contains('D') => anyhow!("disabled"),
+            // Upper case letters are the capabilities of the key together with its subkeys
+            _ if !capabilities.contains('E') => anyhow!("cannot encrypt"),
+            _ => return Ok(()),
+        };
+    }
+    Err(problem)
+}
+
 pub(super) fn wait_child_process(cmd: &mut Child) -> Result<()> {
     let status = cmd.wait()?;
     if status.success() {
@@ -85,12 +200,21 @@ macro_rules! get_keys_field {
 
 impl PGPClient {
     pub fn new<S: AsRef<str>>(executable: S, infos: &[impl AsRef<str>]) -> Result<Self> {
-        let mut gpg_client =
-            PGPClient { executable: executable.as_ref().to_string(), keys: Vec::new() };
+        let mut gpg_client = PGPClient {
+            executable: executable.as_ref().to_string(),
+            options: Vec::new(),
+            keys: Vec::new(),
+        };
         gpg_client.update_info(infos)?;
         Ok(gpg_client)
     }
 
+    /// Pass `options` to every encryption and decryption, `executable_config.pgp_options`
+    pub fn with_options(mut self, options: &[String]) -> Self {
+        self.options = options.to_vec();
+        self
+    }
+
     pub fn get_executable(&self) -> &str {
         &self.executable
     }
@@ -117,3 +241,40 @@ impl PGPClient {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_usability_test() {
+        let usable = "pub:u:255:22:AAAA:1700000000:::u:::scESC::::::23::0:\n";
+        let expired = "pub:e:255:22:BBBB:1600000000:1650000000::u:::sc::::::23::0:\n";
+        let sign_only = "pub:u:255:22:CCCC:1700000000:::u:::scSC::::::23::0:\n";
+        let disabled = "pub:u:255:22:DDDD:1700000000:::u:::scESCD::::::23::0:\n";
+
+        assert!(parse_key_usability(usable).is_ok());
+        assert_eq!(parse_key_usability(expired).unwrap_err().to_string(), "expired");
+        assert_eq!(parse_key_usability(sign_only).unwrap_err().to_string(), "cannot encrypt");
+        assert_eq!(parse_key_usability(disabled).unwrap_err().to_string(), "disabled");
+        assert!(parse_key_usability(&format!("{expired}{usable}")).is_ok());
+        assert_eq!(parse_key_usability("").unwrap_err().to_string(), "not in the keyring");
+    }
+
+    #[test]
+    fn key_ids_test() {
+        let colons = "tru::1:1700000000:0:3:1:5\n\
+                      pub:u:3072:1:A13C6C9470745EFE:1700000000:::u:::scESC::::::23::0:\n\
+                      fpr:::::::::AAAAAAAAAAAAAAAAAAAAAAAAA13C6C9470745EFE:\n\
+                      sub:u:3072:1:166014c5622f9f04:1700000000::::::e::::::23:\n\
+                      sub:u:3072:1:0123456789ABCDEF:1700000000::::::s::::::23:\n";
+        assert_eq!(parse_encryption_key_ids(colons), vec!["166014C5622F9F04"]);
+
+        let packets = "# off=0 ctb=85 tag=1 hlen=3 plen=396\n\
+                       :pubkey enc packet: version 3, algo 1, keyid 166014C5622F9F04\n\
+                       \tdata: [3069 bits]\n\
+                       :pubkey enc packet: version 3, algo 18, keyid 0000000000000000\n\
+                       :encrypted data packet:\n";
+        assert_eq!(parse_recipient_key_ids(packets), vec!["166014C5622F9F04", "0000000000000000"]);
+    }
+}
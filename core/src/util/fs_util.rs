@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::io::{BufRead, Read, Write};
 #[cfg(unix)]
 use std::os::unix::fs::symlink;
@@ -12,9 +13,12 @@ use directories::ProjectDirs;
 use fs_extra::dir::{self, CopyOptions};
 use log::debug;
 use secrecy::{ExposeSecret, SecretBox};
+use walkdir::{DirEntry, WalkDir};
 
-use crate::constants::default_constants::BACKUP_EXTENSION;
+use crate::constants::default_constants::{BACKUP_EXTENSION, FPR_FILENAME};
+use crate::pgp::groups::expand_recipients;
 use crate::pgp::PGPClient;
+use crate::util::tree::HIDDEN_DIRS;
 use crate::{IOErr, IOErrType};
 
 pub fn find_executable_in_path(executable: &str) -> Option<PathBuf> {
@@ -22,7 +26,7 @@ pub fn find_executable_in_path(executable: &str) -> Option<PathBuf> {
         for path in env::split_paths(&paths) {
             let full_path = path.join(executable);
 
-            if is_executable(&full_path).is_ok() {
+            if is_executable(&full_path).unwrap_or(false) {
                 return Some(full_path);
             }
         }
@@ -58,55 +62,66 @@ pub fn copy_dir_recursive<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> Res
     Ok(())
 }
 
+/// Everything in the store below `root`, without the directories holding no passwords
+pub(crate) fn walk_store(root: &Path) -> impl Iterator<Item = walkdir::Result<DirEntry>> {
+    WalkDir::new(root).follow_links(true).sort_by_file_name().into_iter().filter_entry(|entry| {
+        entry.depth() == 0
+            || !(entry.file_type().is_dir()
+                && HIDDEN_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+    })
+}
+
 pub fn get_home_dir() -> PathBuf {
     dirs::home_dir().unwrap_or(PathBuf::from("~"))
 }
 
-pub fn get_dir_gpg_id_content(root: &Path, cur_dir: &Path) -> Result<Vec<String>> {
+/// Recipients applying to `cur_dir`, with `@group` lines expanded to their members, `groups` are
+/// those of the config
+pub fn get_dir_gpg_id_content(
+    root: &Path,
+    cur_dir: &Path,
+    groups: &BTreeMap<String, Vec<String>>,
+) -> Result<Vec<String>> {
+    expand_recipients(root, &get_dir_gpg_id_lines(root, cur_dir)?, groups)
+}
+
+/// Lines of the `.gpg-id` applying to `cur_dir` as written, groups included
+pub fn get_dir_gpg_id_lines(root: &Path, cur_dir: &Path) -> Result<Vec<String>> {
+    let key_file = get_dir_gpg_id_path(root, cur_dir)?;
+    read_gpg_id(&key_file)
+}
+
+/// Non empty lines of the `.gpg-id` at `path`
+pub fn read_gpg_id(path: &Path) -> Result<Vec<String>> {
+    let key = fs::read_to_string(path)?;
+    debug!("Found key(s): {key:?}");
+    Ok(key
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect())
+}
+
+/// The `.gpg-id` applying to `cur_dir`, the nearest one between it and `root`
+pub fn get_dir_gpg_id_path(root: &Path, cur_dir: &Path) -> Result<PathBuf> {
     path_attack_check(root, cur_dir)?;
-    let mut to_check = cur_dir.to_path_buf();
-
-    while to_check != root {
-        if to_check.is_dir() {
-            let key_file = to_check.join(".gpg-id");
-            debug!("Check {key_file:?} for .gpg-id file");
-
-            if key_file.exists() && key_file.is_file() {
-                if let Ok(key) = fs::read_to_string(key_file) {
-                    debug!("Found key(s): {key:?}");
-
-                    return Ok(key
-                        .lines()
-                        .map(|line| line.trim())
-                        .filter(|line| !line.is_empty())
-                        .map(|line| line.to_string())
-                        .collect());
-                }
-            }
+    let mut to_check = cur_dir;
+
+    loop {
+        let key_file = to_check.join(FPR_FILENAME);
+        debug!("Check {key_file:?} for .gpg-id file");
+        if key_file.is_file() {
+            return Ok(key_file);
+        }
+        if to_check == root {
+            break;
         }
         match to_check.parent() {
-            Some(parent) => {
-                to_check = parent.to_path_buf();
-            }
+            Some(parent) => to_check = parent,
             None => break,
         }
     }
-
-    if root.is_dir() {
-        let key_file = root.join(".gpg-id");
-        debug!("Checking root {root:?} for .gpg-id file");
-        if key_file.exists() && key_file.is_file() {
-            if let Ok(key) = fs::read_to_string(key_file) {
-                debug!("Found key: {key:?}");
-                return Ok(key
-                    .split('\n')
-                    .map(|line| line.trim())
-                    .filter(|line| !line.is_empty())
-                    .map(|line| line.to_string())
-                    .collect());
-            }
-        }
-    }
     Err(anyhow!(format!("Cannot find '.gpg-id' for {:?}", cur_dir)))
 }
 
@@ -134,7 +149,7 @@ pub(crate) fn restore_backup_file(file_path: &Path) -> Result<()> {
     Err(anyhow!("File does not has extension"))
 }
 
-fn is_executable(path: &Path) -> Result<bool> {
+pub(crate) fn is_executable(path: &Path) -> Result<bool> {
     if path.is_file() {
         #[cfg(unix)]
         {
@@ -177,6 +192,49 @@ pub fn create_symlink<P: AsRef<Path>>(original: P, link: P) -> Result<()> {
     }
 }
 
+/// Set the umask of the process from an octal string like `077`, a no-op off unix
+pub fn set_umask(umask: &str) -> Result<()> {
+    let mask = u32::from_str_radix(umask, 8)
+        .ok()
+        .filter(|mask| *mask <= 0o777)
+        .ok_or_else(|| anyhow!("Invalid umask '{umask}'"))?;
+
+    #[cfg(unix)]
+    // SAFETY: umask only swaps the mask of the process and cannot fail
+    unsafe {
+        libc::umask(mask as libc::mode_t);
+    }
+    #[cfg(not(unix))]
+    let _ = mask;
+    Ok(())
+}
+
+/// Write `content` to a new file only the owner can read, an existing file is replaced only
+/// with `force`
+pub fn write_private_file(path: &Path, content: &[u8], force: bool) -> Result<()> {
+    create_private_file(path, force)?.write_all(content)?;
+    Ok(())
+}
+
+/// Create a new file only the owner can read, like [`write_private_file`] for content written
+/// piece by piece
+pub fn create_private_file(path: &Path, force: bool) -> Result<fs::File> {
+    if force && path.is_file() {
+        fs::remove_file(path)?;
+    }
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path).map_err(|e| match e.kind() {
+        io::ErrorKind::AlreadyExists => anyhow!(format!("{path:?} already exists")),
+        _ => e.into(),
+    })
+}
+
 pub fn path_to_str(path: &Path) -> Result<&str> {
     Ok(path.to_str().ok_or_else(|| IOErr::new(IOErrType::InvalidPath, path))?)
 }
@@ -226,10 +284,19 @@ pub fn create_or_overwrite(
     client: &PGPClient,
     pass_path: &Path,
     password: &SecretBox<str>,
+) -> Result<()> {
+    create_or_overwrite_bytes(client, pass_path, password.expose_secret().as_bytes())
+}
+
+/// Like [`create_or_overwrite`] for any content, a binary entry included
+pub fn create_or_overwrite_bytes(
+    client: &PGPClient,
+    pass_path: &Path,
+    content: &[u8],
 ) -> Result<()> {
     if pass_path.exists() {
         let backup = backup_encrypted_file(pass_path)?;
-        match client.encrypt(password.expose_secret(), path_to_str(pass_path)?) {
+        match client.encrypt_bytes(content, path_to_str(pass_path)?) {
             Ok(_) => {
                 fs::remove_file(&backup)?;
                 Ok(())
@@ -240,7 +307,7 @@ pub fn create_or_overwrite(
             }
         }
     } else {
-        client.encrypt(password.expose_secret(), path_to_str(pass_path)?)?;
+        client.encrypt_bytes(content, path_to_str(pass_path)?)?;
         Ok(())
     }
 }
@@ -256,3 +323,17 @@ pub fn default_config_path() -> String {
         "~/.config/pars/config.toml".into()
     }
 }
+
+/// Config shared by every user of the machine, applied before the user's one
+pub fn system_config_path() -> PathBuf {
+    #[cfg(windows)]
+    {
+        let program_data = env::var("PROGRAMDATA").unwrap_or_else(|_| "C:\\ProgramData".into());
+        PathBuf::from(program_data).join("pars").join("config.toml")
+    }
+
+    #[cfg(not(windows))]
+    {
+        PathBuf::from("/etc/pars/config.toml")
+    }
+}
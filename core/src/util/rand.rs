@@ -3,3 +3,9 @@ use rand::{self, distr, Rng};
 pub(crate) fn rand_alphabet_string(length: usize) -> String {
     rand::rng().sample_iter(&distr::Alphanumeric).take(length).map(char::from).collect()
 }
+
+/// Random string of `length` characters picked from `charset`, which must not be empty
+pub(crate) fn rand_string_from(charset: &[char], length: usize) -> String {
+    let mut rng = rand::rng();
+    (0..length).map(|_| charset[rng.random_range(0..charset.len())]).collect()
+}
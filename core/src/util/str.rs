@@ -15,6 +15,33 @@ pub fn remove_lines_postfix(src: &str, postfix: &str) -> String {
     result
 }
 
+/// `key: value` lines following the password line of an entry, in order
+pub fn entry_fields(content: &str) -> Vec<(&str, &str)> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .filter(|(key, _)| !key.is_empty() && !key.contains(char::is_whitespace))
+        .collect()
+}
+
+/// Levenshtein distance between `a` and `b`, counted in chars
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cur = row[j + 1];
+            row[j + 1] = if ca == *cb { prev } else { 1 + prev.min(cur).min(row[j]) };
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
 #[cfg(windows)]
 pub(crate) fn fit_to_powershell(original_str: &str) -> String {
     let mut result = String::with_capacity(original_str.len());
@@ -49,3 +76,26 @@ pub(crate) fn fit_to_unix(original_str: &str) -> String {
     }
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn entry_fields_test() {
+        let content =
+            "p@ss: word\nlogin: alice\nurl: https://example.com\nsome note\n: empty\nbad key: x";
+        assert_eq!(entry_fields(content), vec![("login", "alice"), ("url", "https://example.com")]);
+        assert!(entry_fields("only a password").is_empty());
+    }
+
+    #[test]
+    fn edit_distance_test() {
+        assert_eq!(edit_distance("clip_time", "clip_time"), 0);
+        assert_eq!(edit_distance("clip_tme", "clip_time"), 1);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
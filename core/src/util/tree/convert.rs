@@ -9,7 +9,8 @@ use bumpalo::Bump;
 use log::debug;
 use regex::Regex;
 
-use super::{DirTree, FilterType, TreeConfig, TreeNode};
+use super::{DirTree, FilterType, TreeConfig, TreeNode, HIDDEN_DIRS};
+use crate::constants::default_constants::REPO_CONFIG_FILENAME;
 use crate::util::fs_util::{filename_to_str, path_to_str};
 
 impl<'a> DirTree<'a> {
@@ -105,11 +106,15 @@ impl<'a> DirTree<'a> {
                 let entry_type = entry.file_type()?;
                 let entry_name = filename_to_str(&entry.path())?.to_string();
 
-                let is_git_dir = &entry_name == ".git" && entry_type.is_dir();
+                let is_hidden_dir =
+                    HIDDEN_DIRS.contains(&entry_name.as_str()) && entry_type.is_dir();
                 let is_dot_gpg_id = &entry_name == ".gpg-id" && entry_type.is_file();
+                let is_repo_config = entry_name == REPO_CONFIG_FILENAME
+                    && entry_type.is_file()
+                    && entry.path().parent() == Some(config.root);
                 let match_blacklist = config.filter_type == FilterType::Exclude
                     && Self::filter_match(&config.filters, filename_to_str(&entry.path())?);
-                if is_git_dir || is_dot_gpg_id || match_blacklist {
+                if is_hidden_dir || is_dot_gpg_id || is_repo_config || match_blacklist {
                     stack.push_back((parent_idx, entry_iter));
                     continue;
                 }
@@ -7,7 +7,12 @@ use bumpalo::collections::Vec as BumpVec;
 use colored::Color;
 use regex::Regex;
 
+pub use self::print::{attach_trees, join_trees, tree_entries};
 use crate::config;
+use crate::constants::default_constants::{PUBLIC_KEYS_DIR, TRASH_DIR};
+
+/// Directories of the store that hold no passwords
+pub(crate) const HIDDEN_DIRS: &[&str] = &[".git", ".extensions", PUBLIC_KEYS_DIR, TRASH_DIR];
 
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub enum FilterType {
@@ -62,6 +67,15 @@ pub struct TreeNode {
     pub visible: bool,
 }
 
+/// A node of a [`DirTree`] flattened in display order
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TreeEntry {
+    pub depth: usize,
+    /// Path relative to the tree root, `/` separated, without the `.gpg` extension
+    pub path: String,
+    pub is_dir: bool,
+}
+
 pub struct DirTree<'a> {
     pub map: BumpVec<'a, TreeNode>,
     pub root: usize,
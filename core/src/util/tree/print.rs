@@ -1,10 +1,11 @@
 use std::collections::VecDeque;
 
 use anyhow::Result;
+use bumpalo::Bump;
 use colored::Colorize;
 use log::debug;
 
-use super::{DirTree, NodeType, TreePrintConfig};
+use super::{DirTree, NodeType, TreeConfig, TreeEntry, TreePrintConfig};
 
 impl DirTree<'_> {
     pub fn print_tree(&self, config: &TreePrintConfig) -> Result<String> {
@@ -102,6 +103,91 @@ impl DirTree<'_> {
     }
 }
 
+impl DirTree<'_> {
+    /// Visible directories and `.gpg` entries in the order `print_tree` shows them
+    pub fn entries(&self) -> Vec<TreeEntry> {
+        let mut entries = Vec::new();
+        let mut stack: Vec<(usize, usize, String)> =
+            self.map[self.root].children.iter().rev().map(|idx| (*idx, 0, String::new())).collect();
+        while let Some((idx, depth, prefix)) = stack.pop() {
+            let node = &self.map[idx];
+            if !node.visible {
+                continue;
+            }
+            let is_dir = matches!(node.node_type, NodeType::Dir) || !node.children.is_empty();
+            let name = match node.name.strip_suffix(".gpg") {
+                Some(name) if !is_dir => name,
+                _ if !is_dir => continue,
+                _ => node.name.as_str(),
+            };
+            let path = format!("{prefix}{name}");
+            if is_dir && !node.is_recursive {
+                let child_prefix = format!("{path}/");
+                stack.extend(
+                    node.children.iter().rev().map(|idx| (*idx, depth + 1, child_prefix.clone())),
+                );
+            }
+            entries.push(TreeEntry { depth, path, is_dir });
+        }
+        entries
+    }
+}
+
+/// Build the tree for `config` and flatten it, see [`DirTree::entries`]
+pub fn tree_entries(config: &TreeConfig) -> Result<Vec<TreeEntry>> {
+    let bump = Bump::new();
+    let entries = DirTree::new(config, &bump)?.entries();
+    Ok(entries)
+}
+
+/// Nest already printed trees under one node per name, used to show several repos at once
+pub fn join_trees(trees: &[(&str, String)], config: &TreePrintConfig) -> String {
+    let mut tree_builder = String::new();
+    for (idx, (name, tree)) in trees.iter().enumerate() {
+        let is_last = idx + 1 == trees.len();
+        tree_builder.push_str(if is_last { "└── " } else { "├── " });
+        match config.dir_color {
+            Some(color) => tree_builder.push_str(&name.color(color).to_string()),
+            None => tree_builder.push_str(name),
+        }
+        tree_builder.push('\n');
+        for line in tree.lines() {
+            tree_builder.push_str(if is_last { "    " } else { "│   " });
+            tree_builder.push_str(line);
+            tree_builder.push('\n');
+        }
+    }
+    tree_builder.pop();
+    tree_builder
+}
+
+/// Append trees as extra top level nodes of an already printed `tree`
+pub fn attach_trees(tree: &str, trees: &[(&str, String)], config: &TreePrintConfig) -> String {
+    if trees.is_empty() {
+        return tree.to_string();
+    }
+    let lines: Vec<&str> = tree.lines().collect();
+    let last_top = lines.iter().rposition(|line| line.starts_with("└── "));
+    let mut tree_builder = String::new();
+    for (idx, line) in lines.iter().enumerate() {
+        match last_top {
+            // The former last node gets siblings now
+            Some(last) if idx == last => {
+                tree_builder.push_str("├── ");
+                tree_builder.push_str(&line["└── ".len()..]);
+            }
+            Some(last) if idx > last && line.starts_with("    ") => {
+                tree_builder.push_str("│   ");
+                tree_builder.push_str(&line["    ".len()..]);
+            }
+            _ => tree_builder.push_str(line),
+        }
+        tree_builder.push('\n');
+    }
+    tree_builder.push_str(&join_trees(trees, config));
+    tree_builder
+}
+
 #[cfg(test)]
 mod tests {
     use bumpalo::Bump;
@@ -171,6 +257,88 @@ mod tests {
         )
     }
 
+    #[test]
+    fn entries_case() {
+        // ├── c.gpg
+        // ├── dir1
+        // │   ├── a.gpg
+        // │   └── notes.txt
+        // └── dir2
+        //     └── sub
+        //         └── b.gpg
+        let (_tmp_dir, root) = gen_unique_temp_dir();
+        let structure: &[(Option<&str>, &[&str])] = &[
+            (Some("dir1"), &["a.gpg", "notes.txt"][..]),
+            (Some("dir2/sub"), &["b.gpg"][..]),
+            (None, &["c.gpg"][..]),
+        ];
+        create_dir_structure(&root, structure);
+        let config = TreeConfig {
+            root: &root,
+            target: "",
+            filter_type: FilterType::Disable,
+            filters: Vec::new(),
+        };
+        let bump = Bump::new();
+        let tree = DirTree::new(&config, &bump).unwrap();
+        let entries = tree.entries();
+        assert_eq!(
+            entries.iter().map(|e| (e.depth, e.path.as_str(), e.is_dir)).collect::<Vec<_>>(),
+            vec![
+                (0, "c", false),
+                (0, "dir1", true),
+                (1, "dir1/a", false),
+                (0, "dir2", true),
+                (1, "dir2/sub", true),
+                (2, "dir2/sub/b", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn join_trees_case() {
+        let no_color_print = TreePrintConfig {
+            dir_color: None,
+            file_color: None,
+            symbol_color: None,
+            tree_color: None,
+        };
+        let trees = [
+            ("personal", "├── dir1\n│   └── file1\n└── file2".to_string()),
+            ("work", "└── file3".to_string()),
+        ];
+        assert_eq!(
+            join_trees(&trees, &no_color_print),
+            r#"├── personal
+│   ├── dir1
+│   │   └── file1
+│   └── file2
+└── work
+    └── file3"#
+        );
+    }
+
+    #[test]
+    fn attach_trees_case() {
+        let no_color_print = TreePrintConfig {
+            dir_color: None,
+            file_color: None,
+            symbol_color: None,
+            tree_color: None,
+        };
+        let tree = "├── file1\n└── dir1\n    └── file2";
+        let trees = [("shared", "└── file3".to_string())];
+        assert_eq!(
+            attach_trees(tree, &trees, &no_color_print),
+            r#"├── file1
+├── dir1
+│   └── file2
+└── shared
+    └── file3"#
+        );
+        assert_eq!(attach_trees("", &trees, &no_color_print), "└── shared\n    └── file3");
+    }
+
     #[test]
     fn test_filtered_case() {
         let no_color_print = TreePrintConfig {